@@ -0,0 +1,404 @@
+//! Recursive-Descent Disassembly
+//!
+//! A linear sweep decodes sprite data as if it were code. This module instead
+//! traces execution from the entry point, following jumps, subroutine calls,
+//! skips and returns, to classify every ROM byte as code or data. Jump and call
+//! targets and data referenced by `ANNN` are given labels, and the control
+//! flow can be rendered as a labelled listing or as a Graphviz DOT graph.
+//!
+//! `BNNN` computed jumps cannot be followed statically; they are recorded as
+//! unresolved edges and code reached only through them is classified as data.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::disasm::{Disassembly, Syntax, disassemble_one};
+use crate::state::{Address, Platform};
+
+/// Number of data bytes per `db` line in a listing.
+const DATA_BYTES_PER_LINE: usize = 8;
+
+/// Kind of a control-flow edge.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// Execution continues with the next instruction.
+    Fallthrough,
+    /// `1NNN` jump.
+    Jump,
+    /// `2NNN` subroutine call.
+    Call,
+    /// A skip instruction skipping over the next instruction.
+    Skip,
+    /// `BNNN` computed jump. The target is the base address `NNN`; the actual
+    /// destination depends on a register and is unresolved.
+    Computed,
+}
+
+/// A control-flow edge between two instructions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Edge {
+    pub from: Address,
+    pub to: Address,
+    pub kind: EdgeKind,
+}
+
+impl Edge {
+    /// Returns true for computed jumps, whose destination is not known.
+    pub fn is_unresolved(&self) -> bool {
+        self.kind == EdgeKind::Computed
+    }
+}
+
+/// Result of tracing a ROM from its entry point.
+pub struct Analysis {
+    rom: Vec<u8>,
+    start: Address,
+    syntax: Syntax,
+    instructions: BTreeMap<Address, Disassembly>,
+    edges: Vec<Edge>,
+    labels: BTreeMap<Address, String>,
+}
+
+/// Traces `rom`, loaded at `start`, from its first byte.
+///
+/// Instructions are decoded for `platform` and rendered in `syntax`. A path
+/// ends at a return, exit, jump, computed jump, an undecodable word, the end
+/// of the ROM, or an instruction overlapping one that was already traced.
+pub fn trace(rom: &[u8], start: Address, platform: Platform, syntax: Syntax) -> Analysis {
+    let end = start + rom.len();
+    let mut instructions: BTreeMap<Address, Disassembly> = BTreeMap::new();
+    let mut code_bytes = BTreeSet::new();
+    let mut edges = Vec::new();
+    let mut data_refs = BTreeSet::new();
+    let mut worklist = vec![start];
+
+    while let Some(addr) = worklist.pop() {
+        if addr < start || addr >= end || instructions.contains_key(&addr) {
+            continue;
+        }
+        let line = disassemble_one(&rom[addr - start..], addr, platform, syntax);
+        let span = addr..addr + line.bytes.len();
+        if line.is_data || span.clone().any(|a| code_bytes.contains(&a)) {
+            continue;
+        }
+        code_bytes.extend(span);
+
+        let raw = u16::from_be_bytes([line.bytes[0], line.bytes[1]]);
+        let next = addr + line.bytes.len();
+        for (to, kind) in successors(raw, next, rom, start, platform) {
+            edges.push(Edge {
+                from: addr,
+                to,
+                kind,
+            });
+            if kind != EdgeKind::Computed {
+                worklist.push(to);
+            }
+        }
+        if let Some(target) = data_reference(&line) {
+            data_refs.insert(target);
+        }
+        instructions.insert(addr, line);
+    }
+    edges.sort_by_key(|edge| (edge.from, edge.to));
+
+    // Label jump and call targets, and data references that start a line
+    let mut labels = BTreeMap::new();
+    for edge in &edges {
+        let prefix = match edge.kind {
+            EdgeKind::Jump => "label",
+            EdgeKind::Call => "sub",
+            _ => continue,
+        };
+        if instructions.contains_key(&edge.to) {
+            labels
+                .entry(edge.to)
+                .or_insert_with(|| format!("{}_{:03x}", prefix, edge.to));
+        }
+    }
+    for &target in &data_refs {
+        let in_rom = (start..end).contains(&target);
+        if in_rom && (instructions.contains_key(&target) || !code_bytes.contains(&target)) {
+            labels
+                .entry(target)
+                .or_insert_with(|| format!("data_{:03x}", target));
+        }
+    }
+    if instructions.contains_key(&start) {
+        labels.insert(start, "main".to_string());
+    }
+
+    Analysis {
+        rom: rom.to_vec(),
+        start,
+        syntax,
+        instructions,
+        edges,
+        labels,
+    }
+}
+
+/// Returns the control-flow successors of the instruction word `raw`, where
+/// `next` is the address of the following instruction.
+fn successors(
+    raw: u16,
+    next: Address,
+    rom: &[u8],
+    start: Address,
+    platform: Platform,
+) -> Vec<(Address, EdgeKind)> {
+    let nnn = Address::from(raw & 0xFFF);
+    match raw >> 12 {
+        0x0 if raw == 0x00EE || raw == 0x00FD => vec![],
+        0x1 => vec![(nnn, EdgeKind::Jump)],
+        0x2 => vec![(nnn, EdgeKind::Call), (next, EdgeKind::Fallthrough)],
+        0x3 | 0x4 | 0x9 | 0xE => skip_successors(next, rom, start, platform),
+        0x5 if raw & 0xF == 0 => skip_successors(next, rom, start, platform),
+        0xB => vec![(nnn, EdgeKind::Computed)],
+        _ => vec![(next, EdgeKind::Fallthrough)],
+    }
+}
+
+/// Successors of a skip instruction: the next instruction and the one after
+/// it, stepping over all 4 bytes of an XO-CHIP `F000 NNNN`.
+fn skip_successors(
+    next: Address,
+    rom: &[u8],
+    start: Address,
+    platform: Platform,
+) -> Vec<(Address, EdgeKind)> {
+    let offset = next - start;
+    let is_long =
+        platform.has_xochip_extensions() && rom.get(offset..offset + 2) == Some(&[0xF0, 0x00][..]);
+    let skipped = next + if is_long { 4 } else { 2 };
+    vec![(next, EdgeKind::Fallthrough), (skipped, EdgeKind::Skip)]
+}
+
+/// Returns the address loaded into the index register by `ANNN` or
+/// `F000 NNNN`.
+fn data_reference(line: &Disassembly) -> Option<Address> {
+    match *line.bytes.as_slice() {
+        [0xF0, 0x00, high, low] => Some(Address::from(u16::from_be_bytes([high, low]))),
+        [high, low] if high >> 4 == 0xA => {
+            Some(Address::from(u16::from_be_bytes([high, low]) & 0xFFF))
+        }
+        _ => None,
+    }
+}
+
+impl Analysis {
+    /// Returns the traced instructions keyed by address.
+    pub fn instructions(&self) -> &BTreeMap<Address, Disassembly> {
+        &self.instructions
+    }
+
+    /// Returns all control-flow edges, ordered by source address.
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// Returns the generated labels keyed by address.
+    pub fn labels(&self) -> &BTreeMap<Address, String> {
+        &self.labels
+    }
+
+    /// Returns true if the byte at `addr` belongs to a traced instruction.
+    pub fn is_code(&self, addr: Address) -> bool {
+        self.instructions
+            .range(..=addr)
+            .next_back()
+            .is_some_and(|(&start, line)| addr < start + line.bytes.len())
+    }
+
+    /// Renders the ROM as a labelled assembly listing.
+    ///
+    /// Every byte appears exactly once, either in an instruction or in a `db`
+    /// data line. Jump, call and index operands that point at a label use the
+    /// label name, and computed jumps are marked with a comment.
+    pub fn listing(&self) -> String {
+        let comment = match self.syntax {
+            Syntax::Octo => "#",
+            Syntax::Cowgod => ";",
+        };
+        let end = self.start + self.rom.len();
+        let mut out = String::new();
+        let mut addr = self.start;
+
+        while addr < end {
+            if let Some(label) = self.labels.get(&addr) {
+                match self.syntax {
+                    Syntax::Octo => writeln!(out, ": {}", label).unwrap(),
+                    Syntax::Cowgod => writeln!(out, "{}:", label).unwrap(),
+                }
+            }
+
+            if let Some(line) = self.instructions.get(&addr) {
+                let mut text = self.with_label(line);
+                if line.bytes[0] >> 4 == 0xB {
+                    write!(text, "  {} computed jump, unresolved", comment).unwrap();
+                }
+                writeln!(out, "\t{}", text).unwrap();
+                addr += line.bytes.len();
+                continue;
+            }
+
+            // Gather data up to the next instruction, label or line length
+            let mut data_end = addr + 1;
+            while data_end < end
+                && data_end - addr < DATA_BYTES_PER_LINE
+                && !self.instructions.contains_key(&data_end)
+                && !self.labels.contains_key(&data_end)
+            {
+                data_end += 1;
+            }
+            let values: Vec<String> = self.rom[addr - self.start..data_end - self.start]
+                .iter()
+                .map(|b| format!("{:#04x}", b))
+                .collect();
+            writeln!(out, "\tdb {}", values.join(", ")).unwrap();
+            addr = data_end;
+        }
+        out
+    }
+
+    /// Returns the instruction text with a jump, call or index target
+    /// replaced by its label.
+    fn with_label(&self, line: &Disassembly) -> String {
+        let (target, formatted) = match *line.bytes.as_slice() {
+            [0xF0, 0x00, high, low] => {
+                let target = u16::from_be_bytes([high, low]);
+                (Address::from(target), format!("{:#06x}", target))
+            }
+            [high, low] if matches!(high >> 4, 0x1 | 0x2 | 0xA | 0xB) => {
+                let target = u16::from_be_bytes([high, low]) & 0xFFF;
+                (Address::from(target), format!("{:#05x}", target))
+            }
+            _ => return line.text.clone(),
+        };
+        match (self.labels.get(&target), line.text.strip_suffix(&formatted)) {
+            (Some(label), Some(prefix)) => format!("{}{}", prefix, label),
+            _ => line.text.clone(),
+        }
+    }
+
+    /// Renders the control-flow graph of basic blocks in Graphviz DOT format.
+    ///
+    /// Calls are drawn dashed, skips are labelled, and each computed jump
+    /// points at a red dashed "unresolved" node.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "digraph cfg {{").unwrap();
+        writeln!(out, "    node [shape=box, fontname=\"monospace\"];").unwrap();
+
+        for (leader, block) in self.blocks() {
+            let mut label = String::new();
+            if let Some(name) = self.labels.get(&leader) {
+                write!(label, "{}:\\l", name).unwrap();
+            }
+            for line in &block {
+                write!(
+                    label,
+                    "{:04X}  {}\\l",
+                    line.address,
+                    escape(&self.with_label(line))
+                )
+                .unwrap();
+            }
+            writeln!(out, "    \"{:#06x}\" [label=\"{}\"];", leader, label).unwrap();
+
+            let last = block.last().map(|line| line.address);
+            for edge in self.edges.iter().filter(|edge| {
+                block.iter().any(|line| line.address == edge.from)
+                    && (edge.kind == EdgeKind::Call || Some(edge.from) == last)
+            }) {
+                match edge.kind {
+                    EdgeKind::Computed => {
+                        writeln!(
+                            out,
+                            "    \"unresolved_{:#06x}\" [shape=diamond, style=dashed, color=red, label=\"{:#05x} + v0\"];",
+                            edge.from, edge.to
+                        )
+                        .unwrap();
+                        writeln!(
+                            out,
+                            "    \"{:#06x}\" -> \"unresolved_{:#06x}\" [style=dashed, color=red, label=\"computed\"];",
+                            leader, edge.from
+                        )
+                        .unwrap();
+                    }
+                    kind if self.instructions.contains_key(&edge.to) => {
+                        let attributes = match kind {
+                            EdgeKind::Call => " [style=dashed, label=\"call\"]",
+                            EdgeKind::Skip => " [label=\"skip\"]",
+                            EdgeKind::Jump => " [label=\"jump\"]",
+                            _ => "",
+                        };
+                        writeln!(
+                            out,
+                            "    \"{:#06x}\" -> \"{:#06x}\"{};",
+                            leader, edge.to, attributes
+                        )
+                        .unwrap();
+                    }
+                    _ => {}
+                }
+            }
+        }
+        writeln!(out, "}}").unwrap();
+        out
+    }
+
+    /// Splits the traced instructions into basic blocks keyed by their first
+    /// instruction.
+    ///
+    /// Blocks start at the entry point, at jump, call and skip targets and
+    /// after skip instructions, and end at any instruction that does not simply
+    /// fall through to the next one.
+    fn blocks(&self) -> BTreeMap<Address, Vec<&Disassembly>> {
+        let mut leaders: BTreeSet<Address> = BTreeSet::from([self.start]);
+        let mut terminators = BTreeSet::new();
+        for edge in &self.edges {
+            match edge.kind {
+                EdgeKind::Fallthrough => {}
+                EdgeKind::Skip | EdgeKind::Jump | EdgeKind::Computed => {
+                    leaders.insert(edge.to);
+                    terminators.insert(edge.from);
+                }
+                EdgeKind::Call => {
+                    leaders.insert(edge.to);
+                }
+            }
+        }
+        // Instructions after a skip start a block of their own
+        for edge in &self.edges {
+            if edge.kind == EdgeKind::Fallthrough && terminators.contains(&edge.from) {
+                leaders.insert(edge.to);
+            }
+        }
+
+        let mut blocks = BTreeMap::new();
+        for &leader in leaders.iter().filter(|a| self.instructions.contains_key(a)) {
+            let mut block = Vec::new();
+            let mut addr = leader;
+            while let Some(line) = self.instructions.get(&addr) {
+                block.push(line);
+                let falls_through = self
+                    .edges
+                    .iter()
+                    .any(|edge| edge.from == addr && edge.kind == EdgeKind::Fallthrough);
+                addr += line.bytes.len();
+                if !falls_through || terminators.contains(&line.address) || leaders.contains(&addr)
+                {
+                    break;
+                }
+            }
+            blocks.insert(leader, block);
+        }
+        blocks
+    }
+}
+
+/// Escapes a string for use inside a quoted DOT label.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,664 @@
+//! CHIP-8 Assembler
+//!
+//! Assembles source written in the Octo or Cowgod syntax emitted by the
+//! disassembler into a ROM image that [`Memory::load_rom`] can load. Both
+//! syntaxes can be mixed freely in one file.
+//!
+//! # Source Format
+//! - Comments start with `#` or `;` and run to the end of the line.
+//! - Labels are written `: name` (Octo) or `name:` (Cowgod), and can be used
+//!   wherever an address or number is expected.
+//! - Constants are defined with `:const NAME VALUE` or `NAME equ VALUE`.
+//! - Numbers are decimal, `0x` hexadecimal or `0b` binary.
+//! - `db` emits bytes and `dw` emits big-endian 16-bit words. Operands may be
+//!   separated by commas or spaces.
+//! - Sprite rows can be written as quoted literals in `db`, one character per
+//!   pixel: `.` or `0` is off, `#`, `X` or `1` is on. An 8-character row is one
+//!   byte and a 16-character row two bytes, e.g. `db "..####..", "#......#"`.
+//! - `include "other.asm"` splices in another file, resolved relative to the
+//!   including file.
+//!
+//! [`Memory::load_rom`]: crate::state::Memory::load_rom
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::state::{Address, Platform};
+
+/// Maximum depth of nested `include` directives.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// An assembly error with the source location it was found at.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AsmError {
+    /// File the error was found in, or `None` for source passed as a string.
+    pub file: Option<PathBuf>,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column number.
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}:", file.display())?,
+            None => write!(f, "line ")?,
+        }
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// Assembles `source`, placing the first byte at `start` in the memory of
+/// `platform`.
+///
+/// `include` paths are resolved relative to the current directory.
+pub fn assemble(source: &str, start: Address, platform: Platform) -> Result<Vec<u8>, AsmError> {
+    let mut assembler = Assembler::default();
+    assembler.load(source, None, 0)?;
+    assembler.assemble(start, platform)
+}
+
+/// Assembles the file at `path`, placing the first byte at `start` in the
+/// memory of `platform`.
+pub fn assemble_file(path: &Path, start: Address, platform: Platform) -> Result<Vec<u8>, AsmError> {
+    let mut assembler = Assembler::default();
+    assembler.load_file(path, None, 0)?;
+    assembler.assemble(start, platform)
+}
+
+/// Source position of a token.
+#[derive(Clone, Debug)]
+struct Location {
+    file: Option<PathBuf>,
+    line: usize,
+    column: usize,
+}
+
+impl Location {
+    /// Creates an error at this location.
+    fn error(&self, message: impl Into<String>) -> AsmError {
+        AsmError {
+            file: self.file.clone(),
+            line: self.line,
+            column: self.column,
+            message: message.into(),
+        }
+    }
+}
+
+/// A single token with its position.
+#[derive(Clone, Debug)]
+struct Token {
+    text: String,
+    location: Location,
+}
+
+impl Token {
+    /// Returns true if the token is the keyword `keyword`, ignoring case.
+    fn is(&self, keyword: &str) -> bool {
+        self.text.eq_ignore_ascii_case(keyword)
+    }
+}
+
+/// A parsed source statement.
+enum Statement {
+    Label(Token),
+    Const(Token, Token),
+    Code(Vec<Token>),
+}
+
+/// Two-pass assembler state.
+#[derive(Default)]
+struct Assembler {
+    statements: Vec<Statement>,
+    symbols: HashMap<String, u32>,
+}
+
+impl Assembler {
+    /// Reads and loads a source file.
+    fn load_file(
+        &mut self,
+        path: &Path,
+        included_from: Option<&Location>,
+        depth: usize,
+    ) -> Result<(), AsmError> {
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            let message = format!("Cannot read {}: {}", path.display(), e);
+            match included_from {
+                Some(location) => location.error(message),
+                None => AsmError {
+                    file: Some(path.to_path_buf()),
+                    line: 0,
+                    column: 0,
+                    message,
+                },
+            }
+        })?;
+        self.load(&source, Some(path), depth)
+    }
+
+    /// Splits source into statements, expanding includes.
+    fn load(&mut self, source: &str, file: Option<&Path>, depth: usize) -> Result<(), AsmError> {
+        for (line_idx, line) in source.lines().enumerate() {
+            let mut tokens = tokenize(line, file, line_idx + 1)?;
+
+            // Label definitions: `: name` or `name:`, optionally followed by code
+            loop {
+                if tokens.len() >= 2 && tokens[0].text == ":" {
+                    let label = tokens.remove(1);
+                    tokens.remove(0);
+                    self.statements.push(Statement::Label(label));
+                } else if let Some(name) = tokens
+                    .first()
+                    .and_then(|t| t.text.strip_suffix(':'))
+                    .filter(|name| is_symbol(name))
+                    .map(str::to_string)
+                {
+                    let mut label = tokens.remove(0);
+                    label.text = name;
+                    self.statements.push(Statement::Label(label));
+                } else {
+                    break;
+                }
+            }
+
+            match tokens.as_slice() {
+                [] => {}
+                [keyword, name, value] if keyword.is(":const") => {
+                    self.statements
+                        .push(Statement::Const(name.clone(), value.clone()));
+                }
+                [name, keyword, value] if keyword.is("equ") => {
+                    self.statements
+                        .push(Statement::Const(name.clone(), value.clone()));
+                }
+                [keyword, path] if keyword.is("include") => {
+                    if depth >= MAX_INCLUDE_DEPTH {
+                        return Err(keyword.location.error("Includes nested too deeply"));
+                    }
+                    let name = path
+                        .text
+                        .strip_prefix('"')
+                        .and_then(|p| p.strip_suffix('"'))
+                        .ok_or_else(|| path.location.error("Expected a quoted file name"))?;
+                    let base = file.and_then(Path::parent).unwrap_or(Path::new(""));
+                    self.load_file(&base.join(name), Some(&path.location), depth + 1)?;
+                }
+                _ => self.statements.push(Statement::Code(tokens)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolves labels and encodes every statement, checking that the program
+    /// fits in the memory of `platform`.
+    fn assemble(&mut self, start: Address, platform: Platform) -> Result<Vec<u8>, AsmError> {
+        let statements = std::mem::take(&mut self.statements);
+
+        // Pass 1: assign addresses to labels and evaluate constants
+        let mut addr = start as u32;
+        for statement in &statements {
+            match statement {
+                Statement::Label(name) => self.define(name, addr)?,
+                Statement::Const(name, value) => {
+                    let value = self.value(value, true)?;
+                    self.define(name, value)?;
+                }
+                Statement::Code(tokens) => addr += self.encode(tokens, false)?.len() as u32,
+            }
+        }
+
+        // Pass 2: encode with every symbol known
+        let mut rom = Vec::new();
+        for statement in &statements {
+            if let Statement::Code(tokens) = statement {
+                rom.extend(self.encode(tokens, true)?);
+            }
+        }
+        if start + rom.len() > platform.memory_size() {
+            return Err(AsmError {
+                file: None,
+                line: 0,
+                column: 0,
+                message: format!(
+                    "Program of {} bytes from {:#05x} does not fit in {} bytes of memory",
+                    rom.len(),
+                    start,
+                    platform.memory_size()
+                ),
+            });
+        }
+        Ok(rom)
+    }
+
+    /// Defines a label or constant.
+    fn define(&mut self, name: &Token, value: u32) -> Result<(), AsmError> {
+        if !is_symbol(&name.text) {
+            return Err(name.location.error(format!("Invalid name '{}'", name.text)));
+        }
+        if self.symbols.insert(name.text.clone(), value).is_some() {
+            return Err(name
+                .location
+                .error(format!("'{}' is already defined", name.text)));
+        }
+        Ok(())
+    }
+
+    /// Evaluates a number or symbol. Unknown symbols are an error when
+    /// `strict`, and evaluate to 0 otherwise.
+    fn value(&self, token: &Token, strict: bool) -> Result<u32, AsmError> {
+        if let Some(value) = parse_number(&token.text) {
+            return Ok(value);
+        }
+        match self.symbols.get(&token.text) {
+            Some(&value) => Ok(value),
+            None if !strict && is_symbol(&token.text) => Ok(0),
+            None => Err(token
+                .location
+                .error(format!("Unknown symbol or invalid number '{}'", token.text))),
+        }
+    }
+
+    /// Evaluates an operand that must fit in `bits` bits.
+    fn operand(&self, token: &Token, bits: u32, strict: bool) -> Result<u16, AsmError> {
+        let value = self.value(token, strict)?;
+        if value >= 1 << bits {
+            return Err(token
+                .location
+                .error(format!("Value {:#x} does not fit in {} bits", value, bits)));
+        }
+        Ok(value as u16)
+    }
+
+    /// Encodes one instruction or data directive.
+    fn encode(&self, tokens: &[Token], strict: bool) -> Result<Vec<u8>, AsmError> {
+        let first = &tokens[0];
+        if first.is("db") || first.is("dw") {
+            return self.encode_data(tokens, strict);
+        }
+        if first.is("i") && tokens.len() == 4 && tokens[1].is(":=") && tokens[2].is("long")
+            || first.is("ld") && tokens.len() == 4 && tokens[1].is("i") && tokens[2].is("long")
+        {
+            let target = self.operand(&tokens[3], 16, strict)?;
+            return Ok([[0xF0, 0x00], target.to_be_bytes()].concat());
+        }
+        let word = self.encode_instruction(tokens, strict)?;
+        Ok(word.to_be_bytes().to_vec())
+    }
+
+    /// Encodes a `db` or `dw` directive.
+    fn encode_data(&self, tokens: &[Token], strict: bool) -> Result<Vec<u8>, AsmError> {
+        let words = tokens[0].is("dw");
+        let mut bytes = Vec::new();
+        for token in &tokens[1..] {
+            if let Some(row) = token
+                .text
+                .strip_prefix('"')
+                .and_then(|t| t.strip_suffix('"'))
+            {
+                bytes.extend(sprite_row(row).ok_or_else(|| {
+                    token
+                        .location
+                        .error("Sprite rows must be 8 or 16 pixels of '.', '0', '#', 'X' or '1'")
+                })?);
+            } else if words {
+                bytes.extend(self.operand(token, 16, strict)?.to_be_bytes());
+            } else {
+                bytes.push(self.operand(token, 8, strict)? as u8);
+            }
+        }
+        if bytes.is_empty() {
+            return Err(tokens[0].location.error("Expected data"));
+        }
+        Ok(bytes)
+    }
+
+    /// Encodes a 2-byte instruction in either syntax.
+    fn encode_instruction(&self, tokens: &[Token], strict: bool) -> Result<u16, AsmError> {
+        let lower: Vec<String> = tokens.iter().map(|t| t.text.to_ascii_lowercase()).collect();
+        let words: Vec<&str> = lower.iter().map(String::as_str).collect();
+        let reg = |idx: usize| register(words[idx]).ok_or(idx);
+        let nn = |idx: usize| self.operand(&tokens[idx], 8, strict);
+        let n = |idx: usize| self.operand(&tokens[idx], 4, strict);
+        let nnn = |idx: usize| self.operand(&tokens[idx], 12, strict);
+        let xy = |x: u16, y: u16| (x << 8) | (y << 4);
+        let is_reg = |idx: usize| register(words[idx]).is_some();
+
+        let word = match words.as_slice() {
+            // Instructions without operands
+            ["clear"] | ["cls"] => 0x00E0,
+            ["return"] | ["ret"] => 0x00EE,
+            ["scroll-right"] | ["scr"] => 0x00FB,
+            ["scroll-left"] | ["scl"] => 0x00FC,
+            ["exit"] => 0x00FD,
+            ["lores"] | ["low"] => 0x00FE,
+            ["hires"] | ["high"] => 0x00FF,
+            ["audio"] => 0xF002,
+            ["scroll-down" | "scd", _] => 0x00C0 | n(1)?,
+            ["scroll-up" | "scu", _] => 0x00D0 | n(1)?,
+            ["sys", _] => nnn(1)?,
+
+            // Control flow
+            ["jump" | "jp", _] if !is_reg(1) => 0x1000 | nnn(1)?,
+            [":call" | "call", _] => 0x2000 | nnn(1)?,
+            ["jump0", _] => 0xB000 | nnn(1)?,
+            ["jp", "v0", _] => 0xB000 | nnn(2)?,
+
+            // Octo conditionals, which skip the next instruction when false
+            ["if", _, "!=" | "==", _, "then"] => {
+                let x = reg(1).map_err(|i| self.expected_register(tokens, i))?;
+                let equal = words[2] == "==";
+                match (register(words[3]), equal) {
+                    (Some(y), false) => 0x5000 | xy(x, y),
+                    (Some(y), true) => 0x9000 | xy(x, y),
+                    (None, false) => 0x3000 | (x << 8) | nn(3)?,
+                    (None, true) => 0x4000 | (x << 8) | nn(3)?,
+                }
+            }
+            ["if", _, "-key" | "key", "then"] => {
+                let x = reg(1).map_err(|i| self.expected_register(tokens, i))?;
+                if words[2] == "-key" {
+                    0xE09E | (x << 8)
+                } else {
+                    0xE0A1 | (x << 8)
+                }
+            }
+
+            // Cowgod skips
+            ["se" | "sne", _, _] => {
+                let x = reg(1).map_err(|i| self.expected_register(tokens, i))?;
+                let equal = words[0] == "se";
+                match (register(words[2]), equal) {
+                    (Some(y), true) => 0x5000 | xy(x, y),
+                    (Some(y), false) => 0x9000 | xy(x, y),
+                    (None, true) => 0x3000 | (x << 8) | nn(2)?,
+                    (None, false) => 0x4000 | (x << 8) | nn(2)?,
+                }
+            }
+            ["skp" | "sknp", _] => {
+                let x = reg(1).map_err(|i| self.expected_register(tokens, i))?;
+                if words[0] == "skp" {
+                    0xE09E | (x << 8)
+                } else {
+                    0xE0A1 | (x << 8)
+                }
+            }
+
+            // Register ranges and memory
+            ["save" | "load", _, "-", _] | ["save" | "load", _, _]
+                if words.len() == 4 || is_reg(2) =>
+            {
+                let x = reg(1).map_err(|i| self.expected_register(tokens, i))?;
+                let y = reg(words.len() - 1).map_err(|i| self.expected_register(tokens, i))?;
+                let op = if words[0] == "save" { 0x2 } else { 0x3 };
+                0x5000 | xy(x, y) | op
+            }
+            ["save" | "load" | "saveflags" | "loadflags" | "bcd", _] => {
+                let x = reg(1).map_err(|i| self.expected_register(tokens, i))?;
+                let op = match words[0] {
+                    "save" => 0x55,
+                    "load" => 0x65,
+                    "saveflags" => 0x75,
+                    "loadflags" => 0x85,
+                    _ => 0x33,
+                };
+                0xF000 | (x << 8) | op
+            }
+
+            // Drawing
+            ["sprite" | "drw", _, _, _] => {
+                let x = reg(1).map_err(|i| self.expected_register(tokens, i))?;
+                let y = reg(2).map_err(|i| self.expected_register(tokens, i))?;
+                0xD000 | xy(x, y) | n(3)?
+            }
+            ["plane", _] => 0xF001 | (n(1)? << 8),
+
+            // Octo assignments to the index register and timers
+            ["i", ":=", "hex" | "bighex", _] => {
+                let x = reg(3).map_err(|i| self.expected_register(tokens, i))?;
+                let op = if words[2] == "hex" { 0x29 } else { 0x30 };
+                0xF000 | (x << 8) | op
+            }
+            ["i", ":=", _] => 0xA000 | nnn(2)?,
+            ["i", "+=", _] => {
+                let x = reg(2).map_err(|i| self.expected_register(tokens, i))?;
+                0xF01E | (x << 8)
+            }
+            ["delay" | "buzzer" | "pitch", ":=", _] => {
+                let x = reg(2).map_err(|i| self.expected_register(tokens, i))?;
+                let op = match words[0] {
+                    "delay" => 0x15,
+                    "buzzer" => 0x18,
+                    _ => 0x3A,
+                };
+                0xF000 | (x << 8) | op
+            }
+
+            // Octo register assignments
+            [_, ":=", "random", _] => {
+                let x = reg(0).map_err(|i| self.expected_register(tokens, i))?;
+                0xC000 | (x << 8) | nn(3)?
+            }
+            [_, ":=", "delay"] | [_, ":=", "key"] => {
+                let x = reg(0).map_err(|i| self.expected_register(tokens, i))?;
+                let op = if words[2] == "delay" { 0x07 } else { 0x0A };
+                0xF000 | (x << 8) | op
+            }
+            [
+                _,
+                ":=" | "+=" | "-=" | "=-" | "|=" | "&=" | "^=" | ">>=" | "<<=",
+                _,
+            ] => {
+                let x = reg(0).map_err(|i| self.expected_register(tokens, i))?;
+                match (words[1], register(words[2])) {
+                    (":=", Some(y)) => 0x8000 | xy(x, y),
+                    ("|=", Some(y)) => 0x8001 | xy(x, y),
+                    ("&=", Some(y)) => 0x8002 | xy(x, y),
+                    ("^=", Some(y)) => 0x8003 | xy(x, y),
+                    ("+=", Some(y)) => 0x8004 | xy(x, y),
+                    ("-=", Some(y)) => 0x8005 | xy(x, y),
+                    (">>=", Some(y)) => 0x8006 | xy(x, y),
+                    ("=-", Some(y)) => 0x8007 | xy(x, y),
+                    ("<<=", Some(y)) => 0x800E | xy(x, y),
+                    (":=", None) => 0x6000 | (x << 8) | nn(2)?,
+                    ("+=", None) => 0x7000 | (x << 8) | nn(2)?,
+                    _ => return Err(self.expected_register(tokens, 2)),
+                }
+            }
+
+            // Cowgod loads
+            ["ld", "i", _] => 0xA000 | nnn(2)?,
+            ["ld", "dt" | "st" | "f" | "hf" | "b" | "[i]" | "r", _] => {
+                let x = reg(2).map_err(|i| self.expected_register(tokens, i))?;
+                let op = match words[1] {
+                    "dt" => 0x15,
+                    "st" => 0x18,
+                    "f" => 0x29,
+                    "hf" => 0x30,
+                    "b" => 0x33,
+                    "[i]" => 0x55,
+                    _ => 0x75,
+                };
+                0xF000 | (x << 8) | op
+            }
+            ["ld", _, "dt" | "k" | "[i]" | "r"] => {
+                let x = reg(1).map_err(|i| self.expected_register(tokens, i))?;
+                let op = match words[2] {
+                    "dt" => 0x07,
+                    "k" => 0x0A,
+                    "[i]" => 0x65,
+                    _ => 0x85,
+                };
+                0xF000 | (x << 8) | op
+            }
+            ["ld" | "add", _, _] => {
+                if words[0] == "add" && words[1] == "i" {
+                    let x = reg(2).map_err(|i| self.expected_register(tokens, i))?;
+                    0xF01E | (x << 8)
+                } else {
+                    let x = reg(1).map_err(|i| self.expected_register(tokens, i))?;
+                    match (words[0], register(words[2])) {
+                        ("ld", Some(y)) => 0x8000 | xy(x, y),
+                        ("add", Some(y)) => 0x8004 | xy(x, y),
+                        ("ld", None) => 0x6000 | (x << 8) | nn(2)?,
+                        _ => 0x7000 | (x << 8) | nn(2)?,
+                    }
+                }
+            }
+            ["or" | "and" | "xor" | "sub" | "shr" | "subn" | "shl", _, ..] if words.len() <= 3 => {
+                let x = reg(1).map_err(|i| self.expected_register(tokens, i))?;
+                let y = match words.len() {
+                    3 => reg(2).map_err(|i| self.expected_register(tokens, i))?,
+                    _ => x,
+                };
+                let op = match words[0] {
+                    "or" => 0x1,
+                    "and" => 0x2,
+                    "xor" => 0x3,
+                    "sub" => 0x5,
+                    "shr" => 0x6,
+                    "subn" => 0x7,
+                    _ => 0xE,
+                };
+                0x8000 | xy(x, y) | op
+            }
+            ["rnd", _, _] => {
+                let x = reg(1).map_err(|i| self.expected_register(tokens, i))?;
+                0xC000 | (x << 8) | nn(2)?
+            }
+            ["pitch", _] => {
+                let x = reg(1).map_err(|i| self.expected_register(tokens, i))?;
+                0xF03A | (x << 8)
+            }
+
+            // Octo calls a subroutine by naming it
+            [_] if is_symbol(&tokens[0].text) => 0x2000 | nnn(0)?,
+
+            _ => {
+                return Err(tokens[0]
+                    .location
+                    .error(format!("Unknown instruction '{}'", join(tokens))));
+            }
+        };
+        Ok(word)
+    }
+
+    /// Creates an "expected register" error for the token at `idx`.
+    fn expected_register(&self, tokens: &[Token], idx: usize) -> AsmError {
+        tokens[idx].location.error(format!(
+            "Expected a register V0-VF, found '{}'",
+            tokens[idx].text
+        ))
+    }
+}
+
+/// Splits a line into tokens, dropping comments. Tokens are separated by
+/// whitespace or commas; quoted strings form a single token.
+fn tokenize(line: &str, file: Option<&Path>, line_number: usize) -> Result<Vec<Token>, AsmError> {
+    let location = |column: usize| Location {
+        file: file.map(Path::to_path_buf),
+        line: line_number,
+        column: column + 1,
+    };
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        let c = chars[idx];
+        if c == '#' || c == ';' {
+            break;
+        }
+        if c.is_whitespace() || c == ',' {
+            idx += 1;
+            continue;
+        }
+
+        let start = idx;
+        if c == '"' {
+            idx += 1;
+            while idx < chars.len() && chars[idx] != '"' {
+                idx += 1;
+            }
+            if idx == chars.len() {
+                return Err(location(start).error("Unterminated string"));
+            }
+            idx += 1;
+        } else {
+            while idx < chars.len()
+                && !chars[idx].is_whitespace()
+                && !matches!(chars[idx], ',' | ';' | '"')
+            {
+                idx += 1;
+            }
+        }
+        tokens.push(Token {
+            text: chars[start..idx].iter().collect(),
+            location: location(start),
+        });
+    }
+    Ok(tokens)
+}
+
+/// Parses a decimal, `0x` hexadecimal or `0b` binary number.
+fn parse_number(text: &str) -> Option<u32> {
+    let lower = text.to_ascii_lowercase();
+    if let Some(hex) = lower.strip_prefix("0x") {
+        u32::from_str_radix(hex, 16).ok()
+    } else if let Some(binary) = lower.strip_prefix("0b") {
+        u32::from_str_radix(binary, 2).ok()
+    } else {
+        lower.parse().ok()
+    }
+}
+
+/// Parses a register name `v0`-`vf` (already lowercased).
+fn register(text: &str) -> Option<u16> {
+    let digit = text.strip_prefix('v')?;
+    if digit.len() != 1 {
+        return None;
+    }
+    u16::from_str_radix(digit, 16).ok()
+}
+
+/// Returns true if `text` can name a label or constant.
+fn is_symbol(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        && register(&text.to_ascii_lowercase()).is_none()
+}
+
+/// Converts a sprite row literal into one or two bytes.
+fn sprite_row(row: &str) -> Option<Vec<u8>> {
+    if row.len() != 8 && row.len() != 16 {
+        return None;
+    }
+    let mut bits = 0u16;
+    for c in row.chars() {
+        let bit = match c {
+            '.' | '0' => 0,
+            '#' | 'X' | 'x' | '1' => 1,
+            _ => return None,
+        };
+        bits = (bits << 1) | bit;
+    }
+    Some(if row.len() == 8 {
+        vec![bits as u8]
+    } else {
+        bits.to_be_bytes().to_vec()
+    })
+}
+
+/// Joins tokens back into text for error messages.
+fn join(tokens: &[Token]) -> String {
+    let words: Vec<&str> = tokens.iter().map(|t| t.text.as_str()).collect();
+    words.join(" ")
+}
@@ -0,0 +1,382 @@
+//! Gameplay Capture
+//!
+//! A [`Recorder`] appends every frame of the emulation loop to a video file,
+//! taken from the emulated display at its native resolution, scaled by an
+//! integer factor, in the theme's palette:
+//!
+//! - **GIF**: an animated GIF that loops forever. Runs of identical frames are
+//!   merged into one longer frame, so idle screens cost almost nothing.
+//! - **Y4M**: an uncompressed YUV4MPEG2 stream at the emulator's frame rate,
+//!   which `ffmpeg` and most video tools read directly.
+//!
+//! Platforms with a high resolution mode are captured at 128×64, with low
+//! resolution frames doubled, so the video size never changes mid-stream.
+//!
+//! Optionally, the sound is written to a WAV file next to the video: the beep
+//! while the sound timer is non-zero, or the XO-CHIP audio pattern at the
+//! programmed pitch, matching the live audio.
+
+use std::f32::consts::TAU;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, bail};
+use ratatui::style::Color;
+
+use crate::emulator::FrontendOptions;
+use crate::state::{
+    AUDIO_PATTERN_SIZE, Chip8State, DISPLAY_HEIGHT, DISPLAY_WIDTH, HIRES_DISPLAY_HEIGHT,
+    HIRES_DISPLAY_WIDTH, Settings,
+};
+use crate::theme::palette_rgb;
+
+/// Default scale of captured videos.
+pub const DEFAULT_CAPTURE_SCALE: usize = 4;
+
+/// Sample rate of the WAV sidecar in Hz.
+const WAV_SAMPLE_RATE: u32 = 44100;
+
+/// Amplitude of the XO-CHIP audio pattern, as played live.
+const PATTERN_AMPLITUDE: f32 = 0.25;
+
+/// Video format of captures.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum CaptureFormat {
+    /// Animated GIF in the palette colours.
+    #[default]
+    Gif,
+
+    /// Uncompressed YUV4MPEG2 video.
+    Y4m,
+}
+
+impl CaptureFormat {
+    /// The format for a file name extension, `.gif` or `.y4m`.
+    pub fn from_path(path: &Path) -> anyhow::Result<Self> {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("gif") => Ok(CaptureFormat::Gif),
+            Some("y4m") => Ok(CaptureFormat::Y4m),
+            _ => bail!(
+                "Cannot tell the capture format of {}, use a .gif or .y4m file",
+                path.display()
+            ),
+        }
+    }
+
+    /// File name extension of the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            CaptureFormat::Gif => "gif",
+            CaptureFormat::Y4m => "y4m",
+        }
+    }
+}
+
+/// Records the frames, and optionally the sound, of a running machine.
+pub struct Recorder {
+    path: PathBuf,
+    video: Video,
+    audio: Option<Wav>,
+    frame_rate: u64,
+    /// Canvas size in display pixels and image pixels per display pixel.
+    width: usize,
+    height: usize,
+    scale: usize,
+    palette: [(u8, u8, u8); 4],
+    /// Frames added so far.
+    frames: u64,
+}
+
+/// Video stream being written.
+enum Video {
+    /// GIF encoder, with the frame not yet written since following identical
+    /// frames extend it, and the index of its first frame.
+    Gif {
+        encoder: gif::Encoder<BufWriter<File>>,
+        pending: Option<(Vec<u8>, u64)>,
+    },
+    Y4m(BufWriter<File>),
+}
+
+/// WAV file being written, with the state of the tone generator.
+struct Wav {
+    out: BufWriter<File>,
+    samples: u32,
+    /// Position within a sine period, or within the XO-CHIP pattern in bits.
+    phase: f32,
+    /// Beep frequency in Hz and output volume from 0 to 1.
+    frequency: f32,
+    volume: f32,
+}
+
+impl Recorder {
+    /// Starts recording the machine running with `settings` to `path`, in the
+    /// format given by its extension, at `scale`. With `audio`, the sound is
+    /// written to the same path with a `.wav` extension, at the beep frequency
+    /// and volume of `options`.
+    pub fn start(
+        path: &Path,
+        settings: &Settings,
+        options: &FrontendOptions,
+        palette: &[Color; 4],
+        scale: usize,
+        audio: bool,
+    ) -> anyhow::Result<Self> {
+        let format = CaptureFormat::from_path(path)?;
+        let (width, height) = if settings.platform.has_schip_extensions() {
+            (HIRES_DISPLAY_WIDTH, HIRES_DISPLAY_HEIGHT)
+        } else {
+            (DISPLAY_WIDTH, DISPLAY_HEIGHT)
+        };
+        let scale = scale.max(1);
+        let palette = palette_rgb(palette);
+        let (image_width, image_height) = (width * scale, height * scale);
+        // GIF stores the image size in 16 bits
+        let gif_size = u16::try_from(image_width)
+            .ok()
+            .zip(u16::try_from(image_height).ok());
+        if matches!(format, CaptureFormat::Gif) && gif_size.is_none() {
+            bail!(
+                "A capture scale of {} makes {}×{} frames, larger than a GIF can hold",
+                scale,
+                image_width,
+                image_height
+            );
+        }
+
+        let create = |path: &Path| {
+            File::create(path)
+                .map(BufWriter::new)
+                .with_context(|| format!("Cannot create {}", path.display()))
+        };
+        let mut out = create(path)?;
+        let video = match format {
+            CaptureFormat::Gif => {
+                let global_palette: Vec<u8> =
+                    palette.iter().flat_map(|&(r, g, b)| [r, g, b]).collect();
+                let (gif_width, gif_height) = gif_size.unwrap_or_default();
+                let mut encoder = gif::Encoder::new(out, gif_width, gif_height, &global_palette)?;
+                encoder.set_repeat(gif::Repeat::Infinite)?;
+                Video::Gif {
+                    encoder,
+                    pending: None,
+                }
+            }
+            CaptureFormat::Y4m => {
+                writeln!(
+                    out,
+                    "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444",
+                    image_width, image_height, settings.frame_rate
+                )?;
+                Video::Y4m(out)
+            }
+        };
+        let audio = if audio {
+            let mut out = create(&path.with_extension("wav"))?;
+            write_wav_header(&mut out, 0)?;
+            Some(Wav {
+                out,
+                samples: 0,
+                phase: 0.0,
+                frequency: options.beep_frequency,
+                volume: options.volume.clamp(0.0, 1.0),
+            })
+        } else {
+            None
+        };
+
+        Ok(Recorder {
+            path: path.to_path_buf(),
+            video,
+            audio,
+            frame_rate: settings.frame_rate.max(1),
+            width,
+            height,
+            scale,
+            palette,
+            frames: 0,
+        })
+    }
+
+    /// Path of the video file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends the current frame of `state`, and the sound played during it.
+    pub fn add_frame(&mut self, state: &Chip8State) -> anyhow::Result<()> {
+        let pixels = self.canvas(state);
+        match &mut self.video {
+            Video::Gif { encoder, pending } => {
+                // Identical frames extend the pending one
+                if pending.as_ref().is_none_or(|(frame, _)| *frame != pixels) {
+                    if let Some((frame, start)) = pending.take() {
+                        let delay = gif_delay(start, self.frames, self.frame_rate);
+                        write_gif_frame(encoder, &frame, self.width * self.scale, delay)?;
+                    }
+                    *pending = Some((pixels, self.frames));
+                }
+            }
+            Video::Y4m(out) => {
+                out.write_all(b"FRAME\n")?;
+                let palette = self.palette;
+                for values in [
+                    palette.map(luma),
+                    palette.map(chroma_blue),
+                    palette.map(chroma_red),
+                ] {
+                    let bytes: Vec<u8> = pixels.iter().map(|&i| values[usize::from(i)]).collect();
+                    out.write_all(&bytes)?;
+                }
+            }
+        }
+        self.frames += 1;
+
+        if let Some(wav) = &mut self.audio {
+            let samples = frame_samples(self.frames - 1, self.frame_rate);
+            wav.add_samples(state, samples)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the last frame and completes the files.
+    pub fn finish(mut self) -> anyhow::Result<()> {
+        match &mut self.video {
+            Video::Gif { encoder, pending } => {
+                if let Some((frame, start)) = pending.take() {
+                    let delay = gif_delay(start, self.frames, self.frame_rate);
+                    write_gif_frame(encoder, &frame, self.width * self.scale, delay)?;
+                }
+                encoder.get_mut().flush()?;
+            }
+            Video::Y4m(out) => out.flush()?,
+        }
+        if let Some(mut wav) = self.audio {
+            wav.out.seek(SeekFrom::Start(0))?;
+            write_wav_header(&mut wav.out, wav.samples)?;
+            wav.out.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Palette indices of the scaled canvas, with low resolution frames on a
+    /// high resolution canvas doubled.
+    fn canvas(&self, state: &Chip8State) -> Vec<u8> {
+        let display = &state.display;
+        let factor_x = self.width / display.width().max(1);
+        let factor_y = self.height / display.height().max(1);
+        let (width, height) = (self.width * self.scale, self.height * self.scale);
+        (0..height)
+            .flat_map(|y| {
+                (0..width).map(move |x| {
+                    display.color(x / self.scale / factor_x, y / self.scale / factor_y)
+                })
+            })
+            .collect()
+    }
+}
+
+impl Wav {
+    /// Appends `count` samples of the sound `state` makes.
+    fn add_samples(&mut self, state: &Chip8State, count: u32) -> anyhow::Result<()> {
+        let xochip = state.settings.platform.has_xochip_extensions();
+        let pattern_bits = (AUDIO_PATTERN_SIZE * 8) as f32;
+        let playback_rate = 4000.0 * 2f32.powf((f32::from(state.pitch) - 64.0) / 48.0);
+        for _ in 0..count {
+            let sample = if state.sound_timer == 0 {
+                0.0
+            } else if xochip {
+                let bit = self.phase as usize;
+                self.phase = (self.phase + playback_rate / WAV_SAMPLE_RATE as f32) % pattern_bits;
+                if (state.audio_pattern[bit / 8] >> (7 - bit % 8)) & 1 == 1 {
+                    PATTERN_AMPLITUDE
+                } else {
+                    -PATTERN_AMPLITUDE
+                }
+            } else {
+                let sample = (self.phase * TAU).sin();
+                self.phase = (self.phase + self.frequency / WAV_SAMPLE_RATE as f32) % 1.0;
+                sample
+            };
+            let value = (sample * self.volume * f32::from(i16::MAX)) as i16;
+            self.out.write_all(&value.to_le_bytes())?;
+        }
+        self.samples += count;
+        Ok(())
+    }
+}
+
+/// Writes an indexed GIF frame shown for `delay` hundredths of a
+/// second.
+fn write_gif_frame(
+    encoder: &mut gif::Encoder<BufWriter<File>>,
+    pixels: &[u8],
+    width: usize,
+    delay: u16,
+) -> anyhow::Result<()> {
+    let height = pixels.len() / width;
+    let mut frame = gif::Frame::from_indexed_pixels(width as u16, height as u16, pixels, None);
+    frame.delay = delay;
+    encoder.write_frame(&frame)?;
+    Ok(())
+}
+
+/// Delay in hundredths of a second of a GIF frame shown from frame `start` to
+/// frame `end`, rounding the start and end times rather than the length so
+/// the delays add up to the real time.
+fn gif_delay(start: u64, end: u64, frame_rate: u64) -> u16 {
+    let centiseconds = |frame: u64| (frame * 100 + frame_rate / 2) / frame_rate;
+    (centiseconds(end) - centiseconds(start)).clamp(1, u64::from(u16::MAX)) as u16
+}
+
+/// Number of audio samples in frame `frame`, rounding the frame's start and
+/// end times.
+fn frame_samples(frame: u64, frame_rate: u64) -> u32 {
+    let sample = |frame: u64| frame * u64::from(WAV_SAMPLE_RATE) / frame_rate;
+    (sample(frame + 1) - sample(frame)) as u32
+}
+
+/// Writes the header of a 16-bit mono PCM WAV file with `samples` samples.
+fn write_wav_header(out: &mut impl Write, samples: u32) -> std::io::Result<()> {
+    let data_size = samples * 2;
+    out.write_all(b"RIFF")?;
+    out.write_all(&(36 + data_size).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?; // PCM
+    out.write_all(&1u16.to_le_bytes())?; // Mono
+    out.write_all(&WAV_SAMPLE_RATE.to_le_bytes())?;
+    out.write_all(&(WAV_SAMPLE_RATE * 2).to_le_bytes())?;
+    out.write_all(&2u16.to_le_bytes())?; // Bytes per sample
+    out.write_all(&16u16.to_le_bytes())?; // Bits per sample
+    out.write_all(b"data")?;
+    out.write_all(&data_size.to_le_bytes())
+}
+
+/// BT.601 luma of an RGB colour, in the studio range 16-235.
+fn luma((r, g, b): (u8, u8, u8)) -> u8 {
+    let y = 16.0
+        + 65.481 * f32::from(r) / 255.0
+        + 128.553 * f32::from(g) / 255.0
+        + 24.966 * f32::from(b) / 255.0;
+    y.round() as u8
+}
+
+/// BT.601 blue-difference chroma of an RGB colour, in the studio range 16-240.
+fn chroma_blue((r, g, b): (u8, u8, u8)) -> u8 {
+    let cb = 128.0 - 37.797 * f32::from(r) / 255.0 - 74.203 * f32::from(g) / 255.0
+        + 112.0 * f32::from(b) / 255.0;
+    cb.round() as u8
+}
+
+/// BT.601 red-difference chroma of an RGB colour, in the studio range 16-240.
+fn chroma_red((r, g, b): (u8, u8, u8)) -> u8 {
+    let cr = 128.0 + 112.0 * f32::from(r) / 255.0
+        - 93.786 * f32::from(g) / 255.0
+        - 18.214 * f32::from(b) / 255.0;
+    cr.round() as u8
+}
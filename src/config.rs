@@ -0,0 +1,939 @@
+//! Configuration File
+//!
+//! The terminal frontend reads its defaults from a TOML file, by default
+//! `$XDG_CONFIG_HOME/chip8/config.toml` (`~/.config/chip8/config.toml`), or
+//! the file passed with `--config`. Every setting is optional:
+//!
+//! ```toml
+//! [timing]
+//! frame_rate = 60
+//! ips = 700
+//!
+//! [machine]
+//! platform = "chip8"
+//! rng = "xorshift"
+//! rewind_frames = 600
+//!
+//! [quirks]
+//! preset = "vip"
+//! shift_in_place = true
+//!
+//! # A built-in theme, optionally with its pixel colours replaced by four
+//! # colours for the background, plane 1, plane 2 and both planes, or just the
+//! # background and plane 1 changed. The colour depth defaults to what
+//! # COLORTERM and TERM report.
+//! [palette]
+//! theme = "amber"
+//! colors = ["reset", "white", "lightred", "lightyellow"]
+//! background = "black"
+//! foreground = "#33ff66"
+//! depth = "256"
+//!
+//! # How pixels are drawn, and an integer scale instead of fitting the terminal.
+//! # Persistence keeps pixels visible after they are turned off, to reduce
+//! # flicker: "fade" dims them over decay_frames frames, "merge" shows the
+//! # pixels lit in either of the last two frames.
+//! [display]
+//! renderer = "half-block"
+//! scale = 2
+//! persistence = "fade"
+//! decay_frames = 4
+//!
+//! # Draw the display as a bitmap in terminals supporting Kitty graphics or
+//! # Sixel, at a scale in bitmap pixels instead of fitting the terminal
+//! [graphics]
+//! protocol = "auto"
+//! scale = 8
+//!
+//! # Screenshots taken with F12 or --screenshot-at-frame: a PNG in the palette
+//! # colours, scaled up, or a black and white plain PBM
+//! [screenshot]
+//! format = "png"
+//! scale = 8
+//!
+//! # Videos captured with F11 or --capture: the format of captures started
+//! # with F11, the scale, and whether to write the sound to a WAV file too
+//! [capture]
+//! format = "gif"
+//! scale = 4
+//! audio = true
+//!
+//! # Host keys for each CHIP-8 key. Keys not listed keep the QWERTY default.
+//! [keymap]
+//! 5 = ["w", "up"]
+//! 8 = ["s", "down"]
+//!
+//! [audio]
+//! enabled = true
+//! frequency = 440.0
+//! volume = 0.5
+//!
+//! [debugger]
+//! enabled = false
+//! breakpoints = ["0x2a4"]
+//! watchpoints = ["v3 == 0x10"]
+//!
+//! [input]
+//! backend = "crossterm"
+//!
+//! # ROM database in the community programs.json format, searched before the
+//! # built-in one
+//! [database]
+//! path = "/usr/local/share/chip-8-database/programs.json"
+//!
+//! # Overrides for one ROM, by file name or SHA-1 hash, with the same sections
+//! [rom."pong.ch8".timing]
+//! ips = 1000
+//!
+//! [rom."pong.ch8".keymap]
+//! 1 = ["1", "up"]
+//! ```
+//!
+//! # Precedence
+//!
+//! Each setting is taken from the first of these that sets it: the command
+//! line, the ROM's section, the ROM's entry in the [ROM database](crate::romdb),
+//! the global section and the built-in defaults. Individual quirks apply on top
+//! of the quirks preset set in the same place or below, while a preset replaces
+//! the individual quirks set below it. The ROM's keymap changes apply on top of
+//! the database's, which apply on top of the global ones. `chip8 config dump`
+//! prints the resulting configuration.
+//!
+//! Host keys are named by the character they type without Shift (`q`, `1`,
+//! `;`), or as `up`, `down`, `left`, `right`, `space`, `enter` and `tab`.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{Context, anyhow, bail};
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::capture::{CaptureFormat, DEFAULT_CAPTURE_SCALE};
+use crate::debugger::{Watchpoint, parse_address};
+use crate::emulator::{DEFAULT_BEEP_FREQUENCY, FrontendOptions};
+use crate::graphics::GraphicsMode;
+use crate::input::{InputBackend, Keymap};
+use crate::phosphor::{DEFAULT_DECAY_FRAMES, MAX_DECAY_FRAMES, Persistence};
+use crate::render::Renderer;
+use crate::rewind::DEFAULT_REWIND_FRAMES;
+use crate::rng::RngKind;
+use crate::romdb::KnownRom;
+use crate::savestate::{RomHash, hex};
+use crate::screenshot::{DEFAULT_SCREENSHOT_SCALE, ScreenshotFormat};
+use crate::state::{
+    Address, DEFAULT_FRAME_RATE, DEFAULT_INSTRUCTIONS_PER_SECOND, Platform, Quirks, QuirksPreset,
+    Settings,
+};
+use crate::theme::{ColorDepth, Theme, ThemeName};
+
+/// Contents of a configuration file, or one layer of settings.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub timing: TimingConfig,
+    pub machine: MachineConfig,
+    pub quirks: QuirksConfig,
+    pub palette: PaletteConfig,
+    pub display: DisplayConfig,
+    pub graphics: GraphicsConfig,
+    pub screenshot: ScreenshotConfig,
+    pub capture: CaptureConfig,
+
+    /// Keymap changes applied to the default QWERTY keymap.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub keymap: KeymapConfig,
+
+    pub audio: AudioConfig,
+    pub debugger: DebuggerConfig,
+    pub input: InputConfig,
+    pub database: DatabaseConfig,
+
+    /// Per-ROM sections, keyed by ROM file name or lowercase SHA-1 hash.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub rom: BTreeMap<String, Config>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimingConfig {
+    /// Frames per second.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame_rate: Option<u64>,
+
+    /// Instructions per second.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ips: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MachineConfig {
+    #[serde(with = "value_enum", skip_serializing_if = "Option::is_none")]
+    pub platform: Option<Platform>,
+
+    #[serde(with = "value_enum", skip_serializing_if = "Option::is_none")]
+    pub rng: Option<RngKind>,
+
+    /// Random seed. Unset seeds from the operating system.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rewind_frames: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuirksConfig {
+    /// Preset the individual quirks apply on top of. Unset uses the
+    /// platform's preset.
+    #[serde(with = "value_enum", skip_serializing_if = "Option::is_none")]
+    pub preset: Option<QuirksPreset>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vf_reset: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_increment: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clipping: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shift_in_place: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jump_with_vx: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_wait: Option<bool>,
+}
+
+/// Colours of the game screen and panels. Colours are terminal colour names,
+/// `#rrggbb` or 256-colour indices.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PaletteConfig {
+    #[serde(with = "value_enum", skip_serializing_if = "Option::is_none")]
+    pub theme: Option<ThemeName>,
+
+    /// Colours of the background, plane 1, plane 2 and both planes, replacing
+    /// the theme's. Only taken from the layer that sets the theme, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub colors: Option<Vec<String>>,
+
+    /// Replaces the background colour.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+
+    /// Replaces the colour of plane 1, the only plane outside XO-CHIP.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub foreground: Option<String>,
+
+    /// Colours the terminal can show. Unset detects it from the environment.
+    #[serde(with = "value_enum", skip_serializing_if = "Option::is_none")]
+    pub depth: Option<ColorDepth>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplayConfig {
+    #[serde(with = "value_enum", skip_serializing_if = "Option::is_none")]
+    pub renderer: Option<Renderer>,
+
+    /// Integer scale of the display. Unset scales it to fit the terminal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<usize>,
+
+    #[serde(with = "value_enum", skip_serializing_if = "Option::is_none")]
+    pub persistence: Option<Persistence>,
+
+    /// Frames pixels fade for with the `fade` persistence.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decay_frames: Option<u8>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GraphicsConfig {
+    #[serde(with = "value_enum", skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<GraphicsMode>,
+
+    /// Bitmap pixels per display pixel. Unset scales it to fit the terminal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScreenshotConfig {
+    #[serde(with = "value_enum", skip_serializing_if = "Option::is_none")]
+    pub format: Option<ScreenshotFormat>,
+
+    /// Image pixels per display pixel in PNG screenshots.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CaptureConfig {
+    /// Format of captures started with F11.
+    #[serde(with = "value_enum", skip_serializing_if = "Option::is_none")]
+    pub format: Option<CaptureFormat>,
+
+    /// Image pixels per display pixel in captured videos.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<usize>,
+
+    /// Also write the sound to a WAV file next to the video.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio: Option<bool>,
+}
+
+/// Keymap section: host keys bound to each CHIP-8 key, named `0`-`9` and
+/// `a`-`f`.
+pub type KeymapConfig = BTreeMap<String, KeyNames>;
+
+/// One host key name or a list of them.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum KeyNames {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AudioConfig {
+    /// Play sound at all.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    /// Beep frequency in Hz.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency: Option<f32>,
+
+    /// Volume from 0 to 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume: Option<f32>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DebuggerConfig {
+    /// Start paused with the debugger panel shown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    /// Breakpoint addresses, set when the debugger is enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breakpoints: Option<Vec<String>>,
+
+    /// Watchpoint specifications, set when the debugger is enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub watchpoints: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InputConfig {
+    /// Keyboard input backend. Unset picks crossterm over SSH and rdev
+    /// otherwise.
+    #[serde(with = "value_enum", skip_serializing_if = "Option::is_none")]
+    pub backend: Option<InputBackend>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// `programs.json` file searched before the built-in ROM database.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+impl Config {
+    /// Default location of the configuration file, `chip8/config.toml` in
+    /// the XDG config directory.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(config_home.join("chip8").join("config.toml"))
+    }
+
+    /// Reads a configuration file.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read config {}", path.display()))?;
+        let config: Config =
+            toml::from_str(&text).with_context(|| format!("Invalid config {}", path.display()))?;
+        if config.rom.values().any(|rom| !rom.rom.is_empty()) {
+            bail!(
+                "Invalid config {}: ROM sections cannot be nested",
+                path.display()
+            );
+        }
+        Ok(config)
+    }
+
+    /// Reads the file at `path`, or the file at the default location if there
+    /// is one. A missing default file is the same as an empty one.
+    pub fn load_or_default(path: Option<&Path>) -> anyhow::Result<Self> {
+        match path {
+            Some(path) => Config::load(path),
+            None => match Config::default_path() {
+                Some(path) if path.exists() => Config::load(&path),
+                _ => Ok(Config::default()),
+            },
+        }
+    }
+
+    /// The built-in defaults, with every setting that has a fixed default
+    /// filled in.
+    pub fn defaults() -> Self {
+        Config {
+            timing: TimingConfig {
+                frame_rate: Some(DEFAULT_FRAME_RATE),
+                ips: Some(DEFAULT_INSTRUCTIONS_PER_SECOND),
+            },
+            machine: MachineConfig {
+                platform: Some(Platform::default()),
+                rng: Some(RngKind::default()),
+                seed: None,
+                rewind_frames: Some(DEFAULT_REWIND_FRAMES),
+            },
+            quirks: QuirksConfig::default(),
+            palette: PaletteConfig {
+                theme: Some(ThemeName::default()),
+                colors: None,
+                background: None,
+                foreground: None,
+                depth: Some(ColorDepth::detect()),
+            },
+            display: DisplayConfig {
+                renderer: Some(Renderer::default()),
+                scale: None,
+                persistence: Some(Persistence::default()),
+                decay_frames: Some(DEFAULT_DECAY_FRAMES),
+            },
+            graphics: GraphicsConfig {
+                protocol: Some(GraphicsMode::default()),
+                scale: None,
+            },
+            screenshot: ScreenshotConfig {
+                format: Some(ScreenshotFormat::default()),
+                scale: Some(DEFAULT_SCREENSHOT_SCALE),
+            },
+            capture: CaptureConfig {
+                format: Some(CaptureFormat::default()),
+                scale: Some(DEFAULT_CAPTURE_SCALE),
+                audio: Some(false),
+            },
+            keymap: Keymap::default().to_config(),
+            audio: AudioConfig {
+                enabled: Some(true),
+                frequency: Some(DEFAULT_BEEP_FREQUENCY),
+                volume: Some(1.0),
+            },
+            debugger: DebuggerConfig {
+                enabled: Some(false),
+                breakpoints: Some(Vec::new()),
+                watchpoints: Some(Vec::new()),
+            },
+            input: InputConfig {
+                backend: Some(InputBackend::detect()),
+            },
+            database: DatabaseConfig::default(),
+            rom: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the section for a ROM, looked up by SHA-1 hash and then by file
+    /// name.
+    pub fn rom_config(&self, rom: &Path, hash: &RomHash) -> Option<&Config> {
+        self.rom.get(&hex(hash)).or_else(|| {
+            let name = rom.file_name()?.to_str()?;
+            self.rom.get(name)
+        })
+    }
+
+    /// Resolves the configuration for a run: the command line settings in
+    /// `cli` layered over this file's section for `rom`, if any, the ROM's
+    /// database entry `known`, if any, the global section and the defaults.
+    /// The result has every setting filled in except the optional seed, quirks
+    /// preset, palette colours, display scale and database path, and a complete
+    /// keymap.
+    pub fn effective(
+        &self,
+        cli: &Config,
+        rom: Option<(&Path, &RomHash)>,
+        known: Option<&KnownRom>,
+    ) -> anyhow::Result<Self> {
+        let rom_config = rom.and_then(|(path, hash)| self.rom_config(path, hash));
+        let mut layers = vec![cli];
+        layers.extend(rom_config);
+        // The database entry depends on the frame rate and platform chosen
+        // above it
+        let frame_rate = layers
+            .iter()
+            .chain([&self])
+            .find_map(|layer| layer.timing.frame_rate)
+            .unwrap_or(DEFAULT_FRAME_RATE);
+        let platform = layers.iter().find_map(|layer| layer.machine.platform);
+        let known_config = known.map(|known| known.config(frame_rate, platform));
+        layers.extend(known_config.as_ref());
+        layers.push(self);
+        let defaults = Config::defaults();
+        layers.push(&defaults);
+
+        let mut keymap = Keymap::default();
+        keymap.apply(&self.keymap).context("Invalid keymap")?;
+        if let Some(known) = known {
+            keymap
+                .apply(&known.keymap_config(&keymap))
+                .context("Invalid keys in the ROM database")?;
+        }
+        if let (Some(rom_config), Some((path, _))) = (rom_config, rom) {
+            keymap
+                .apply(&rom_config.keymap)
+                .with_context(|| format!("Invalid keymap for ROM {}", path.display()))?;
+        }
+
+        let mut effective = layers
+            .into_iter()
+            .rev()
+            .fold(Config::default(), |lower, layer| layer.over(&lower));
+        effective.keymap = keymap.to_config();
+        effective.rom = BTreeMap::new();
+
+        let quirks = effective.quirks();
+        effective.quirks = QuirksConfig {
+            preset: effective.quirks.preset,
+            vf_reset: Some(quirks.vf_reset),
+            memory_increment: Some(quirks.memory_increment),
+            clipping: Some(quirks.clipping),
+            shift_in_place: Some(quirks.shift_in_place),
+            jump_with_vx: Some(quirks.jump_with_vx),
+            display_wait: Some(quirks.display_wait),
+        };
+        // Report invalid values before anything starts
+        let frame_rate = effective.timing.frame_rate.unwrap_or(DEFAULT_FRAME_RATE);
+        let ips = effective
+            .timing
+            .ips
+            .unwrap_or(DEFAULT_INSTRUCTIONS_PER_SECOND);
+        if frame_rate == 0 {
+            bail!("The frame rate must be at least 1");
+        }
+        if ips < frame_rate {
+            bail!(
+                "The instructions per second ({}) must be at least the frame rate ({})",
+                ips,
+                frame_rate
+            );
+        }
+        effective.theme()?;
+        if effective.display.scale == Some(0) {
+            bail!("The display scale must be at least 1");
+        }
+        if effective
+            .display
+            .decay_frames
+            .is_some_and(|frames| !(1..=MAX_DECAY_FRAMES).contains(&frames))
+        {
+            bail!(
+                "The decay frames must be between 1 and {}",
+                MAX_DECAY_FRAMES
+            );
+        }
+        if effective.graphics.scale == Some(0) {
+            bail!("The graphics scale must be at least 1");
+        }
+        if effective.screenshot.scale == Some(0) {
+            bail!("The screenshot scale must be at least 1");
+        }
+        if effective.capture.scale == Some(0) {
+            bail!("The capture scale must be at least 1");
+        }
+        effective.breakpoints()?;
+        effective.watchpoints()?;
+        Ok(effective)
+    }
+
+    /// Layers this configuration over `lower`: every setting this one leaves
+    /// unset is taken from `lower`. Keymaps and ROM sections are not merged.
+    fn over(&self, lower: &Config) -> Config {
+        Config {
+            timing: TimingConfig {
+                frame_rate: self.timing.frame_rate.or(lower.timing.frame_rate),
+                ips: self.timing.ips.or(lower.timing.ips),
+            },
+            machine: MachineConfig {
+                platform: self.machine.platform.or(lower.machine.platform),
+                rng: self.machine.rng.or(lower.machine.rng),
+                seed: self.machine.seed.or(lower.machine.seed),
+                rewind_frames: self.machine.rewind_frames.or(lower.machine.rewind_frames),
+            },
+            quirks: match self.quirks.preset {
+                // A preset picked here replaces the individual quirks picked below
+                Some(_) => self.quirks.clone(),
+                None => QuirksConfig {
+                    preset: lower.quirks.preset,
+                    vf_reset: self.quirks.vf_reset.or(lower.quirks.vf_reset),
+                    memory_increment: self
+                        .quirks
+                        .memory_increment
+                        .or(lower.quirks.memory_increment),
+                    clipping: self.quirks.clipping.or(lower.quirks.clipping),
+                    shift_in_place: self.quirks.shift_in_place.or(lower.quirks.shift_in_place),
+                    jump_with_vx: self.quirks.jump_with_vx.or(lower.quirks.jump_with_vx),
+                    display_wait: self.quirks.display_wait.or(lower.quirks.display_wait),
+                },
+            },
+            palette: PaletteConfig {
+                theme: self.palette.theme.or(lower.palette.theme),
+                // A theme picked here replaces the colours picked below
+                colors: match self.palette.theme {
+                    Some(_) => self.palette.colors.clone(),
+                    None => self
+                        .palette
+                        .colors
+                        .clone()
+                        .or_else(|| lower.palette.colors.clone()),
+                },
+                background: self
+                    .palette
+                    .background
+                    .clone()
+                    .or_else(|| lower.palette.background.clone()),
+                foreground: self
+                    .palette
+                    .foreground
+                    .clone()
+                    .or_else(|| lower.palette.foreground.clone()),
+                depth: self.palette.depth.or(lower.palette.depth),
+            },
+            display: DisplayConfig {
+                renderer: self.display.renderer.or(lower.display.renderer),
+                scale: self.display.scale.or(lower.display.scale),
+                persistence: self.display.persistence.or(lower.display.persistence),
+                decay_frames: self.display.decay_frames.or(lower.display.decay_frames),
+            },
+            graphics: GraphicsConfig {
+                protocol: self.graphics.protocol.or(lower.graphics.protocol),
+                scale: self.graphics.scale.or(lower.graphics.scale),
+            },
+            screenshot: ScreenshotConfig {
+                format: self.screenshot.format.or(lower.screenshot.format),
+                scale: self.screenshot.scale.or(lower.screenshot.scale),
+            },
+            capture: CaptureConfig {
+                format: self.capture.format.or(lower.capture.format),
+                scale: self.capture.scale.or(lower.capture.scale),
+                audio: self.capture.audio.or(lower.capture.audio),
+            },
+            keymap: KeymapConfig::new(),
+            audio: AudioConfig {
+                enabled: self.audio.enabled.or(lower.audio.enabled),
+                frequency: self.audio.frequency.or(lower.audio.frequency),
+                volume: self.audio.volume.or(lower.audio.volume),
+            },
+            debugger: DebuggerConfig {
+                enabled: self.debugger.enabled.or(lower.debugger.enabled),
+                breakpoints: self
+                    .debugger
+                    .breakpoints
+                    .clone()
+                    .or_else(|| lower.debugger.breakpoints.clone()),
+                watchpoints: self
+                    .debugger
+                    .watchpoints
+                    .clone()
+                    .or_else(|| lower.debugger.watchpoints.clone()),
+            },
+            input: InputConfig {
+                backend: self.input.backend.or(lower.input.backend),
+            },
+            database: DatabaseConfig {
+                path: self
+                    .database
+                    .path
+                    .clone()
+                    .or_else(|| lower.database.path.clone()),
+            },
+            rom: BTreeMap::new(),
+        }
+    }
+
+    /// The platform, defaulting to CHIP-8.
+    pub fn platform(&self) -> Platform {
+        self.machine.platform.unwrap_or_default()
+    }
+
+    /// The quirks: the preset, or the platform's preset, with the individual
+    /// quirks applied on top.
+    pub fn quirks(&self) -> Quirks {
+        let mut quirks = self
+            .quirks
+            .preset
+            .map(Quirks::preset)
+            .unwrap_or_else(|| Quirks::for_platform(self.platform()));
+        let flags = [
+            (&mut quirks.vf_reset, self.quirks.vf_reset),
+            (&mut quirks.memory_increment, self.quirks.memory_increment),
+            (&mut quirks.clipping, self.quirks.clipping),
+            (&mut quirks.shift_in_place, self.quirks.shift_in_place),
+            (&mut quirks.jump_with_vx, self.quirks.jump_with_vx),
+            (&mut quirks.display_wait, self.quirks.display_wait),
+        ];
+        for (quirk, value) in flags {
+            if let Some(value) = value {
+                *quirk = value;
+            }
+        }
+        quirks
+    }
+
+    /// Builds the emulator settings for running `rom`, using the defaults for
+    /// anything unset.
+    pub fn settings(&self, rom: String) -> anyhow::Result<Settings> {
+        let defaults = Settings::new(DEFAULT_FRAME_RATE, DEFAULT_INSTRUCTIONS_PER_SECOND, rom);
+        Ok(Settings {
+            frame_rate: self.timing.frame_rate.unwrap_or(defaults.frame_rate),
+            ips: self.timing.ips.unwrap_or(defaults.ips),
+            platform: self.platform(),
+            quirks: self.quirks(),
+            rng: self.machine.rng.unwrap_or(defaults.rng),
+            seed: self.machine.seed,
+            ..defaults
+        })
+    }
+
+    /// Builds the options of the terminal frontend, using the defaults for
+    /// anything unset.
+    pub fn frontend_options(&self) -> FrontendOptions {
+        let defaults = FrontendOptions::default();
+        FrontendOptions {
+            debug: self.debugger.enabled.unwrap_or(defaults.debug),
+            rewind_frames: self.machine.rewind_frames.unwrap_or(defaults.rewind_frames),
+            sound: self.audio.enabled.unwrap_or(defaults.sound),
+            beep_frequency: self.audio.frequency.unwrap_or(defaults.beep_frequency),
+            volume: self.audio.volume.unwrap_or(defaults.volume).clamp(0.0, 1.0),
+        }
+    }
+
+    /// The theme, with the palette colours, background and foreground
+    /// replaced if set, reduced to the colour depth.
+    pub fn theme(&self) -> anyhow::Result<Theme> {
+        let mut theme = Theme::named(self.palette.theme.unwrap_or_default());
+        if let Some(colors) = &self.palette.colors {
+            let colors = colors
+                .iter()
+                .map(|name| parse_color(name))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            theme.palette = colors.try_into().map_err(|colors: Vec<_>| {
+                anyhow!(
+                    "The palette needs 4 colours (background, plane 1, plane 2, both), found {}",
+                    colors.len()
+                )
+            })?;
+        }
+        if let Some(background) = &self.palette.background {
+            theme.palette[0] = parse_color(background)?;
+        }
+        if let Some(foreground) = &self.palette.foreground {
+            theme.palette[1] = parse_color(foreground)?;
+        }
+        Ok(theme.reduce(self.palette.depth.unwrap_or_else(ColorDepth::detect)))
+    }
+
+    /// The keymap: the default keymap with this configuration's changes.
+    pub fn keymap(&self) -> anyhow::Result<Keymap> {
+        let mut keymap = Keymap::default();
+        keymap.apply(&self.keymap)?;
+        Ok(keymap)
+    }
+
+    /// The breakpoint addresses.
+    pub fn breakpoints(&self) -> anyhow::Result<Vec<Address>> {
+        self.debugger
+            .breakpoints
+            .iter()
+            .flatten()
+            .map(|addr| parse_address(addr).context("Invalid breakpoint"))
+            .collect()
+    }
+
+    /// The watchpoints.
+    pub fn watchpoints(&self) -> anyhow::Result<Vec<Watchpoint>> {
+        self.debugger
+            .watchpoints
+            .iter()
+            .flatten()
+            .map(|spec| spec.parse().context("Invalid watchpoint"))
+            .collect()
+    }
+
+    /// The input backend, detected from the session if unset.
+    pub fn input_backend(&self) -> InputBackend {
+        self.input.backend.unwrap_or_else(InputBackend::detect)
+    }
+
+    /// Formats the configuration as TOML.
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+}
+
+/// Parses a terminal colour name, `#rrggbb` or 256-colour index.
+fn parse_color(name: &str) -> anyhow::Result<Color> {
+    Color::from_str(name).map_err(|_| anyhow!("Invalid colour '{}'", name))
+}
+
+/// Serializes optional clap value enums by their command line names, so that
+/// the config file accepts the same values as the options.
+mod value_enum {
+    use clap::ValueEnum;
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<T: ValueEnum, S: Serializer>(
+        value: &Option<T>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let name = value
+            .as_ref()
+            .and_then(ValueEnum::to_possible_value)
+            .ok_or_else(|| serde::ser::Error::custom("value has no name"))?;
+        serializer.serialize_str(name.get_name())
+    }
+
+    pub fn deserialize<'de, T: ValueEnum, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<T>, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        T::from_str(&name, true).map(Some).map_err(|_| {
+            let expected: Vec<_> = T::value_variants()
+                .iter()
+                .filter_map(|v| v.to_possible_value())
+                .map(|v| v.get_name().to_string())
+                .collect();
+            D::Error::custom(format!(
+                "invalid value '{}', expected one of: {}",
+                name,
+                expected.join(", ")
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::romdb::RomDatabase;
+    use crate::savestate::rom_hash;
+
+    const ROM: [u8; 2] = [0x12, 0x00];
+
+    /// A global section and a section for `game.ch8`.
+    const FILE: &str = r#"
+[timing]
+frame_rate = 50
+ips = 900
+
+[machine]
+rng = "lcg"
+
+[quirks]
+shift_in_place = false
+clipping = false
+
+[rom."game.ch8".timing]
+ips = 1200
+
+[rom."game.ch8".machine]
+seed = 5
+"#;
+
+    /// A database listing `ROM` as a SUPER-CHIP game at 30 instructions per
+    /// frame.
+    fn known() -> KnownRom {
+        let json = format!(
+            r#"[{{"title": "Game", "roms": {{"{}": {{"platforms": ["superchip"], "tickrate": 30}}}}}}]"#,
+            hex(&rom_hash(&ROM))
+        );
+        RomDatabase::parse(&json)
+            .unwrap()
+            .lookup(&rom_hash(&ROM))
+            .unwrap()
+    }
+
+    fn resolve(file: &str, cli: &Config, rom: &str, known: Option<&KnownRom>) -> Settings {
+        let file: Config = toml::from_str(file).unwrap();
+        let hash = rom_hash(&ROM);
+        file.effective(cli, Some((Path::new(rom), &hash)), known)
+            .unwrap()
+            .settings(rom.to_string())
+            .unwrap()
+    }
+
+    #[test]
+    fn layers_apply_in_order() {
+        let mut cli = Config::default();
+        cli.machine.seed = Some(9);
+        let settings = resolve(FILE, &cli, "game.ch8", Some(&known()));
+        // The command line beats the ROM's section
+        assert_eq!(settings.seed, Some(9));
+        // The ROM's section beats the database's 30 instructions per frame
+        assert_eq!(settings.ips, 1200);
+        // The database beats the global section
+        assert_eq!(settings.platform, Platform::SuperChip);
+        // The global section beats the defaults
+        assert_eq!(settings.frame_rate, 50);
+        assert_eq!(settings.rng, RngKind::Lcg);
+
+        // Without the ROM's section, the database's tickrate applies at the
+        // global frame rate
+        let settings = resolve(FILE, &Config::default(), "other.ch8", Some(&known()));
+        assert_eq!(settings.ips, 30 * 50);
+        assert_eq!(settings.seed, None);
+
+        // And without a database entry, the global section
+        let settings = resolve(FILE, &Config::default(), "other.ch8", None);
+        assert_eq!(settings.ips, 900);
+        assert_eq!(settings.platform, Platform::Chip8);
+    }
+
+    #[test]
+    fn presets_replace_individual_quirks_from_lower_layers() {
+        // The database's preset replaces the global individual quirks
+        let settings = resolve(FILE, &Config::default(), "game.ch8", Some(&known()));
+        assert_eq!(settings.quirks, Quirks::preset(QuirksPreset::SchipLegacy));
+
+        // A command line preset replaces the ROM section's individual quirks,
+        // while individual quirks beside it still apply
+        let file = "[rom.\"game.ch8\".quirks]\nvf_reset = true\n";
+        let mut cli = Config::default();
+        cli.quirks.preset = Some(QuirksPreset::SchipModern);
+        cli.quirks.display_wait = Some(true);
+        let settings = resolve(file, &cli, "game.ch8", None);
+        assert_eq!(
+            settings.quirks,
+            Quirks {
+                display_wait: true,
+                ..Quirks::preset(QuirksPreset::SchipModern)
+            }
+        );
+    }
+
+    #[test]
+    fn individual_quirks_apply_over_the_default_preset() {
+        let settings = resolve(FILE, &Config::default(), "other.ch8", None);
+        assert_eq!(
+            settings.quirks,
+            Quirks {
+                shift_in_place: false,
+                clipping: false,
+                ..Quirks::preset(QuirksPreset::Vip)
+            }
+        );
+    }
+}
@@ -0,0 +1,352 @@
+//! Interactive Debugger Panel
+//!
+//! A terminal panel shown next to the game screen in `--debug` mode. It
+//! displays the registers, call stack, timers and a disassembly window, and
+//! drives the machine's [`Debugger`](crate::debugger::Debugger) from debugger
+//! keys: pause/continue, single-step, step over `2NNN` calls, run to the cursor,
+//! toggle breakpoints and add watchpoints.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Paragraph, Wrap},
+};
+
+use crate::debugger::{RunStatus, StopReason, Watchpoint, WatchpointHit};
+use crate::disasm::{Syntax, disassemble_at};
+use crate::machine::Machine;
+use crate::state::Address;
+use crate::theme::Theme;
+
+/// Key bindings shown at the bottom of the panel. None of them overlap the
+/// CHIP-8 keypad mapping.
+const HELP: &str = "p run/pause  n step  o step over  g run to cursor  b breakpoint  \
+    m add watchpoint  u clear watchpoints  ↑↓ PgUp PgDn Home cursor";
+
+/// Help shown while a watchpoint is being typed.
+const PROMPT_HELP: &str = "Enter add  Backspace delete  empty Enter cancels  \
+    e.g. v3 == 0x10, i, w 0x300-0x30f, rw 0x400";
+
+/// Number of instructions the cursor moves on Page Up / Page Down.
+const PAGE_LINES: usize = 8;
+
+/// Debugger panel state: whether execution is paused, the disassembly cursor,
+/// the last status message and the watchpoint being typed, if any.
+pub struct DebugPanel {
+    paused: bool,
+    cursor: Address,
+    status: String,
+    prompt: Option<String>,
+}
+
+impl DebugPanel {
+    /// Creates a panel that starts paused with the cursor on the program counter.
+    pub fn new(machine: &Machine) -> Self {
+        DebugPanel {
+            paused: true,
+            cursor: machine.state().pc,
+            status: "Paused".to_string(),
+            prompt: None,
+        }
+    }
+
+    /// Returns true while execution is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Returns true while a watchpoint is being typed.
+    pub fn is_prompting(&self) -> bool {
+        self.prompt.is_some()
+    }
+
+    /// Runs one frame unless paused.
+    ///
+    /// Stopping on a breakpoint or failing to execute an instruction pauses
+    /// execution instead of ending the program, so the state that led there
+    /// can be inspected.
+    pub fn run_frame(&mut self, machine: &mut Machine) {
+        if self.paused {
+            return;
+        }
+        match machine.run_frame() {
+            Ok(RunStatus::Completed) => {}
+            Ok(RunStatus::Stopped(reason)) => {
+                self.pause(machine);
+                self.status = match reason {
+                    StopReason::Breakpoint(addr) => format!("Breakpoint at {:#06x}", addr),
+                    StopReason::Target(addr) => format!("Stopped at {:#06x}", addr),
+                    StopReason::Watchpoint(hit) => Self::describe_hit(&hit, machine),
+                };
+            }
+            Err(e) => {
+                self.pause(machine);
+                self.status = format!("Error: {}", e);
+            }
+        }
+        if !self.paused {
+            self.cursor = machine.state().pc;
+        }
+    }
+
+    /// Handles a debugger key press. Other keys are ignored.
+    pub fn handle_key(&mut self, key: KeyEvent, machine: &mut Machine) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        if let Some(prompt) = &mut self.prompt {
+            match key.code {
+                KeyCode::Char(c) => prompt.push(c),
+                KeyCode::Backspace => {
+                    prompt.pop();
+                }
+                KeyCode::Enter => self.submit_prompt(machine),
+                _ => {}
+            }
+            return;
+        }
+        match key.code {
+            KeyCode::Char('p') if self.paused => self.resume(machine),
+            KeyCode::Char('p') => {
+                self.pause(machine);
+                self.status = "Paused".to_string();
+            }
+            KeyCode::Char('n') => self.step(machine),
+            KeyCode::Char('o') => self.step_over(machine),
+            KeyCode::Char('g') => {
+                machine.debugger_mut().run_to(self.cursor);
+                self.resume(machine);
+            }
+            KeyCode::Char('b') => {
+                let set = machine.debugger_mut().toggle_breakpoint(self.cursor);
+                self.status = format!(
+                    "Breakpoint {} at {:#06x}",
+                    if set { "set" } else { "cleared" },
+                    self.cursor
+                );
+            }
+            KeyCode::Char('m') => self.prompt = Some(String::new()),
+            KeyCode::Char('u') => {
+                machine.debugger_mut().clear_watchpoints();
+                self.status = "Watchpoints cleared".to_string();
+            }
+            KeyCode::Up => self.move_cursor(machine, -1),
+            KeyCode::Down => self.move_cursor(machine, 1),
+            KeyCode::PageUp => self.move_cursor(machine, -(PAGE_LINES as isize)),
+            KeyCode::PageDown => self.move_cursor(machine, PAGE_LINES as isize),
+            KeyCode::Home => self.cursor = machine.state().pc,
+            _ => {}
+        }
+    }
+
+    /// Parses and adds the typed watchpoint, closing the prompt.
+    fn submit_prompt(&mut self, machine: &mut Machine) {
+        let Some(spec) = self.prompt.take() else {
+            return;
+        };
+        if spec.trim().is_empty() {
+            return;
+        }
+        self.status = match spec.parse::<Watchpoint>() {
+            Ok(watchpoint) => {
+                machine.debugger_mut().add_watchpoint(watchpoint);
+                format!("Watchpoint added: {}", watchpoint)
+            }
+            Err(e) => format!("Error: {}", e),
+        };
+    }
+
+    /// Describes a watchpoint hit: the watchpoint, the instruction that
+    /// triggered it and the old and new values.
+    fn describe_hit(hit: &WatchpointHit, machine: &Machine) -> String {
+        let state = machine.state();
+        let instruction = disassemble_at(
+            &state.memory,
+            hit.pc,
+            state.settings.platform,
+            Syntax::default(),
+        );
+        let location = match hit.addr {
+            Some(addr) => format!(" [{:#06x}]", addr),
+            None => String::new(),
+        };
+        format!(
+            "Watchpoint {} hit by {} at {:#06x}:{} {:#x} -> {:#x}",
+            hit.watchpoint, instruction.text, hit.pc, location, hit.old, hit.new
+        )
+    }
+
+    /// Pauses execution and moves the cursor to the program counter.
+    fn pause(&mut self, machine: &mut Machine) {
+        self.paused = true;
+        self.cursor = machine.state().pc;
+    }
+
+    /// Continues execution from the current program counter.
+    fn resume(&mut self, machine: &mut Machine) {
+        machine.debugger_mut().resume();
+        self.paused = false;
+        self.status = "Running".to_string();
+    }
+
+    /// Executes a single instruction and pauses.
+    fn step(&mut self, machine: &mut Machine) {
+        machine.debugger_mut().clear_target();
+        let result = machine.step();
+        self.pause(machine);
+        self.status = match result {
+            Ok(RunStatus::Stopped(StopReason::Watchpoint(hit))) => {
+                Self::describe_hit(&hit, machine)
+            }
+            Ok(_) => format!("Stepped to {:#06x}", machine.state().pc),
+            Err(e) => format!("Error: {}", e),
+        };
+    }
+
+    /// Runs a `2NNN` subroutine call until it returns to the next instruction;
+    /// any other instruction is single-stepped.
+    fn step_over(&mut self, machine: &mut Machine) {
+        let state = machine.state();
+        let pc = state.pc;
+        let is_call = state
+            .memory
+            .data()
+            .get(pc)
+            .is_some_and(|high| high & 0xF0 == 0x20);
+
+        if is_call {
+            machine.debugger_mut().run_to(pc + 2);
+            self.resume(machine);
+        } else {
+            self.step(machine);
+        }
+    }
+
+    /// Moves the disassembly cursor by `lines` instructions.
+    fn move_cursor(&mut self, machine: &Machine, lines: isize) {
+        let last = machine.state().memory.size() - 2;
+        self.cursor = self.cursor.saturating_add_signed(lines * 2).min(last);
+    }
+
+    /// Renders the panel, framed in the theme's colours.
+    pub fn draw(&self, frame: &mut ratatui::Frame, area: Rect, machine: &Machine, theme: &Theme) {
+        let title = if self.paused {
+            "Debugger - PAUSED"
+        } else {
+            "Debugger - RUNNING"
+        };
+        let block = theme.block(title);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(10), // Registers, timers, stack, watchpoints and status
+                Constraint::Min(0),     // Disassembly
+                Constraint::Length(2),  // Key help or watchpoint prompt
+            ])
+            .split(inner);
+
+        frame.render_widget(
+            Paragraph::new(self.register_lines(machine)).wrap(Wrap { trim: false }),
+            chunks[0],
+        );
+        frame.render_widget(
+            Paragraph::new(self.disassembly_lines(machine, chunks[1].height as usize)),
+            chunks[1],
+        );
+        let help = match &self.prompt {
+            Some(prompt) => Paragraph::new(vec![
+                Line::from(format!("Watch: {}_", prompt)),
+                Line::from(Span::styled(
+                    PROMPT_HELP,
+                    Style::default().fg(Color::DarkGray),
+                )),
+            ]),
+            None => Paragraph::new(HELP).style(Style::default().fg(Color::DarkGray)),
+        };
+        frame.render_widget(help.wrap(Wrap { trim: true }), chunks[2]);
+    }
+
+    /// Formats V0-VF, the program counter, index, timers, stack and status.
+    fn register_lines(&self, machine: &Machine) -> Vec<Line<'static>> {
+        let state = machine.state();
+        let values = state.registers.values();
+
+        let mut lines: Vec<Line> = values
+            .chunks(4)
+            .enumerate()
+            .map(|(row, chunk)| {
+                let text: Vec<String> = chunk
+                    .iter()
+                    .enumerate()
+                    .map(|(col, value)| format!("V{:X} {:02X}", row * 4 + col, value))
+                    .collect();
+                Line::from(text.join("  "))
+            })
+            .collect();
+        lines.push(Line::from(format!(
+            "PC {:04X}  I {:04X}  DT {:02X}  ST {:02X}",
+            state.pc, state.index, state.delay_timer, state.sound_timer
+        )));
+
+        let stack: Vec<String> = state.stack.iter().map(|a| format!("{:04X}", a)).collect();
+        lines.push(Line::from(format!("Stack [{}]", stack.join(" "))));
+
+        let watchpoints: Vec<String> = machine
+            .debugger()
+            .watchpoints()
+            .iter()
+            .map(Watchpoint::to_string)
+            .collect();
+        lines.push(Line::from(format!("Watch [{}]", watchpoints.join(", "))));
+        lines.push(Line::from(Span::styled(
+            self.status.clone(),
+            Style::default().fg(Color::Yellow),
+        )));
+        lines
+    }
+
+    /// Disassembles `height` lines around the cursor, marking the program
+    /// counter with `>` and breakpoints with `*`.
+    fn disassembly_lines(&self, machine: &Machine, height: usize) -> Vec<Line<'static>> {
+        let state = machine.state();
+        let debugger = machine.debugger();
+        let platform = state.settings.platform;
+
+        let mut addr = self.cursor.saturating_sub(height / 2 * 2);
+        let mut lines = Vec::with_capacity(height);
+        while lines.len() < height && addr < state.memory.size() {
+            let line = disassemble_at(&state.memory, addr, platform, Syntax::default());
+            let bytes: String = line.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+            let text = format!(
+                "{}{} {:04X}  {:<8}  {}",
+                if addr == state.pc { '>' } else { ' ' },
+                if debugger.has_breakpoint(addr) {
+                    '*'
+                } else {
+                    ' '
+                },
+                addr,
+                bytes,
+                line.text
+            );
+
+            let mut style = Style::default();
+            if debugger.has_breakpoint(addr) {
+                style = style.fg(Color::LightRed);
+            } else if addr == state.pc {
+                style = style.fg(Color::LightGreen);
+            }
+            if addr == self.cursor {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            lines.push(Line::from(Span::styled(text, style)));
+            addr += line.bytes.len().max(1);
+        }
+        lines
+    }
+}
@@ -0,0 +1,423 @@
+//! Debugger Support
+//!
+//! Execution control used by [`Machine::run_frame`] to stop a program part way
+//! through a frame: user breakpoints on program counter addresses, a single
+//! temporary breakpoint that implements step-over and run-to-cursor, and
+//! watchpoints on memory ranges, registers and the index register.
+//!
+//! [`Machine::run_frame`]: crate::machine::Machine::run_frame
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use crate::state::{AccessKind, Address, Chip8State, MemoryAccess, NUM_REGISTERS, Register};
+
+/// Why execution stopped before the end of a frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// The program counter reached a user breakpoint.
+    Breakpoint(Address),
+    /// The program counter reached the temporary step-over or run-to-cursor target.
+    Target(Address),
+    /// The instruction that just executed triggered a watchpoint.
+    Watchpoint(WatchpointHit),
+}
+
+/// Outcome of running a frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RunStatus {
+    /// The frame ran to completion, or ended early because the machine halted
+    /// or is waiting for the vertical blank.
+    Completed,
+    /// Execution stopped before the instruction at the program counter.
+    Stopped(StopReason),
+}
+
+/// Memory accesses matched by a memory watchpoint.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WatchAccess {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl WatchAccess {
+    /// Returns true if an access of `kind` matches.
+    fn matches(self, kind: AccessKind) -> bool {
+        match self {
+            WatchAccess::Read => kind == AccessKind::Read,
+            WatchAccess::Write => kind == AccessKind::Write,
+            WatchAccess::ReadWrite => true,
+        }
+    }
+}
+
+/// A value that a value watchpoint observes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WatchTarget {
+    Register(Register),
+    Index,
+}
+
+/// Comparison operator of a watchpoint condition.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    /// Operators in the order they are matched when parsing, so that `<=`
+    /// is found before `<`.
+    const ALL: [Comparison; 6] = [
+        Comparison::Equal,
+        Comparison::NotEqual,
+        Comparison::LessOrEqual,
+        Comparison::GreaterOrEqual,
+        Comparison::Less,
+        Comparison::Greater,
+    ];
+
+    /// Operator symbol.
+    fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        }
+    }
+
+    /// Evaluates `lhs <op> rhs`.
+    fn eval(self, lhs: usize, rhs: usize) -> bool {
+        match self {
+            Comparison::Equal => lhs == rhs,
+            Comparison::NotEqual => lhs != rhs,
+            Comparison::Less => lhs < rhs,
+            Comparison::LessOrEqual => lhs <= rhs,
+            Comparison::Greater => lhs > rhs,
+            Comparison::GreaterOrEqual => lhs >= rhs,
+        }
+    }
+}
+
+/// A condition that stops execution after an instruction.
+///
+/// Watchpoints are written as text, parsed by [`FromStr`]:
+/// - `r 0x300`, `w 0x300-0x30f`, `rw 0x300-0x30f`: a read, write or any access
+///   to a byte in the inclusive address range
+/// - `v3`, `i`: any change of a register or the index register
+/// - `v3 == 0x10`, `i >= 0x300`: a change that leaves the value satisfying the
+///   condition (`==`, `!=`, `<`, `<=`, `>`, `>=`)
+///
+/// Instruction fetches never trigger memory watchpoints.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Watchpoint {
+    Memory {
+        start: Address,
+        end: Address,
+        access: WatchAccess,
+    },
+    Value {
+        target: WatchTarget,
+        condition: Option<(Comparison, usize)>,
+    },
+}
+
+impl Watchpoint {
+    /// Returns the first hit caused by an instruction at `pc`, given the
+    /// watched values before and after it executed and the memory it accessed.
+    fn check(
+        &self,
+        pc: Address,
+        before: &WatchedValues,
+        after: &WatchedValues,
+        accesses: &[MemoryAccess],
+    ) -> Option<WatchpointHit> {
+        match *self {
+            Watchpoint::Memory { start, end, access } => accesses
+                .iter()
+                .find(|a| (start..=end).contains(&a.addr) && access.matches(a.kind))
+                .map(|a| WatchpointHit {
+                    watchpoint: *self,
+                    pc,
+                    addr: Some(a.addr),
+                    old: usize::from(a.old),
+                    new: usize::from(a.new),
+                }),
+            Watchpoint::Value { target, condition } => {
+                let (old, new) = (before.get(target), after.get(target));
+                let satisfied = condition.is_none_or(|(op, value)| op.eval(new, value));
+                (old != new && satisfied).then_some(WatchpointHit {
+                    watchpoint: *self,
+                    pc,
+                    addr: None,
+                    old,
+                    new,
+                })
+            }
+        }
+    }
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Watchpoint::Memory { start, end, access } => {
+                let access = match access {
+                    WatchAccess::Read => "r",
+                    WatchAccess::Write => "w",
+                    WatchAccess::ReadWrite => "rw",
+                };
+                write!(f, "{} {:#06x}", access, start)?;
+                if end != start {
+                    write!(f, "-{:#06x}", end)?;
+                }
+                Ok(())
+            }
+            Watchpoint::Value { target, condition } => {
+                match target {
+                    WatchTarget::Register(reg) => write!(f, "v{:x}", reg as usize)?,
+                    WatchTarget::Index => write!(f, "i")?,
+                }
+                if let Some((op, value)) = condition {
+                    write!(f, " {} {:#x}", op.symbol(), value)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FromStr for Watchpoint {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let spec = s.trim().to_ascii_lowercase();
+
+        if let Some((access, range)) = spec.split_once(char::is_whitespace) {
+            let access = match access {
+                "r" => Some(WatchAccess::Read),
+                "w" => Some(WatchAccess::Write),
+                "rw" => Some(WatchAccess::ReadWrite),
+                _ => None,
+            };
+            if let Some(access) = access {
+                let (start, end) = match range.split_once('-') {
+                    Some((start, end)) => (parse_address(start)?, parse_address(end)?),
+                    None => (parse_address(range)?, parse_address(range)?),
+                };
+                if end < start {
+                    return Err(anyhow!("Watchpoint range ends before it starts: {}", s));
+                }
+                return Ok(Watchpoint::Memory { start, end, access });
+            }
+        }
+
+        let (target, condition) = match Comparison::ALL
+            .iter()
+            .find_map(|&op| spec.split_once(op.symbol()).map(|split| (op, split)))
+        {
+            Some((op, (target, value))) => (target, Some((op, parse_address(value)?))),
+            None => (spec.as_str(), None),
+        };
+        let target = match target.trim() {
+            "i" => WatchTarget::Index,
+            reg => {
+                let index = reg
+                    .strip_prefix('v')
+                    .filter(|digit| digit.len() == 1)
+                    .and_then(|digit| usize::from_str_radix(digit, 16).ok())
+                    .ok_or_else(|| anyhow!("Invalid watchpoint: {}", s))?;
+                WatchTarget::Register(Register::from_index(index)?)
+            }
+        };
+        Ok(Watchpoint::Value { target, condition })
+    }
+}
+
+/// Details of a triggered watchpoint.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct WatchpointHit {
+    /// The watchpoint that triggered.
+    pub watchpoint: Watchpoint,
+    /// Address of the instruction that triggered it.
+    pub pc: Address,
+    /// Memory address accessed, for memory watchpoints.
+    pub addr: Option<Address>,
+    /// Value before the instruction executed.
+    pub old: usize,
+    /// Value after the instruction executed. Equal to `old` for memory reads.
+    pub new: usize,
+}
+
+/// Register and index values captured around an instruction for value
+/// watchpoints.
+pub(crate) struct WatchedValues {
+    registers: [u8; NUM_REGISTERS],
+    index: Address,
+}
+
+impl WatchedValues {
+    /// Captures the watchable values of `state`.
+    pub(crate) fn capture(state: &Chip8State) -> Self {
+        WatchedValues {
+            registers: state.registers.values(),
+            index: state.index,
+        }
+    }
+
+    /// Returns the value of `target`.
+    fn get(&self, target: WatchTarget) -> usize {
+        match target {
+            WatchTarget::Register(reg) => usize::from(self.registers[reg as usize]),
+            WatchTarget::Index => self.index,
+        }
+    }
+}
+
+/// Parses a decimal or `0x`-prefixed hexadecimal address.
+pub fn parse_address(s: &str) -> anyhow::Result<Address> {
+    let s = s.trim();
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => Address::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|_| anyhow!("Invalid number: {}", s))
+}
+
+/// Breakpoint state consulted before each instruction of a frame.
+#[derive(Clone, Debug, Default)]
+pub struct Debugger {
+    breakpoints: BTreeSet<Address>,
+    target: Option<Address>,
+    resuming: bool,
+    watchpoints: Vec<Watchpoint>,
+}
+
+impl Debugger {
+    /// Creates a debugger with no breakpoints.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a breakpoint at `addr`.
+    pub fn add_breakpoint(&mut self, addr: Address) {
+        self.breakpoints.insert(addr);
+    }
+
+    /// Removes the breakpoint at `addr`, if any.
+    pub fn remove_breakpoint(&mut self, addr: Address) {
+        self.breakpoints.remove(&addr);
+    }
+
+    /// Adds or removes the breakpoint at `addr`. Returns true if the breakpoint
+    /// is now set.
+    pub fn toggle_breakpoint(&mut self, addr: Address) -> bool {
+        if self.breakpoints.remove(&addr) {
+            false
+        } else {
+            self.breakpoints.insert(addr);
+            true
+        }
+    }
+
+    /// Returns true if a breakpoint is set at `addr`.
+    pub fn has_breakpoint(&self, addr: Address) -> bool {
+        self.breakpoints.contains(&addr)
+    }
+
+    /// Iterates over all breakpoint addresses in ascending order.
+    pub fn breakpoints(&self) -> impl Iterator<Item = Address> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    /// Sets the temporary breakpoint, replacing any previous one. It is
+    /// cleared as soon as execution stops on it.
+    pub fn run_to(&mut self, addr: Address) {
+        self.target = Some(addr);
+    }
+
+    /// Returns the pending temporary breakpoint.
+    pub fn target(&self) -> Option<Address> {
+        self.target
+    }
+
+    /// Clears the temporary breakpoint.
+    pub fn clear_target(&mut self) {
+        self.target = None;
+    }
+
+    /// Lets the next instruction execute even if a breakpoint is set on it.
+    ///
+    /// Call this before continuing from a stop so that execution does not
+    /// immediately stop again on the same address.
+    pub fn resume(&mut self) {
+        self.resuming = true;
+    }
+
+    /// Adds a watchpoint.
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.watchpoints.push(watchpoint);
+    }
+
+    /// Removes all watchpoints.
+    pub fn clear_watchpoints(&mut self) {
+        self.watchpoints.clear();
+    }
+
+    /// Returns all watchpoints in the order they were added.
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    /// Returns true if any watchpoint is set.
+    pub(crate) fn has_watchpoints(&self) -> bool {
+        !self.watchpoints.is_empty()
+    }
+
+    /// Returns true if memory accesses need to be traced.
+    pub(crate) fn has_memory_watchpoints(&self) -> bool {
+        self.watchpoints
+            .iter()
+            .any(|w| matches!(w, Watchpoint::Memory { .. }))
+    }
+
+    /// Checks the watchpoints against an instruction at `pc` that has just
+    /// executed, returning the first hit.
+    pub(crate) fn check_watchpoints(
+        &self,
+        pc: Address,
+        before: &WatchedValues,
+        after: &WatchedValues,
+        accesses: &[MemoryAccess],
+    ) -> Option<WatchpointHit> {
+        self.watchpoints
+            .iter()
+            .find_map(|w| w.check(pc, before, after, accesses))
+    }
+
+    /// Checks whether execution should stop before the instruction at `pc`.
+    pub(crate) fn check(&mut self, pc: Address) -> Option<StopReason> {
+        if std::mem::take(&mut self.resuming) {
+            return None;
+        }
+        if self.target == Some(pc) {
+            self.target = None;
+            return Some(StopReason::Target(pc));
+        }
+        if self.breakpoints.contains(&pc) {
+            return Some(StopReason::Breakpoint(pc));
+        }
+        None
+    }
+}
@@ -0,0 +1,149 @@
+//! CHIP-8 Disassembler
+//!
+//! Turns instruction words back into assembly using the mnemonics provided by
+//! [`Instruction::mnemonic`], in either Octo or Cowgod syntax. Only canonical
+//! encodings are given a mnemonic: words that [`decode`] rejects, or accepts
+//! leniently (such as `5XY1`), are emitted as `db` data so that the output
+//! always describes the exact bytes in memory.
+//!
+//! [`Instruction::mnemonic`]: crate::instruction::Instruction::mnemonic
+
+use crate::instruction::decode;
+use crate::state::{Address, Memory, Platform};
+
+/// Assembly syntax used for mnemonics.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "frontend", derive(clap::ValueEnum))]
+pub enum Syntax {
+    /// Octo assembly, e.g. `v0 := 0x12`, `if v1 != 0x03 then`, `sprite v0 v1 5`.
+    #[default]
+    Octo,
+
+    /// Cowgod's technical reference, e.g. `ld v0, 0x12`, `se v1, 0x03`,
+    /// `drw v0, v1, 5`.
+    Cowgod,
+}
+
+/// A single disassembled instruction or data word.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Disassembly {
+    /// Address of the first byte.
+    pub address: Address,
+    /// Raw bytes covered by this line: 2 for an instruction, 4 for the XO-CHIP
+    /// `F000 NNNN` long index load, and 1 or 2 for data.
+    pub bytes: Vec<u8>,
+    /// Assembly text, e.g. `v0 := 0x12` or `db 0x51, 0x23`.
+    pub text: String,
+    /// True when the bytes did not decode to a canonical instruction.
+    pub is_data: bool,
+}
+
+impl Disassembly {
+    /// Formats the raw bytes as hexadecimal instruction words, e.g. `F000 1234`.
+    pub fn hex_words(&self) -> String {
+        let words: Vec<String> = self
+            .bytes
+            .chunks(2)
+            .map(|word| word.iter().map(|b| format!("{:02X}", b)).collect())
+            .collect();
+        words.join(" ")
+    }
+}
+
+/// Disassembles the instruction at `addr` in memory.
+pub fn disassemble_at(
+    memory: &Memory,
+    addr: Address,
+    platform: Platform,
+    syntax: Syntax,
+) -> Disassembly {
+    let bytes = memory.data().get(addr..).unwrap_or_default();
+    disassemble_one(bytes, addr, platform, syntax)
+}
+
+/// Disassembles a whole ROM by linear sweep, starting at `start`, the address
+/// the first byte is loaded at.
+pub fn disassemble(
+    rom: &[u8],
+    start: Address,
+    platform: Platform,
+    syntax: Syntax,
+) -> Vec<Disassembly> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset < rom.len() {
+        let line = disassemble_one(&rom[offset..], start + offset, platform, syntax);
+        offset += line.bytes.len();
+        lines.push(line);
+    }
+    lines
+}
+
+/// Disassembles the instruction at the start of `bytes`, located at `addr`.
+///
+/// A trailing single byte is emitted as one byte of data; an empty slice
+/// yields an empty `db` line.
+pub fn disassemble_one(
+    bytes: &[u8],
+    addr: Address,
+    platform: Platform,
+    syntax: Syntax,
+) -> Disassembly {
+    let data = |bytes: &[u8]| Disassembly {
+        address: addr,
+        bytes: bytes.to_vec(),
+        text: data_text(bytes),
+        is_data: true,
+    };
+
+    let [high, low, ..] = *bytes else {
+        return data(bytes);
+    };
+    let raw = u16::from_be_bytes([high, low]);
+    if !is_canonical(raw, platform) {
+        return data(&bytes[..2]);
+    }
+    let Ok(instruction) = decode(raw, platform) else {
+        return data(&bytes[..2]);
+    };
+
+    let mut text = instruction.mnemonic(syntax);
+    let mut len = 2;
+    if raw == 0xF000 && platform.has_xochip_extensions() {
+        let [_, _, target_high, target_low, ..] = *bytes else {
+            return data(&bytes[..2]);
+        };
+        text = format!(
+            "{} {:#06x}",
+            text,
+            u16::from_be_bytes([target_high, target_low])
+        );
+        len = 4;
+    }
+
+    Disassembly {
+        address: addr,
+        bytes: bytes[..len].to_vec(),
+        text,
+        is_data: false,
+    }
+}
+
+/// Returns false for words that [`decode`] accepts even though the low nibble
+/// is not part of a defined encoding, which would not reassemble to the same
+/// word: `5XYN` other than `5XY0` (and `5XY2`/`5XY3` on XO-CHIP), and `9XYN`
+/// other than `9XY0`.
+fn is_canonical(raw: u16, platform: Platform) -> bool {
+    let n = raw & 0xF;
+    match raw >> 12 {
+        0x5 => n == 0 || (platform.has_xochip_extensions() && (n == 2 || n == 3)),
+        0x9 => n == 0,
+        _ => true,
+    }
+}
+
+/// Formats raw bytes as a `db` data directive.
+fn data_text(bytes: &[u8]) -> String {
+    let values: Vec<String> = bytes.iter().map(|b| format!("{:#04x}", b)).collect();
+    format!("db {}", values.join(", "))
+}
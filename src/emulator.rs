@@ -4,27 +4,151 @@
 //! providing the complete execution environment including audio output, display rendering,
 //! and the primary emulation loop.
 
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use anyhow::anyhow;
 use crossterm::{
-    event,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
     terminal::{disable_raw_mode, enable_raw_mode},
 };
 use ratatui::{
     Terminal,
     backend::CrosstermBackend,
     layout::Alignment,
-    style::{Color, Style},
-    widgets::{Block, Borders, Paragraph},
+    style::Style,
+    widgets::{Block, Paragraph},
 };
 use rodio::{OutputStream, Sink, Source, source::SineWave};
 
-use crate::instruction::{Instruction, decode};
-use crate::state::{Chip8State, DISPLAY_HEIGHT, DISPLAY_WIDTH, MEM_SIZE, Settings};
+use crate::capture::{CaptureFormat, DEFAULT_CAPTURE_SCALE, Recorder};
+use crate::debug_panel::DebugPanel;
+use crate::debugger::Debugger;
+use crate::graphics::{GraphicsMode, GraphicsOutput};
+use crate::input::{InputBackend, KeyListener, Keymap};
+use crate::machine::Machine;
+use crate::movie::Movie;
+use crate::phosphor::{DEFAULT_DECAY_FRAMES, Persistence, Phosphor};
+use crate::render::Renderer;
+use crate::rewind::{DEFAULT_REWIND_FRAMES, Rewind};
+use crate::savestate::RomHash;
+use crate::screenshot::{
+    DEFAULT_SCREENSHOT_SCALE, ScreenshotFormat, save_screenshot, screenshot_path, timestamped_path,
+};
+use crate::state::{AUDIO_PATTERN_SIZE, DEFAULT_PITCH, Keypad, Settings};
+use crate::theme::{ColorDepth, Theme};
+
+/// Output sample rate of the XO-CHIP audio pattern generator in Hz.
+const PATTERN_SAMPLE_RATE: u32 = 44100;
+
+/// Amplitude of the XO-CHIP square wave samples.
+const PATTERN_AMPLITUDE: f32 = 0.25;
+
+/// Number of save state slots, bound to F1-F9.
+const SAVE_SLOTS: u8 = 9;
+
+/// Height of the keypad panel below the game screen.
+const KEYPAD_PANEL_HEIGHT: u16 = 7;
+
+/// Width kept free for the debugger panel when scaling the display to fit.
+const DEBUG_PANEL_WIDTH: u16 = 40;
+
+/// How long a save state message stays in the game screen title.
+const MESSAGE_DURATION: Duration = Duration::from_secs(3);
+
+/// Default frequency of the CHIP-8 beep in Hz.
+pub const DEFAULT_BEEP_FREQUENCY: f32 = 440.0;
+
+/// Options of the terminal frontend that the [`Machine`] does not need:
+/// debugging, rewind and sound.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrontendOptions {
+    /// Start paused with the interactive debugger panel shown next to the
+    /// game screen.
+    pub debug: bool,
+
+    /// Number of frames kept for rewinding; 0 disables rewind.
+    pub rewind_frames: usize,
+
+    /// Play sound while the sound timer is active. No audio device is opened
+    /// when this is false.
+    pub sound: bool,
+
+    /// Frequency of the beep in Hz. XO-CHIP audio patterns set their own
+    /// pitch instead.
+    pub beep_frequency: f32,
+
+    /// Output volume, from 0 (silent) to 1 (full).
+    pub volume: f32,
+}
+
+impl Default for FrontendOptions {
+    fn default() -> Self {
+        FrontendOptions {
+            debug: false,
+            rewind_frames: DEFAULT_REWIND_FRAMES,
+            sound: true,
+            beep_frequency: DEFAULT_BEEP_FREQUENCY,
+            volume: 1.0,
+        }
+    }
+}
+
+/// XO-CHIP audio pattern and pitch shared between the emulation loop and the
+/// audio thread.
+#[derive(Copy, Clone)]
+struct AudioPattern {
+    samples: [u8; AUDIO_PATTERN_SIZE],
+    pitch: u8,
+}
+
+/// Audio source that plays an XO-CHIP 1-bit audio pattern in a loop.
+///
+/// The 128 samples of the pattern are played back at
+/// `4000 * 2^((pitch - 64) / 48)` samples per second and resampled to the
+/// output rate by nearest-neighbour lookup.
+struct PatternWave {
+    pattern: Arc<Mutex<AudioPattern>>,
+    position: f32,
+}
 
-/// Default frequency for the CHIP-8 beep sound in Hz.
-const DEFAULT_FREQUENCY: f32 = 440.0;
+impl Iterator for PatternWave {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let pattern = *self.pattern.lock().unwrap();
+        let pattern_len = (AUDIO_PATTERN_SIZE * 8) as f32;
+        let playback_rate = 4000.0 * 2f32.powf((f32::from(pattern.pitch) - 64.0) / 48.0);
+        let bit = self.position as usize;
+        let sample = (pattern.samples[bit / 8] >> (7 - bit % 8)) & 1;
+
+        self.position = (self.position + playback_rate / PATTERN_SAMPLE_RATE as f32) % pattern_len;
+        Some(if sample == 1 {
+            PATTERN_AMPLITUDE
+        } else {
+            -PATTERN_AMPLITUDE
+        })
+    }
+}
+
+impl Source for PatternWave {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        PATTERN_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
 
 /// Audio subsystem for the CHIP-8 emulator's sound timer functionality.
 ///
@@ -37,6 +161,10 @@ pub struct Beep {
     /// Used to start and stop the beep sound based on the sound timer state.
     sink: Sink,
 
+    /// XO-CHIP audio pattern being played, if this beeper plays patterns
+    /// rather than a fixed sine tone.
+    pattern: Option<Arc<Mutex<AudioPattern>>>,
+
     /// Audio output stream handle.
     /// Must be kept alive for the duration of audio playback. Dropping this
     /// would terminate the audio connection and cause the sink to become invalid.
@@ -45,25 +173,71 @@ pub struct Beep {
 }
 
 impl Beep {
-    /// Creates a new `Beep` instance with the specified frequency.
+    /// Creates a new `Beep` instance with the specified frequency and volume.
     ///
     /// This constructor initializes the audio subsystem and prepares a continuous
     /// sine wave tone. The audio starts in a paused state and must be explicitly
     /// activated using the `on()` method.
-    pub fn new(freq: f32) -> anyhow::Result<Self> {
+    pub fn new(freq: f32, volume: f32) -> anyhow::Result<Self> {
+        let (stream, sink) = Self::paused_sink(SineWave::new(freq).repeat_infinite(), volume)?;
+
+        Ok(Self {
+            sink,
+            pattern: None,
+            stream,
+        })
+    }
+
+    /// Creates a new `Beep` instance that plays an XO-CHIP audio pattern.
+    ///
+    /// The pattern starts out as the XO-CHIP default buffer and can be updated
+    /// with `set_pattern()` while playing. Like `new()`, the audio starts paused.
+    pub fn with_pattern(volume: f32) -> anyhow::Result<Self> {
+        let pattern = Arc::new(Mutex::new(AudioPattern {
+            samples: [0xF0; AUDIO_PATTERN_SIZE],
+            pitch: DEFAULT_PITCH,
+        }));
+        let source = PatternWave {
+            pattern: pattern.clone(),
+            position: 0.0,
+        };
+        let (stream, sink) = Self::paused_sink(source, volume)?;
+
+        Ok(Self {
+            sink,
+            pattern: Some(pattern),
+            stream,
+        })
+    }
+
+    /// Opens the default audio output and queues `source` on a paused sink
+    /// playing at `volume`.
+    fn paused_sink<S>(source: S, volume: f32) -> anyhow::Result<(OutputStream, Sink)>
+    where
+        S: Source<Item = f32> + Send + 'static,
+    {
         let (stream, stream_handle) = OutputStream::try_default()?;
         let sink = Sink::try_new(&stream_handle)?;
-        let source = SineWave::new(freq).repeat_infinite();
 
         sink.append(source);
+        sink.set_volume(volume);
         sink.pause();
 
-        Ok(Self { sink, stream })
+        Ok((stream, sink))
+    }
+
+    /// Updates the audio pattern and pitch played by a pattern beeper.
+    ///
+    /// Has no effect on a sine tone beeper created with `new()`.
+    pub fn set_pattern(&mut self, samples: [u8; AUDIO_PATTERN_SIZE], pitch: u8) {
+        if let Some(pattern) = &self.pattern {
+            *pattern.lock().unwrap() = AudioPattern { samples, pitch };
+        }
     }
 
     /// Starts playing the beep tone.
     ///
-    /// Resumes playback of the sine wave tone. If the tone is already playing,
+    /// Resumes playback of the tone. If the tone is already playing,
     /// this method has no effect. The tone will continue playing until `off()`
     /// is called or the `Beep` instance is dropped.
     pub fn on(&mut self) {
@@ -72,7 +246,7 @@ impl Beep {
 
     /// Stops playing the beep tone.
     ///
-    /// Pauses playback of the sine wave tone. If the tone is already stopped,
+    /// Pauses playback of the tone. If the tone is already stopped,
     /// this method has no effect. The audio system remains ready and can be
     /// restarted immediately with `on()`.
     pub fn off(&mut self) {
@@ -80,49 +254,189 @@ impl Beep {
     }
 }
 
-/// Main emulator struct that encapsulates the CHIP-8 virtual machine state and
-/// audio subsystem.
+/// Main emulator struct that attaches a terminal display, audio and keyboard
+/// input to a headless CHIP-8 [`Machine`].
 pub struct Emulator {
-    state: Chip8State,
-    beeper: Beep,
+    machine: Machine,
+    options: FrontendOptions,
+    beeper: Option<Beep>,
+    debug_panel: Option<DebugPanel>,
+    message: Option<(String, Instant)>,
+    rewind: Rewind,
+    movie: Option<MovieSession>,
+    input: InputBackend,
+    keymap: Keymap,
+    theme: Theme,
+    title: Option<String>,
+    renderer: Renderer,
+    /// Display scale, or `None` to fit the terminal.
+    scale: Option<usize>,
+    graphics_mode: GraphicsMode,
+    /// Bitmap scale with terminal graphics, or `None` to fit the terminal.
+    graphics_scale: Option<usize>,
+    /// Terminal graphics output while running, if the protocol is available.
+    graphics: Option<GraphicsOutput>,
+    /// Display filter applied to the drawn pixels only.
+    phosphor: Phosphor,
+    screenshot_format: ScreenshotFormat,
+    screenshot_scale: usize,
+    /// Frame after which a screenshot is taken automatically.
+    screenshot_frame: Option<u64>,
+    /// Frames run since starting, not counting paused or rewound frames.
+    frame: u64,
+    capture_format: CaptureFormat,
+    capture_scale: usize,
+    /// Whether captures also write the sound to a WAV file.
+    capture_audio: bool,
+    /// Capture to start with the emulation loop.
+    capture_path: Option<PathBuf>,
+    /// Capture in progress.
+    recorder: Option<Recorder>,
+}
+
+/// Input movie being recorded or replayed by the emulator.
+enum MovieSession {
+    /// Recording to `path`. Host key presses are kept on a separate keypad and
+    /// only reach the machine at frame boundaries, so that every frame sees
+    /// exactly the keys recorded for it.
+    Record {
+        movie: Movie,
+        path: PathBuf,
+        host_keys: Keypad,
+        host_mask: u16,
+    },
+
+    /// Replaying a movie, with `frame` the next frame to play. Host key
+    /// presses are ignored.
+    Replay { movie: Movie, frame: usize },
+}
+
+impl MovieSession {
+    /// Runs the next movie frame. Fails if a replay desyncs.
+    fn run_frame(&mut self, machine: &mut Machine) -> anyhow::Result<()> {
+        match self {
+            MovieSession::Record {
+                movie,
+                host_keys,
+                host_mask,
+                ..
+            } => {
+                // Forward only the presses and releases since the last frame,
+                // so keys released by FX0A stay released until pressed again
+                let mask = host_keys.pressed_mask();
+                let pressed = mask & !*host_mask;
+                let released = *host_mask & !mask;
+                let keypad = machine.keypad();
+                keypad.set_pressed_mask((keypad.pressed_mask() | pressed) & !released);
+                *host_mask = mask;
+                movie.record_frame(machine)
+            }
+            MovieSession::Replay { movie, frame } => {
+                if let Some(desync) = movie.play_frame(*frame, machine)? {
+                    return Err(anyhow!("{}", desync));
+                }
+                *frame += 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns true once a replay has played every frame.
+    fn is_finished(&self) -> bool {
+        match self {
+            MovieSession::Record { .. } => false,
+            MovieSession::Replay { movie, frame } => *frame >= movie.frames.len(),
+        }
+    }
 }
 
 impl Emulator {
-    /// Renders the complete emulator interface including game screen and key mapping.
+    /// Renders the complete emulator interface including game screen and key mapping,
+    /// with the debugger panel to the right in debug mode.
     fn draw(&mut self, frame: &mut ratatui::Frame, area: ratatui::layout::Rect, rom_name: &str) {
         use ratatui::layout::{Constraint, Direction, Layout};
 
-        // Calculate the exact size needed for 64x32 display plus borders
-        let game_height = (DISPLAY_HEIGHT as u16) + 2; // +2 for top and bottom borders
+        // Scale the display to fill the space left by the keypad panel below
+        // and the debugger panel, if any, then calculate the exact size needed
+        // for the current display mode plus borders
+        let (display_width, display_height) = (
+            self.machine.display().width(),
+            self.machine.display().height(),
+        );
+        let reserved_width = if self.debug_panel.is_some() {
+            DEBUG_PANEL_WIDTH
+        } else {
+            0
+        };
+        let (free_columns, free_rows) = (
+            area.width.saturating_sub(reserved_width + 2),
+            area.height.saturating_sub(KEYPAD_PANEL_HEIGHT + 2),
+        );
+        let scale = match &self.graphics {
+            Some(graphics) => {
+                graphics.scale(display_width, display_height, free_columns, free_rows)
+            }
+            None => self.scale.unwrap_or_else(|| {
+                self.renderer
+                    .fit_scale(display_width, display_height, free_columns, free_rows)
+            }),
+        };
+        let (columns, rows) = self.display_size(scale);
+        let game_width = columns + 2; // +2 for left and right borders
+        let game_height = rows + 2; // +2 for top and bottom borders
+
+        let area = match &self.debug_panel {
+            Some(debug_panel) => {
+                let columns = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Length(game_width), Constraint::Min(0)])
+                    .split(area);
+                debug_panel.draw(frame, columns[1], &self.machine, &self.theme);
+                columns[0]
+            }
+            None => area,
+        };
 
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(game_height), // Exact size for game area
-                Constraint::Length(7),           // Key mapping area
-                Constraint::Min(0),              // Remaining space
+                Constraint::Length(game_height),         // Exact size for game area
+                Constraint::Length(KEYPAD_PANEL_HEIGHT), // Key mapping area
+                Constraint::Min(0),                      // Remaining space
             ])
             .split(area);
 
-        self.draw_main_screen(frame, chunks[0], rom_name);
+        self.draw_main_screen(frame, chunks[0], rom_name, scale);
         self.draw_key_mapping(frame, chunks[1]);
     }
 
     /// Renders the main CHIP-8 game screen with proper centering and borders.
     ///
-    /// This function handles the display of the 64×32 pixel game area, including:
+    /// This function handles the display of the 64×32 (or 128×64 in SUPER-CHIP high
+    /// resolution mode) pixel game area, including:
     /// - Horizontal centering when the terminal is wider than needed
-    /// - Converting the bit-based display buffer to visual characters
+    /// - Converting the bit-based display buffer to visual characters with the
+    ///   selected [`Renderer`] at `scale`, coloured by the XO-CHIP plane
+    ///   combination of each pixel after the [`Phosphor`] filter, with dimmed
+    ///   pixels on a colour ramp, or as shaded blocks with 16 colours
     /// - Adding a border with the ROM name as the title
+    ///
+    /// With terminal graphics, the screen is left empty and only placed for
+    /// the bitmap drawn after the frame.
     fn draw_main_screen(
         &mut self,
         frame: &mut ratatui::Frame,
         area: ratatui::layout::Rect,
         rom_name: &str,
+        scale: usize,
     ) {
         use ratatui::layout::{Constraint, Direction, Layout};
 
-        let game_width = (DISPLAY_WIDTH as u16) + 2; // +2 for left and right borders
+        let display = self.machine.display();
+        let (display_width, display_height) = (display.width(), display.height());
+        let (columns, _) = self.display_size(scale);
+        let (theme, phosphor) = (&self.theme, &self.phosphor);
+        let game_width = columns + 2; // +2 for left and right borders
 
         // Center the game horizontally if the terminal is wider than needed
         let game_area = if area.width > game_width {
@@ -139,83 +453,371 @@ impl Emulator {
             area
         };
 
-        // Convert display buffer to string representation
-        let mut row_string = String::with_capacity(DISPLAY_WIDTH * DISPLAY_HEIGHT + DISPLAY_HEIGHT);
-        for row_idx in 0..DISPLAY_HEIGHT {
-            for col_idx in 0..DISPLAY_WIDTH {
-                let index = row_idx * DISPLAY_WIDTH + col_idx;
-                row_string.push(if self.state.display[index] {
-                    '█'
-                } else {
-                    ' '
-                });
+        let palette = theme.palette;
+        let shaded_blocks = theme.depth == ColorDepth::Ansi16;
+        let lines = match &mut self.graphics {
+            Some(graphics) => {
+                graphics.place(Block::bordered().inner(game_area), scale);
+                Vec::new()
             }
-            row_string.push('\n');
-        }
+            None => self.renderer.render_shaded(
+                display_width,
+                display_height,
+                scale,
+                palette[0],
+                |x, y| {
+                    let (index, brightness) = phosphor.pixel(x, y);
+                    if shaded_blocks {
+                        (palette[usize::from(index)], brightness)
+                    } else {
+                        (theme.shade(index, brightness), 255)
+                    }
+                },
+            ),
+        };
 
-        let game_paragraph = Paragraph::new(row_string)
-            .block(Block::default().borders(Borders::ALL).title(rom_name))
-            .style(Style::default().fg(Color::White));
+        let title = match &self.message {
+            Some((message, shown)) if shown.elapsed() < MESSAGE_DURATION => {
+                format!("{} - {}", rom_name, message)
+            }
+            _ => rom_name.to_string(),
+        };
+        let game_paragraph = Paragraph::new(lines).block(self.theme.block(title));
         frame.render_widget(game_paragraph, game_area);
     }
 
-    /// Renders the keyboard mapping reference panel.
+    /// Terminal columns and rows covered by the display at `scale`, as text or
+    /// as a bitmap.
+    fn display_size(&self, scale: usize) -> (u16, u16) {
+        let display = self.machine.display();
+        let (width, height) = (display.width(), display.height());
+        match &self.graphics {
+            Some(graphics) => graphics.size(width, height, scale),
+            None => self.renderer.size(width, height, scale),
+        }
+    }
+
+    /// Renders the keyboard mapping reference panel from the active keymap,
+    /// laid out like the CHIP-8 keypad.
     fn draw_key_mapping(&self, frame: &mut ratatui::Frame, area: ratatui::layout::Rect) {
-        let key_mapping = "Key Mapping:\n\
-    1 2 3 4    →    1 2 3 C\n\
-    Q W E R    →    4 5 6 D\n\
-    A S D F    →    7 8 9 E\n\
-    Z X C V    →    A 0 B F";
+        let key_mapping = std::iter::once("Key Mapping:".to_string())
+            .chain(self.keymap.keypad_rows())
+            .collect::<Vec<_>>()
+            .join("\n");
 
         let key_paragraph = Paragraph::new(key_mapping)
             .alignment(Alignment::Center)
-            .block(Block::default().borders(Borders::ALL).title("Keypad"))
-            .style(Style::default().fg(Color::Yellow));
+            .block(self.theme.block("Keypad"))
+            .style(Style::default().fg(self.theme.keypad));
         frame.render_widget(key_paragraph, area);
     }
 
-    /// Fetches and decodes the next instruction from memory.
+    /// Creates a new emulator instance with the provided machine settings and
+    /// frontend options.
+    ///
+    /// This constructor initializes all emulator subsystems including:
+    /// - CHIP-8 machine (memory, registers, timers, display, keypad)
+    /// - Audio subsystem with the configured beep frequency, or an audio pattern
+    ///   player on XO-CHIP, unless sound is disabled
     ///
-    /// This method reads a 16-bit instruction from the current program counter location,
-    /// advances the program counter by 2 bytes, and decodes the raw instruction into
-    /// an executable instruction object.
-    fn fetch_instruction(&mut self) -> anyhow::Result<Box<dyn Instruction>> {
-        if self.state.pc + 1 >= MEM_SIZE {
-            return Err(anyhow!("Program counter out of bounds"));
+    /// The ROM named in the settings is read and loaded into memory.
+    pub fn new(settings: Settings, options: FrontendOptions) -> anyhow::Result<Self> {
+        let beeper = if !options.sound {
+            None
+        } else if settings.platform.has_xochip_extensions() {
+            Some(Beep::with_pattern(options.volume)?)
+        } else {
+            Some(Beep::new(options.beep_frequency, options.volume)?)
+        };
+        let rewind = Rewind::new(options.rewind_frames);
+        let rom_data = std::fs::read(&settings.rom)?;
+        let mut machine = Machine::new(settings);
+        machine.load_rom(&rom_data)?;
+        let debug_panel = options.debug.then(|| DebugPanel::new(&machine));
+
+        Ok(Emulator {
+            machine,
+            options,
+            beeper,
+            debug_panel,
+            message: None,
+            rewind,
+            movie: None,
+            input: InputBackend::detect(),
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+            title: None,
+            renderer: Renderer::default(),
+            scale: None,
+            graphics_mode: GraphicsMode::default(),
+            graphics_scale: None,
+            graphics: None,
+            phosphor: Phosphor::new(Persistence::default(), DEFAULT_DECAY_FRAMES),
+            screenshot_format: ScreenshotFormat::default(),
+            screenshot_scale: DEFAULT_SCREENSHOT_SCALE,
+            screenshot_frame: None,
+            frame: 0,
+            capture_format: CaptureFormat::default(),
+            capture_scale: DEFAULT_CAPTURE_SCALE,
+            capture_audio: false,
+            capture_path: None,
+            recorder: None,
+        })
+    }
+
+    /// Returns the SHA-1 hash of the loaded ROM.
+    pub fn rom_hash(&self) -> &RomHash {
+        self.machine.rom_hash()
+    }
+
+    /// Selects where keyboard input is read from, overriding the backend
+    /// picked by [`InputBackend::detect`].
+    pub fn set_input_backend(&mut self, backend: InputBackend) {
+        self.input = backend;
+    }
+
+    /// Shows `title` above the game screen instead of the ROM's file name.
+    pub fn set_title(&mut self, title: String) {
+        self.title = Some(title);
+    }
+
+    /// Selects how the display is drawn, at a fixed integer `scale`, or scaled
+    /// to fit the terminal if `None`.
+    pub fn set_renderer(&mut self, renderer: Renderer, scale: Option<usize>) {
+        self.renderer = renderer;
+        self.scale = scale;
+    }
+
+    /// Selects the terminal graphics protocol the display is drawn with
+    /// instead of the renderer, at a fixed integer `scale` in bitmap pixels,
+    /// or scaled to fit the terminal if `None`.
+    pub fn set_graphics(&mut self, mode: GraphicsMode, scale: Option<usize>) {
+        self.graphics_mode = mode;
+        self.graphics_scale = scale;
+    }
+
+    /// Keeps pixels visible for a while after they are turned off, fading
+    /// over `decay_frames` frames with [`Persistence::Fade`].
+    pub fn set_persistence(&mut self, persistence: Persistence, decay_frames: u8) {
+        self.phosphor = Phosphor::new(persistence, decay_frames);
+    }
+
+    /// Sets the format and PNG scale of screenshots, and optionally a frame
+    /// after which one is taken automatically.
+    pub fn set_screenshots(
+        &mut self,
+        format: ScreenshotFormat,
+        scale: usize,
+        at_frame: Option<u64>,
+    ) {
+        self.screenshot_format = format;
+        self.screenshot_scale = scale;
+        self.screenshot_frame = at_frame;
+    }
+
+    /// Sets the format of captures started with F11, the scale of all
+    /// captures and whether they write the sound to a WAV file too.
+    pub fn set_capture(&mut self, format: CaptureFormat, scale: usize, audio: bool) {
+        self.capture_format = format;
+        self.capture_scale = scale;
+        self.capture_audio = audio;
+    }
+
+    /// Captures the run from the first frame to `path`, in the format given
+    /// by its extension. Fails if the extension is not a capture format.
+    pub fn capture_to(&mut self, path: PathBuf) -> anyhow::Result<()> {
+        CaptureFormat::from_path(&path)?;
+        self.capture_path = Some(path);
+        Ok(())
+    }
+
+    /// Replaces the default QWERTY keymap.
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
+    }
+
+    /// Sets the colours of the game screen and panels, replacing the default
+    /// theme.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Records the keypad input of every frame into a movie, written to
+    /// `path` when the emulator exits.
+    pub fn record_movie(&mut self, path: PathBuf) {
+        self.movie = Some(MovieSession::Record {
+            movie: Movie::new(&self.machine),
+            path,
+            host_keys: Keypad::new(),
+            host_mask: 0,
+        });
+    }
+
+    /// Replays a movie instead of reading the keyboard. The emulator exits
+    /// after the last frame, and fails at the first frame whose display does
+    /// not match the recording.
+    pub fn replay_movie(&mut self, movie: Movie) -> anyhow::Result<()> {
+        movie.start(&mut self.machine)?;
+        self.movie = Some(MovieSession::Replay { movie, frame: 0 });
+        Ok(())
+    }
+
+    /// The number of frames of the replayed movie played so far and its total
+    /// number of frames, or `None` without a replay.
+    pub fn replay_progress(&self) -> Option<(usize, usize)> {
+        match &self.movie {
+            Some(MovieSession::Replay { movie, frame }) => Some((*frame, movie.frames.len())),
+            _ => None,
         }
-        let high_byte = u16::from(self.state.memory.read(self.state.pc)?);
-        let low_byte = u16::from(self.state.memory.read(self.state.pc + 1)?);
+    }
 
-        // Move the program counter to next instruction
-        self.state.pc += 2;
+    /// Restores the machine from a save state file made with the same ROM,
+    /// forgetting the rewind history that led to the previous state.
+    pub fn load_state_file(&mut self, path: &Path) -> anyhow::Result<()> {
+        let data =
+            std::fs::read(path).map_err(|e| anyhow!("Cannot read {}: {}", path.display(), e))?;
+        self.machine.load_state(&data)?;
+        self.rewind.clear();
+        Ok(())
+    }
 
-        decode((high_byte << 8) | low_byte)
+    /// Writes the machine state to a save state file.
+    pub fn save_state_file(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, self.machine.save_state()?)?;
+        Ok(())
     }
 
-    /// Creates a new emulator instance with the provided configuration settings.
-    ///
-    /// This constructor initializes all emulator subsystems including:
-    /// - CHIP-8 system state (memory, registers, timers, display, input)
-    /// - Audio subsystem with default beep frequency (440 Hz)
-    pub fn new(settings: Settings) -> anyhow::Result<Self> {
-        Ok(Emulator {
-            state: Chip8State::new(settings),
-            beeper: Beep::new(DEFAULT_FREQUENCY)?,
-        })
+    /// Restores the frame before the current one from the rewind history.
+    fn rewind_frame(&mut self) -> anyhow::Result<()> {
+        let message = match self.rewind.pop() {
+            Some(snapshot) => {
+                self.machine.load_state(&snapshot)?;
+                format!("Rewinding ({} frames left)", self.rewind.len())
+            }
+            None => "Rewind history exhausted".to_string(),
+        };
+        self.message = Some((message, Instant::now()));
+        Ok(())
+    }
+
+    /// Path of a numbered save state slot, next to the ROM file: slot 1 of
+    /// `pong.ch8` is `pong.state1`.
+    fn slot_path(&self, slot: u8) -> PathBuf {
+        self.machine
+            .state()
+            .settings
+            .rom
+            .with_extension(format!("state{}", slot))
+    }
+
+    /// Handles the save state hotkeys: F1-F9 load a slot and Shift+F1-F9 save
+    /// to it. Returns false for any other key.
+    fn handle_slot_key(&mut self, key: &KeyEvent) -> bool {
+        let KeyCode::F(slot) = key.code else {
+            return false;
+        };
+        if key.kind != KeyEventKind::Press || !(1..=SAVE_SLOTS).contains(&slot) {
+            return false;
+        }
+        let path = self.slot_path(slot);
+        let message = if key.modifiers.contains(KeyModifiers::SHIFT) {
+            match self.save_state_file(&path) {
+                Ok(()) => format!("Saved slot {}", slot),
+                Err(e) => format!("Save failed: {}", e),
+            }
+        } else if self.movie.is_some() {
+            "Loading states is disabled while a movie is active".to_string()
+        } else {
+            match self.load_state_file(&path) {
+                Ok(()) => format!("Loaded slot {}", slot),
+                Err(e) => format!("Load failed: {}", e),
+            }
+        };
+        self.message = Some((message, Instant::now()));
+        true
+    }
+
+    /// Saves a screenshot of the display next to the ROM and reports where in
+    /// the title bar.
+    fn take_screenshot(&mut self) {
+        let path = screenshot_path(&self.machine.state().settings.rom, self.screenshot_format);
+        let result = save_screenshot(
+            self.machine.display(),
+            &path,
+            self.screenshot_format,
+            &self.theme.palette,
+            self.screenshot_scale,
+        );
+        let message = match result {
+            Ok(()) => format!(
+                "Saved {}",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            Err(e) => format!("Screenshot failed: {:#}", e),
+        };
+        self.message = Some((message, Instant::now()));
+    }
+
+    /// Starts capturing to `path`, or next to the ROM if `None`, reporting
+    /// failures in the title bar.
+    fn start_capture(&mut self, path: Option<PathBuf>) {
+        let settings = &self.machine.state().settings;
+        let path = path
+            .unwrap_or_else(|| timestamped_path(&settings.rom, self.capture_format.extension()));
+        match Recorder::start(
+            &path,
+            settings,
+            &self.options,
+            &self.theme.palette,
+            self.capture_scale,
+            self.capture_audio,
+        ) {
+            Ok(recorder) => {
+                self.recorder = Some(recorder);
+                self.message = Some(("Capturing".to_string(), Instant::now()));
+            }
+            Err(e) => {
+                self.message = Some((format!("Capture failed: {:#}", e), Instant::now()));
+            }
+        }
+    }
+
+    /// Completes the capture in progress, if any, and reports where it went.
+    fn stop_capture(&mut self) {
+        let Some(recorder) = self.recorder.take() else {
+            return;
+        };
+        let name = recorder
+            .path()
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        let message = match recorder.finish() {
+            Ok(()) => format!("Saved {}", name),
+            Err(e) => format!("Capture failed: {:#}", e),
+        };
+        self.message = Some((message, Instant::now()));
+    }
+
+    /// Returns the machine's breakpoints and watchpoints for editing, e.g. to
+    /// set them from the command line before [`Emulator::run`].
+    pub fn debugger_mut(&mut self) -> &mut Debugger {
+        self.machine.debugger_mut()
     }
 
     /// Starts the main emulation loop and runs the CHIP-8 program.
     ///
     /// This method handles the complete emulation lifecycle including:
-    /// - Terminal setup and ROM loading
+    /// - Terminal setup
     /// - Main execution loop with precise timing control
     /// - Input processing and display rendering
     /// - Audio management based on sound timer
     /// - Cleanup and terminal restoration
     ///
     /// # Execution Flow
-    /// 1. **Initialization**: Sets up terminal UI, loads ROM into memory
-    /// 2. **Main Loop**: Runs until Escape key is pressed, each iteration:
+    /// 1. **Initialization**: Sets up terminal UI and starts the global key listener
+    /// 2. **Main Loop**: Runs until Escape key is pressed or the program exits, each iteration:
     ///    - Processes input events
     ///    - Decrements delay and sound timers (60 Hz)
     ///    - Executes calculated number of instructions per frame
@@ -234,65 +836,208 @@ impl Emulator {
     /// - CHIP-8 keypad input is handled via global key listener
     /// - Escape key exits the emulator
     /// - Terminal events are consumed to prevent echo/interference
+    /// - F1-F9 load save state slots 1-9 and Shift+F1-F9 save to them
+    /// - F11 starts and stops capturing a video, see [`Emulator::set_capture`]
+    /// - F12 saves a screenshot, see [`Emulator::set_screenshots`]
+    ///
+    /// # Rewind
+    /// A snapshot of the machine is recorded after every frame, keeping
+    /// [`FrontendOptions::rewind_frames`] frames. While Backspace is held, the
+    /// emulator restores one earlier frame per frame instead of running, so
+    /// the program plays backwards at normal speed.
+    ///
+    /// # Movies
+    /// While recording ([`Emulator::record_movie`]) or replaying
+    /// ([`Emulator::replay_movie`]) a movie, keys reach the machine only at
+    /// frame boundaries, and rewinding and loading save states are disabled
+    /// so that the recorded frames stay reproducible.
+    ///
+    /// # Debug Mode
+    /// With [`FrontendOptions::debug`] set, the emulator starts paused with the
+    /// debugger panel shown next to the game screen. Timers and sound are
+    /// frozen while paused, and breakpoints or instruction errors pause
+    /// execution instead of exiting.
     ///
     /// # Audio Management
     /// - Sound timer > 0: Continuous beep tone plays
     /// - Sound timer = 0: Audio output stops
-    /// - Uses 440 Hz sine wave for authentic CHIP-8 sound
+    /// - Uses a sine wave at [`FrontendOptions::beep_frequency`] (440 Hz by default)
+    /// - XO-CHIP plays the programmed audio pattern at the programmed pitch
+    /// - Nothing plays when [`FrontendOptions::sound`] is off
     pub fn run(&mut self) -> anyhow::Result<()> {
-        let frame_duration = Duration::from_secs_f64(1.0 / self.state.settings.frame_rate as f64);
-        let instructions_per_frame = self.state.settings.ips / self.state.settings.frame_rate;
-        let rom_stem: String = self
-            .state
-            .settings
-            .rom
-            .file_stem()
-            .map(|s| s.to_string_lossy().into_owned())
-            .unwrap_or_else(|| "Unknown ROM".to_string());
-        let rom_data = std::fs::read(self.state.settings.rom.clone())?;
+        let settings = &self.machine.state().settings;
+        let frame_duration = Duration::from_secs_f64(1.0 / settings.frame_rate.max(1) as f64);
+        let rom_name: String = self.title.clone().unwrap_or_else(|| {
+            settings
+                .rom
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "Unknown ROM".to_string())
+        });
 
         enable_raw_mode()?;
+        // Query the terminal before the input backend starts reading its
+        // answers as key presses
+        self.graphics = self
+            .graphics_mode
+            .protocol()
+            .map(|protocol| GraphicsOutput::new(protocol, self.graphics_scale));
         let stdout = std::io::stdout();
         let backend = CrosstermBackend::new(stdout);
         let mut terminal = Terminal::new(backend)?;
         terminal.clear()?;
 
-        self.state.memory.load_rom(&rom_data)?;
+        // Key presses reach the machine's keypad directly, except in movie
+        // sessions where the recorder forwards them once per frame, or the
+        // replay ignores them
+        let keypad = match &self.movie {
+            Some(MovieSession::Record { host_keys, .. }) => host_keys.clone(),
+            Some(MovieSession::Replay { .. }) => Keypad::new(),
+            None => self.machine.keypad(),
+        };
+        let mut keys = KeyListener::start(self.input, self.keymap.clone(), keypad)?;
+        if let Some(path) = self.capture_path.take() {
+            self.start_capture(Some(path));
+        }
+
+        // A replay that desyncs stops the loop, and fails once the terminal
+        // is restored
+        let mut movie_error = None;
         'mainloop: loop {
             let frame_start = Instant::now();
 
-            if self.state.keypad.is_escape_pressed() {
+            if keys.is_escape_pressed()
+                || self.machine.is_halted()
+                || self.movie.as_ref().is_some_and(MovieSession::is_finished)
+                || movie_error.is_some()
+            {
+                if let Some(graphics) = &mut self.graphics {
+                    graphics.clear(terminal.backend_mut())?;
+                }
                 terminal.clear()?;
                 break 'mainloop;
             }
 
-            // Consume and discard any crossterm events to prevent echoing
+            // Consume crossterm events to prevent echoing, feeding them to the
+            // terminal input backend, handling save state hotkeys and passing
+            // other key presses to the debugger panel in debug mode
             while event::poll(Duration::ZERO)? {
-                let _ = event::read()?;
+                let Event::Key(key) = event::read()? else {
+                    continue;
+                };
+                keys.handle_event(&key);
+                if self.handle_slot_key(&key) {
+                    continue;
+                }
+                if key.code == KeyCode::F(11) && key.kind == KeyEventKind::Press {
+                    if self.recorder.is_some() {
+                        self.stop_capture();
+                    } else {
+                        self.start_capture(None);
+                    }
+                    continue;
+                }
+                if key.code == KeyCode::F(12) && key.kind == KeyEventKind::Press {
+                    self.take_screenshot();
+                    continue;
+                }
+                if let Some(debug_panel) = &mut self.debug_panel {
+                    debug_panel.handle_key(key, &mut self.machine);
+                }
             }
+            keys.release_expired();
 
             terminal.try_draw(|frame| -> std::io::Result<()> {
-                self.state.delay_timer = self.state.delay_timer.saturating_sub(1);
-                self.state.sound_timer = self.state.sound_timer.saturating_sub(1);
-
-                if self.state.sound_timer == 0 {
-                    self.beeper.off();
+                let rewinding = keys.is_rewind_pressed()
+                    && self.movie.is_none()
+                    && !self
+                        .debug_panel
+                        .as_ref()
+                        .is_some_and(DebugPanel::is_prompting);
+                let paused =
+                    rewinding || self.debug_panel.as_ref().is_some_and(DebugPanel::is_paused);
+                if rewinding {
+                    self.rewind_frame()
+                        .map_err(|e| std::io::Error::other(e.to_string()))?;
+                } else if let Some(movie) = &mut self.movie {
+                    if let Err(e) = movie.run_frame(&mut self.machine) {
+                        movie_error = Some(e);
+                        return Ok(());
+                    }
                 } else {
-                    self.beeper.on();
+                    if !paused {
+                        self.machine.tick_timers();
+                    }
+                    match &mut self.debug_panel {
+                        Some(debug_panel) => debug_panel.run_frame(&mut self.machine),
+                        None => {
+                            self.machine
+                                .run_frame()
+                                .map_err(|e| std::io::Error::other(e.to_string()))?;
+                        }
+                    }
+                    if !paused && self.rewind.is_enabled() {
+                        // A missing frame would break the chain of deltas, so
+                        // the history restarts from the next snapshot
+                        match self.machine.save_state() {
+                            Ok(snapshot) => self.rewind.push(snapshot),
+                            Err(e) => {
+                                self.rewind.clear();
+                                self.message = Some((
+                                    format!("Rewind history cleared: {:#}", e),
+                                    Instant::now(),
+                                ));
+                            }
+                        }
+                    }
                 }
 
-                for _ in 0..=instructions_per_frame {
-                    let instruction = self
-                        .fetch_instruction()
-                        .map_err(|e| std::io::Error::other(e.to_string()))?;
-                    instruction
-                        .execute(&mut self.state)
-                        .map_err(|e| std::io::Error::other(e.to_string()))?;
+                let state = self.machine.state();
+                if let Some(beeper) = &mut self.beeper {
+                    beeper.set_pattern(state.audio_pattern, state.pitch);
+                    if self.machine.is_sound_active() && !paused {
+                        beeper.on();
+                    } else {
+                        beeper.off();
+                    }
                 }
 
-                self.draw(frame, frame.area(), &rom_stem);
+                if !paused {
+                    self.frame += 1;
+                }
+                if self
+                    .screenshot_frame
+                    .is_some_and(|frame| frame <= self.frame)
+                {
+                    self.screenshot_frame = None;
+                    self.take_screenshot();
+                }
+                if let Some(recorder) = &mut self.recorder
+                    && let Err(e) = recorder.add_frame(self.machine.state())
+                {
+                    self.recorder = None;
+                    self.message = Some((format!("Capture failed: {:#}", e), Instant::now()));
+                }
+
+                self.phosphor.update(self.machine.display());
+                self.draw(frame, frame.area(), &rom_name);
                 Ok(())
             })?;
+            if let Some(graphics) = &mut self.graphics {
+                let display = self.machine.display();
+                let area = ratatui::layout::Rect::from((Default::default(), terminal.size()?));
+                graphics.draw(
+                    terminal.backend_mut(),
+                    area,
+                    display.width(),
+                    display.height(),
+                    self.theme.palette[0],
+                    |x, y| {
+                        let (index, brightness) = self.phosphor.pixel(x, y);
+                        self.theme.shade(index, brightness)
+                    },
+                )?;
+            }
 
             let elapsed = frame_start.elapsed();
             if elapsed < frame_duration {
@@ -300,7 +1045,13 @@ impl Emulator {
             }
         }
         disable_raw_mode()?;
+        if let Some(recorder) = self.recorder.take() {
+            recorder.finish()?;
+        }
 
-        Ok(())
+        if let Some(MovieSession::Record { movie, path, .. }) = &self.movie {
+            movie.save(path)?;
+        }
+        movie_error.map_or(Ok(()), Err)
     }
 }
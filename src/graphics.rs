@@ -0,0 +1,404 @@
+//! Terminal Graphics Output
+//!
+//! Draws the display as a bitmap with the [Kitty graphics protocol] or as
+//! [Sixel] graphics instead of text, in terminals that support either. The
+//! protocol is detected by querying the terminal, and without one the text
+//! [`Renderer`](crate::render::Renderer)s are used.
+//!
+//! The bitmap is scaled by an integer factor, so that every CHIP-8 pixel
+//! becomes a square of bitmap pixels in the theme's palette colours, and it is
+//! only sent again when the display, its position or the terminal size
+//! changes.
+//!
+//! [Kitty graphics protocol]: https://sw.kovidgoyal.net/kitty/graphics-protocol/
+//! [Sixel]: https://vt100.net/docs/vt3xx-gp/chapter14.html
+
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+use ratatui::{layout::Rect, style::Color};
+use rustix::event::{PollFd, PollFlags, Timespec, poll};
+
+use crate::theme::{distance, rgb};
+
+/// Which graphics protocol to draw the display with.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum GraphicsMode {
+    /// Draw the display as text.
+    #[default]
+    Off,
+
+    /// Use Kitty graphics or Sixel if the terminal supports it, and text
+    /// otherwise.
+    Auto,
+
+    /// Always use the Kitty graphics protocol.
+    Kitty,
+
+    /// Always use Sixel graphics.
+    Sixel,
+}
+
+/// A terminal graphics protocol.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    Kitty,
+    Sixel,
+}
+
+/// How long to wait for the terminal to answer the capability query.
+const QUERY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Image id of the display in the Kitty graphics protocol.
+const KITTY_IMAGE_ID: u32 = 8;
+
+/// Maximum payload of one Kitty graphics escape sequence.
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// Cell size assumed when the terminal does not report its size in pixels.
+const DEFAULT_CELL_SIZE: (u16, u16) = (10, 20);
+
+impl GraphicsMode {
+    /// The protocol to use: the one requested, or for [`GraphicsMode::Auto`]
+    /// the one the terminal reports supporting, if any. The terminal must be
+    /// in raw mode.
+    pub fn protocol(self) -> Option<GraphicsProtocol> {
+        match self {
+            GraphicsMode::Off => None,
+            GraphicsMode::Auto => GraphicsProtocol::detect(),
+            GraphicsMode::Kitty => Some(GraphicsProtocol::Kitty),
+            GraphicsMode::Sixel => Some(GraphicsProtocol::Sixel),
+        }
+    }
+}
+
+impl GraphicsProtocol {
+    /// Asks the terminal which protocol it supports: a Kitty graphics query
+    /// that only Kitty-compatible terminals answer, followed by a request for
+    /// the primary device attributes, which all terminals answer and which
+    /// list Sixel support as attribute 4.
+    ///
+    /// Reading the answer stops after [`QUERY_TIMEOUT`], so terminals that
+    /// do not answer are treated as supporting neither protocol.
+    pub fn detect() -> Option<Self> {
+        let mut tty = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/tty")
+            .ok()?;
+        tty.write_all(b"\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\\x1b[c")
+            .ok()?;
+        tty.flush().ok()?;
+
+        let deadline = Instant::now() + QUERY_TIMEOUT;
+        let mut response = Vec::new();
+        let mut byte = [0];
+        while is_readable(&tty, deadline.saturating_duration_since(Instant::now()))
+            && tty.read(&mut byte).is_ok_and(|read| read == 1)
+        {
+            response.push(byte[0]);
+            if byte[0] == b'c' && response.windows(3).any(|w| w == b"\x1b[?") {
+                break;
+            }
+        }
+        let response = String::from_utf8_lossy(&response);
+
+        if response.contains("\x1b_Gi=31;OK") {
+            return Some(GraphicsProtocol::Kitty);
+        }
+        let attributes = response.split("\x1b[?").nth(1)?.strip_suffix('c')?;
+        attributes
+            .split(';')
+            .any(|attribute| attribute == "4")
+            .then_some(GraphicsProtocol::Sixel)
+    }
+}
+
+/// Waits up to `timeout` for input to read from `tty`.
+fn is_readable(tty: &File, timeout: Duration) -> bool {
+    let Ok(timeout) = Timespec::try_from(timeout) else {
+        return false;
+    };
+    let mut fds = [PollFd::new(tty, PollFlags::IN)];
+    poll(&mut fds, Some(&timeout)).is_ok_and(|ready| ready > 0)
+}
+
+/// Draws the display as a bitmap into an area of the terminal.
+pub struct GraphicsOutput {
+    protocol: GraphicsProtocol,
+    /// Bitmap pixels per display pixel, or `None` to fit the area.
+    scale: Option<usize>,
+    /// Size of a terminal cell in pixels.
+    cell_size: (u16, u16),
+    /// Where the next image goes: the area, in cells, and the scale.
+    placement: Option<(Rect, usize)>,
+    /// What was sent last: the placement, the terminal size and the pixels.
+    sent: Option<(Rect, usize, Rect, Vec<Color>)>,
+}
+
+impl GraphicsOutput {
+    /// Prepares drawing with `protocol` at `scale`, or fitting the screen if
+    /// `None`.
+    pub fn new(protocol: GraphicsProtocol, scale: Option<usize>) -> Self {
+        let cell_size = crossterm::terminal::window_size()
+            .ok()
+            .filter(|size| size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0)
+            .map_or(DEFAULT_CELL_SIZE, |size| {
+                (size.width / size.columns, size.height / size.rows)
+            });
+        GraphicsOutput {
+            protocol,
+            scale,
+            cell_size,
+            placement: None,
+            sent: None,
+        }
+    }
+
+    /// The configured scale, or the largest, at least 1, at which a display of
+    /// `width`×`height` pixels fits into `columns`×`rows` cells.
+    pub fn scale(&self, width: usize, height: usize, columns: u16, rows: u16) -> usize {
+        self.scale.unwrap_or_else(|| {
+            let (cell_width, cell_height) = self.cell_size;
+            let fit_x = usize::from(columns) * usize::from(cell_width) / width;
+            let fit_y = usize::from(rows) * usize::from(cell_height) / height;
+            fit_x.min(fit_y).max(1)
+        })
+    }
+
+    /// Terminal columns and rows covered by a display of `width`×`height`
+    /// pixels at `scale`.
+    pub fn size(&self, width: usize, height: usize, scale: usize) -> (u16, u16) {
+        let (cell_width, cell_height) = self.cell_size;
+        (
+            (width * scale).div_ceil(usize::from(cell_width)) as u16,
+            (height * scale).div_ceil(usize::from(cell_height)) as u16,
+        )
+    }
+
+    /// Sets where the next frame is drawn.
+    pub fn place(&mut self, area: Rect, scale: usize) {
+        self.placement = Some((area, scale));
+    }
+
+    /// Sends the display to the terminal at the last placement, unless the
+    /// same image was already sent there. `pixel` returns the colour of a
+    /// display pixel, and the terminal's default colour is drawn as black if
+    /// it is the `background` and white otherwise.
+    pub fn draw(
+        &mut self,
+        out: &mut impl Write,
+        terminal: Rect,
+        width: usize,
+        height: usize,
+        background: Color,
+        pixel: impl Fn(usize, usize) -> Color,
+    ) -> std::io::Result<()> {
+        let Some((area, scale)) = self.placement else {
+            return Ok(());
+        };
+        let pixels: Vec<Color> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| pixel(x, y))
+            .collect();
+        let frame = (area, scale, terminal, pixels);
+        if self.sent.as_ref() == Some(&frame) {
+            return Ok(());
+        }
+
+        let image = Bitmap::new(width, height, scale, background, &frame.3);
+        let data = match self.protocol {
+            GraphicsProtocol::Kitty => kitty_image(&image),
+            GraphicsProtocol::Sixel => sixel_image(&image),
+        };
+        crossterm::queue!(out, crossterm::cursor::MoveTo(area.x, area.y))?;
+        out.write_all(data.as_bytes())?;
+        out.flush()?;
+        self.sent = Some(frame);
+        Ok(())
+    }
+
+    /// Removes the image from the terminal.
+    pub fn clear(&mut self, out: &mut impl Write) -> std::io::Result<()> {
+        if self.protocol == GraphicsProtocol::Kitty {
+            write!(out, "\x1b_Ga=d,d=I,i={},q=2\x1b\\", KITTY_IMAGE_ID)?;
+            out.flush()?;
+        }
+        self.sent = None;
+        Ok(())
+    }
+}
+
+/// A display as indices into a palette of the RGB colours it uses, scaled
+/// when encoded.
+struct Bitmap {
+    width: usize,
+    height: usize,
+    scale: usize,
+    palette: Vec<(u8, u8, u8)>,
+    pixels: Vec<u8>,
+}
+
+impl Bitmap {
+    /// Converts the colours of a display to RGB, with the terminal's default
+    /// colour taken as black for the background and white otherwise. Colours
+    /// beyond the 256 a Sixel palette holds are replaced by the nearest one.
+    fn new(width: usize, height: usize, scale: usize, background: Color, colors: &[Color]) -> Self {
+        let mut palette = Vec::new();
+        let pixels = colors
+            .iter()
+            .map(|&color| {
+                let rgb = rgb(color).unwrap_or(if color == background {
+                    (0, 0, 0)
+                } else {
+                    (255, 255, 255)
+                });
+                if let Some(idx) = palette.iter().position(|&seen| seen == rgb) {
+                    return idx as u8;
+                }
+                if palette.len() < 256 {
+                    palette.push(rgb);
+                    return (palette.len() - 1) as u8;
+                }
+                (0..palette.len())
+                    .min_by_key(|&idx| distance(palette[idx], rgb))
+                    .unwrap_or(0) as u8
+            })
+            .collect();
+        Bitmap {
+            width,
+            height,
+            scale,
+            palette,
+            pixels,
+        }
+    }
+
+    /// Palette index of the scaled pixel at `(x, y)`.
+    fn get(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y / self.scale * self.width + x / self.scale]
+    }
+
+    fn scaled_width(&self) -> usize {
+        self.width * self.scale
+    }
+
+    fn scaled_height(&self) -> usize {
+        self.height * self.scale
+    }
+}
+
+/// Encodes a bitmap as Kitty graphics escape sequences transmitting and
+/// displaying 24-bit RGB data, replacing the previous frame.
+fn kitty_image(image: &Bitmap) -> String {
+    let (width, height) = (image.scaled_width(), image.scaled_height());
+    let mut data = Vec::with_capacity(width * height * 3);
+    for y in 0..height {
+        for x in 0..width {
+            let (r, g, b) = image.palette[usize::from(image.get(x, y))];
+            data.extend_from_slice(&[r, g, b]);
+        }
+    }
+    let payload = base64(&data);
+    let chunks: Vec<&str> = payload
+        .as_bytes()
+        .chunks(KITTY_CHUNK_SIZE)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+
+    let mut out = String::with_capacity(payload.len() + chunks.len() * 16 + 64);
+    for (idx, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(idx + 1 < chunks.len());
+        if idx == 0 {
+            let _ = write!(
+                out,
+                "\x1b_Ga=T,f=24,s={},v={},i={},p=1,q=2,C=1,m={};{}\x1b\\",
+                width, height, KITTY_IMAGE_ID, more, chunk
+            );
+        } else {
+            let _ = write!(out, "\x1b_Gm={};{}\x1b\\", more, chunk);
+        }
+    }
+    out
+}
+
+/// Encodes a bitmap as a Sixel image with one colour register per palette
+/// colour.
+fn sixel_image(image: &Bitmap) -> String {
+    let (width, height) = (image.scaled_width(), image.scaled_height());
+    let mut out = format!("\x1bP0;1;0q\"1;1;{};{}", width, height);
+    for (idx, &(r, g, b)) in image.palette.iter().enumerate() {
+        let percent = |channel: u8| u32::from(channel) * 100 / 255;
+        let _ = write!(
+            out,
+            "#{};2;{};{};{}",
+            idx,
+            percent(r),
+            percent(g),
+            percent(b)
+        );
+    }
+    // Each band of six rows is drawn once per colour, with run-length encoded
+    // columns of the rows in that colour
+    for band in (0..height).step_by(6) {
+        for color in 0..image.palette.len() {
+            let columns: Vec<u8> = (0..width)
+                .map(|x| {
+                    (0..6)
+                        .filter(|dy| {
+                            band + dy < height && usize::from(image.get(x, band + dy)) == color
+                        })
+                        .fold(0, |bits, dy| bits | 1 << dy)
+                })
+                .collect();
+            if columns.iter().all(|&bits| bits == 0) {
+                continue;
+            }
+            let _ = write!(out, "#{}", color);
+            let mut x = 0;
+            while x < columns.len() {
+                let run = columns[x..]
+                    .iter()
+                    .take_while(|&&c| c == columns[x])
+                    .count();
+                let sixel = char::from(0x3F + columns[x]);
+                if run > 3 {
+                    let _ = write!(out, "!{}{}", run, sixel);
+                } else {
+                    out.extend(std::iter::repeat_n(sixel, run));
+                }
+                x += run;
+            }
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+/// Encodes bytes as standard base64 with padding.
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                out.push(char::from(
+                    ALPHABET[(bits >> (18 - 6 * idx) & 0x3F) as usize],
+                ));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
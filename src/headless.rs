@@ -0,0 +1,156 @@
+//! Headless Batch Runs
+//!
+//! Runs a ROM on a [`Machine`] for a fixed number of frames, as fast as
+//! possible and without any terminal, audio or input device, then dumps the
+//! final display. Key presses are scripted per frame with [`KeyPress`]. Test
+//! suites compare the dump against an expected output file to check that a
+//! ROM still passes. The top of the IBM logo in the ASCII dump:
+//!
+//! ```text
+//! ............########.#########...#####.........#####..#.#.......
+//! ......................................................#.#.......
+//! ............########.###########.######.......######...#........
+//! ```
+//!
+//! See [`DumpFormat`] for the available dumps.
+
+use std::str::FromStr;
+
+use anyhow::{Context, anyhow, bail};
+
+use crate::machine::Machine;
+use crate::movie::display_hash;
+use crate::state::{FrameBuffer, Key};
+
+/// Characters of the ASCII dump for each pixel colour index: unlit, plane 1,
+/// plane 2 and both planes.
+const ASCII_PIXELS: [char; 4] = ['.', '#', '+', '@'];
+
+/// How the final display is dumped.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "frontend", derive(clap::ValueEnum))]
+pub enum DumpFormat {
+    /// One line per display row, with `.` for unlit pixels and `#` for lit
+    /// ones. XO-CHIP pixels lit in the second plane are `+`, and `@` in both.
+    #[default]
+    Ascii,
+
+    /// The display hash also stored in input movies, in hexadecimal.
+    Hash,
+}
+
+impl DumpFormat {
+    /// Dumps `display` in this format, ending with a newline.
+    pub fn dump(self, display: &FrameBuffer) -> String {
+        match self {
+            DumpFormat::Ascii => {
+                let mut dump = String::new();
+                for y in 0..display.height() {
+                    dump.extend(
+                        (0..display.width())
+                            .map(|x| ASCII_PIXELS[usize::from(display.color(x, y) & 0b11)]),
+                    );
+                    dump.push('\n');
+                }
+                dump
+            }
+            DumpFormat::Hash => format!("{:016x}\n", display_hash(display)),
+        }
+    }
+}
+
+/// A key held down for a range of frames, written `KEY@FRAME` to hold it
+/// during one frame or `KEY@FIRST-LAST` to hold it from frame `FIRST` to frame
+/// `LAST`, inclusive. Keys are hexadecimal digits and frames count from 0.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct KeyPress {
+    pub key: Key,
+    pub first: u64,
+    pub last: u64,
+}
+
+impl KeyPress {
+    /// Returns true if the key is held during `frame`.
+    pub fn is_held(&self, frame: u64) -> bool {
+        (self.first..=self.last).contains(&frame)
+    }
+}
+
+impl FromStr for KeyPress {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (key, frames) = s
+            .split_once('@')
+            .ok_or_else(|| anyhow!("Expected KEY@FRAME or KEY@FIRST-LAST, found {}", s))?;
+        let key = u8::from_str_radix(key, 16)
+            .ok()
+            .filter(|&key| key < 16)
+            .ok_or_else(|| anyhow!("Invalid key {}, expected a hexadecimal digit", key))?;
+        let parse_frame = |frame: &str| {
+            frame
+                .parse::<u64>()
+                .with_context(|| format!("Invalid frame {} in {}", frame, s))
+        };
+        let (first, last) = match frames.split_once('-') {
+            Some((first, last)) => (parse_frame(first)?, parse_frame(last)?),
+            None => {
+                let frame = parse_frame(frames)?;
+                (frame, frame)
+            }
+        };
+        if last < first {
+            bail!("The key press {} ends before it starts", s);
+        }
+        Ok(KeyPress {
+            key: Key::from_index(key)?,
+            first,
+            last,
+        })
+    }
+}
+
+/// What happened during a batch run.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BatchReport {
+    /// Frames run, fewer than asked for if the program exited.
+    pub frames: u64,
+    /// Frames that ended with the sound timer running.
+    pub sound_frames: u64,
+    /// Whether the program executed the SUPER-CHIP exit instruction.
+    pub halted: bool,
+}
+
+/// Runs up to `frames` frames on a machine with a ROM loaded, holding the keys
+/// of `presses` during their frames. Each frame sets the keypad, ticks the
+/// timers and runs the frame's instructions, like recorded movies do.
+pub fn run_batch(
+    machine: &mut Machine,
+    frames: u64,
+    presses: &[KeyPress],
+) -> anyhow::Result<BatchReport> {
+    let keypad = machine.keypad();
+    let mut report = BatchReport {
+        frames: 0,
+        sound_frames: 0,
+        halted: false,
+    };
+    for frame in 0..frames {
+        if machine.is_halted() {
+            break;
+        }
+        let keys = presses
+            .iter()
+            .filter(|press| press.is_held(frame))
+            .fold(0u16, |keys, press| keys | 1 << press.key as u16);
+        keypad.set_pressed_mask(keys);
+        machine.tick_timers();
+        machine.run_frame()?;
+        report.frames += 1;
+        if machine.is_sound_active() {
+            report.sound_frames += 1;
+        }
+    }
+    report.halted = machine.is_halted();
+    Ok(report)
+}
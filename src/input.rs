@@ -0,0 +1,541 @@
+//! Keyboard Input
+//!
+//! This module connects the host keyboard to the CHIP-8 keypad for the terminal
+//! frontend. It owns the key listener, which reads either global `rdev` events
+//! or the terminal's own key events, and the [`Keymap`] from physical keys to
+//! CHIP-8 keys.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail};
+use crossterm::event::{
+    KeyCode, KeyEvent, KeyEventKind, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+    PushKeyboardEnhancementFlags,
+};
+use crossterm::execute;
+use crossterm::terminal::supports_keyboard_enhancement;
+use rdev::{EventType, Key as RdevKey, listen};
+
+use crate::config::{KeyNames, KeymapConfig};
+use crate::state::{Key, Keypad};
+
+impl Key {
+    /// Converts an rdev keyboard key to the corresponding CHIP-8 key.
+    ///
+    /// This function implements the keyboard mapping from modern QWERTY layout
+    /// to the CHIP-8 hexadecimal keypad. The mapping follows a common convention
+    /// used by many CHIP-8 emulators for ergonomic key placement.
+    ///
+    /// # Keyboard Mapping
+    /// | Keyboard | CHIP-8 |
+    /// |----------|--------|
+    /// | 1        | 1      |
+    /// | 2        | 2      |
+    /// | 3        | 3      |
+    /// | 4        | C      |
+    /// | Q        | 4      |
+    /// | W        | 5      |
+    /// | E        | 6      |
+    /// | R        | D      |
+    /// | A        | 7      |
+    /// | S        | 8      |
+    /// | D        | 9      |
+    /// | F        | E      |
+    /// | Z        | A      |
+    /// | X        | 0      |
+    /// | C        | B      |
+    /// | V        | F      |
+    pub fn from_rdev(key: rdev::Key) -> Option<Key> {
+        match key {
+            RdevKey::Num1 => Some(Key::Key1),
+            RdevKey::Num2 => Some(Key::Key2),
+            RdevKey::Num3 => Some(Key::Key3),
+            RdevKey::Num4 => Some(Key::KeyC),
+            RdevKey::KeyQ => Some(Key::Key4),
+            RdevKey::KeyW => Some(Key::Key5),
+            RdevKey::KeyE => Some(Key::Key6),
+            RdevKey::KeyR => Some(Key::KeyD),
+            RdevKey::KeyA => Some(Key::Key7),
+            RdevKey::KeyS => Some(Key::Key8),
+            RdevKey::KeyD => Some(Key::Key9),
+            RdevKey::KeyF => Some(Key::KeyE),
+            RdevKey::KeyZ => Some(Key::KeyA),
+            RdevKey::KeyX => Some(Key::Key0),
+            RdevKey::KeyC => Some(Key::KeyB),
+            RdevKey::KeyV => Some(Key::KeyF),
+            _ => None,
+        }
+    }
+}
+
+/// Host keys that can be bound in a keymap, by their name in the config file.
+/// Single characters name the key typing that character without Shift.
+const HOST_KEYS: [(&str, RdevKey); 54] = [
+    ("1", RdevKey::Num1),
+    ("2", RdevKey::Num2),
+    ("3", RdevKey::Num3),
+    ("4", RdevKey::Num4),
+    ("5", RdevKey::Num5),
+    ("6", RdevKey::Num6),
+    ("7", RdevKey::Num7),
+    ("8", RdevKey::Num8),
+    ("9", RdevKey::Num9),
+    ("0", RdevKey::Num0),
+    ("a", RdevKey::KeyA),
+    ("b", RdevKey::KeyB),
+    ("c", RdevKey::KeyC),
+    ("d", RdevKey::KeyD),
+    ("e", RdevKey::KeyE),
+    ("f", RdevKey::KeyF),
+    ("g", RdevKey::KeyG),
+    ("h", RdevKey::KeyH),
+    ("i", RdevKey::KeyI),
+    ("j", RdevKey::KeyJ),
+    ("k", RdevKey::KeyK),
+    ("l", RdevKey::KeyL),
+    ("m", RdevKey::KeyM),
+    ("n", RdevKey::KeyN),
+    ("o", RdevKey::KeyO),
+    ("p", RdevKey::KeyP),
+    ("q", RdevKey::KeyQ),
+    ("r", RdevKey::KeyR),
+    ("s", RdevKey::KeyS),
+    ("t", RdevKey::KeyT),
+    ("u", RdevKey::KeyU),
+    ("v", RdevKey::KeyV),
+    ("w", RdevKey::KeyW),
+    ("x", RdevKey::KeyX),
+    ("y", RdevKey::KeyY),
+    ("z", RdevKey::KeyZ),
+    ("-", RdevKey::Minus),
+    ("=", RdevKey::Equal),
+    ("[", RdevKey::LeftBracket),
+    ("]", RdevKey::RightBracket),
+    (";", RdevKey::SemiColon),
+    ("'", RdevKey::Quote),
+    ("\\", RdevKey::BackSlash),
+    (",", RdevKey::Comma),
+    (".", RdevKey::Dot),
+    ("/", RdevKey::Slash),
+    ("`", RdevKey::BackQuote),
+    ("up", RdevKey::UpArrow),
+    ("down", RdevKey::DownArrow),
+    ("left", RdevKey::LeftArrow),
+    ("right", RdevKey::RightArrow),
+    ("space", RdevKey::Space),
+    ("enter", RdevKey::Return),
+    ("tab", RdevKey::Tab),
+];
+
+/// Looks up a host key by its name in [`HOST_KEYS`], ignoring case.
+fn host_key(name: &str) -> Option<RdevKey> {
+    let name = name.to_ascii_lowercase();
+    HOST_KEYS
+        .iter()
+        .find(|(host_name, _)| *host_name == name)
+        .map(|&(_, key)| key)
+}
+
+/// Short label of a host key for the keypad panel.
+fn host_key_label(key: RdevKey) -> String {
+    let name = HOST_KEYS
+        .iter()
+        .find(|&&(_, host)| host == key)
+        .map_or("?", |&(name, _)| name);
+    match name {
+        "up" => "↑".to_string(),
+        "down" => "↓".to_string(),
+        "left" => "←".to_string(),
+        "right" => "→".to_string(),
+        "space" => "Spc".to_string(),
+        "enter" => "Ret".to_string(),
+        "tab" => "Tab".to_string(),
+        _ => name.to_ascii_uppercase(),
+    }
+}
+
+/// Mapping from host keys to CHIP-8 keys.
+///
+/// Several host keys may map to the same CHIP-8 key, for example both `W` and
+/// the up arrow. The default keymap is the QWERTY layout of
+/// [`Key::from_rdev`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Keymap {
+    /// Bindings in the order they were made, so that the first host key bound
+    /// to a CHIP-8 key is listed first on the keypad panel.
+    bindings: Vec<(RdevKey, Key)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap {
+            bindings: HOST_KEYS
+                .iter()
+                .filter_map(|&(_, host)| Key::from_rdev(host).map(|key| (host, key)))
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    /// Returns the CHIP-8 key a host key is bound to.
+    pub fn key(&self, host: RdevKey) -> Option<Key> {
+        self.bindings
+            .iter()
+            .find(|&&(bound, _)| bound == host)
+            .map(|&(_, key)| key)
+    }
+
+    /// Returns the host keys bound to a CHIP-8 key.
+    pub fn host_keys(&self, key: Key) -> impl Iterator<Item = RdevKey> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |&&(_, bound)| bound == key)
+            .map(|&(host, _)| host)
+    }
+
+    /// Rebinds the CHIP-8 keys listed in a keymap config section. Each listed
+    /// CHIP-8 key loses its previous host keys, and each host key listed is
+    /// taken away from the CHIP-8 key it was bound to. Unlisted CHIP-8 keys
+    /// keep their bindings.
+    pub fn apply(&mut self, config: &KeymapConfig) -> anyhow::Result<()> {
+        let mut rebound = Vec::new();
+        for (name, hosts) in config {
+            let key = parse_key_name(name)?;
+            let hosts = match hosts {
+                KeyNames::One(host) => std::slice::from_ref(host),
+                KeyNames::Many(hosts) => hosts.as_slice(),
+            };
+            for host_name in hosts {
+                let host = host_key(host_name).ok_or_else(|| {
+                    anyhow!(
+                        "Unknown host key '{}' for CHIP-8 key {} (expected a letter, digit, \
+                         punctuation, up, down, left, right, space, enter or tab)",
+                        host_name,
+                        name
+                    )
+                })?;
+                if let Some(&(_, other)) = rebound.iter().find(|&&(bound, _)| bound == host) {
+                    bail!(
+                        "Host key '{}' is bound to both CHIP-8 keys {:X} and {}",
+                        host_name,
+                        other as u8,
+                        name
+                    );
+                }
+                rebound.push((host, key));
+            }
+        }
+
+        self.bindings.retain(|&(host, key)| {
+            !rebound
+                .iter()
+                .any(|&(new_host, new_key)| new_host == host || new_key == key)
+        });
+        self.bindings.extend(rebound);
+        Ok(())
+    }
+
+    /// Lists every binding as a keymap config section, which rebuilds this
+    /// keymap when applied to any other.
+    pub fn to_config(&self) -> KeymapConfig {
+        (0..16)
+            .filter_map(|index| Key::from_index(index).ok())
+            .map(|key| {
+                let hosts = self
+                    .host_keys(key)
+                    .filter_map(|host| {
+                        HOST_KEYS
+                            .iter()
+                            .find(|&&(_, bound)| bound == host)
+                            .map(|&(name, _)| name.to_string())
+                    })
+                    .collect();
+                (format!("{:x}", key as u8), KeyNames::Many(hosts))
+            })
+            .collect()
+    }
+
+    /// Formats the keypad panel text: one row per row of the CHIP-8 keypad,
+    /// each key followed by the host keys bound to it.
+    pub fn keypad_rows(&self) -> Vec<String> {
+        const KEYPAD_ROWS: [[Key; 4]; 4] = [
+            [Key::Key1, Key::Key2, Key::Key3, Key::KeyC],
+            [Key::Key4, Key::Key5, Key::Key6, Key::KeyD],
+            [Key::Key7, Key::Key8, Key::Key9, Key::KeyE],
+            [Key::KeyA, Key::Key0, Key::KeyB, Key::KeyF],
+        ];
+
+        let label = |key: Key| {
+            let hosts: Vec<String> = self.host_keys(key).map(host_key_label).collect();
+            if hosts.is_empty() {
+                "-".to_string()
+            } else {
+                hosts.join("/")
+            }
+        };
+        let width = KEYPAD_ROWS
+            .iter()
+            .flatten()
+            .map(|&key| label(key).chars().count())
+            .max()
+            .unwrap_or(1);
+        KEYPAD_ROWS
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&key| format!("{:X}:{:<width$}", key as u8, label(key)))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+}
+
+/// Parses a CHIP-8 key name, a single hexadecimal digit.
+fn parse_key_name(name: &str) -> anyhow::Result<Key> {
+    let index = (name.len() == 1)
+        .then(|| u8::from_str_radix(name, 16).ok())
+        .flatten()
+        .ok_or_else(|| anyhow!("Invalid CHIP-8 key '{}' (expected 0-9 or A-F)", name))?;
+    Key::from_index(index)
+}
+
+/// Source of host keyboard events.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum InputBackend {
+    /// Global keyboard events from `rdev`. Reports key releases reliably, but
+    /// also captures keys typed into other windows, needs X11 or input group
+    /// permissions and does not see the keyboard of a remote session.
+    Rdev,
+
+    /// Key events read from the terminal with `crossterm`. Works wherever the
+    /// terminal does, including over SSH. Key releases are reported by
+    /// terminals supporting the kitty keyboard protocol; on others a key
+    /// counts as held for a short time after each press or auto-repeat.
+    Crossterm,
+}
+
+impl InputBackend {
+    /// Picks the backend for this session: crossterm when connected over SSH,
+    /// where rdev would listen to the remote machine's keyboard, and rdev
+    /// otherwise.
+    pub fn detect() -> Self {
+        let remote = ["SSH_CONNECTION", "SSH_CLIENT", "SSH_TTY"]
+            .iter()
+            .any(|var| std::env::var_os(var).is_some());
+        if remote {
+            InputBackend::Crossterm
+        } else {
+            InputBackend::Rdev
+        }
+    }
+}
+
+/// How long a key counts as held after its first press when the terminal
+/// does not report releases. Long enough to bridge the usual auto-repeat
+/// delay of a held key.
+const FIRST_PRESS_HOLD: Duration = Duration::from_millis(500);
+
+/// How long a key counts as held after an auto-repeated press when the
+/// terminal does not report releases.
+const REPEAT_PRESS_HOLD: Duration = Duration::from_millis(100);
+
+/// Keyboard listener feeding a CHIP-8 [`Keypad`].
+///
+/// # Architecture
+///
+/// - With [`InputBackend::Rdev`], a background thread continuously listens for
+///   global keyboard events using `rdev`
+/// - With [`InputBackend::Crossterm`], the emulator loop passes the terminal's
+///   key events to [`KeyListener::handle_event`], and
+///   [`KeyListener::release_expired`] releases keys on terminals that do not
+///   report key releases
+/// - CHIP-8 key presses are forwarded to a shared `Keypad` handle
+/// - The Escape and Backspace keys are tracked separately for emulator
+///   control: Escape exits and holding Backspace rewinds
+///
+/// # Key Mapping
+///
+/// Physical keyboard keys are mapped to CHIP-8 keys by a [`Keymap`], with
+/// terminal keys first converted to the matching `rdev` key.
+pub struct KeyListener {
+    keys: KeyState,
+
+    /// Key state of the crossterm backend, `None` with rdev.
+    terminal: Option<TerminalKeys>,
+}
+
+/// Key state shared with the rdev listener thread.
+#[derive(Clone)]
+struct KeyState {
+    /// Thread-safe flag indicating if the Escape key is currently pressed.
+    /// Used for emulator control (typically to exit the program).
+    escape_pressed: Arc<Mutex<bool>>,
+
+    /// Thread-safe flag indicating if the Backspace key is held, which plays
+    /// the program backwards.
+    rewind_pressed: Arc<Mutex<bool>>,
+
+    keymap: Keymap,
+    keypad: Keypad,
+}
+
+impl KeyState {
+    /// Records a press or release of a host key.
+    fn set(&self, key: RdevKey, pressed: bool) {
+        if key == RdevKey::Escape {
+            *self.escape_pressed.lock().unwrap() = pressed;
+        } else if key == RdevKey::Backspace {
+            *self.rewind_pressed.lock().unwrap() = pressed;
+        } else if let Some(chip8_key) = self.keymap.key(key) {
+            if pressed {
+                self.keypad.press_key(chip8_key);
+            } else {
+                self.keypad.release_key(chip8_key);
+            }
+        }
+    }
+}
+
+/// Terminal key tracking for the crossterm backend.
+struct TerminalKeys {
+    /// True if the terminal reports key releases, which were enabled with the
+    /// kitty keyboard protocol and must be disabled again on drop.
+    reports_releases: bool,
+
+    /// When each held key is released, if the terminal does not report
+    /// releases.
+    deadlines: HashMap<RdevKey, Instant>,
+}
+
+impl KeyListener {
+    /// Starts listening with `backend`, forwarding the keys bound in `keymap`
+    /// to `keypad`.
+    ///
+    /// The rdev listener thread runs until the program terminates. The
+    /// crossterm backend expects the terminal to be in raw mode.
+    pub fn start(backend: InputBackend, keymap: Keymap, keypad: Keypad) -> anyhow::Result<Self> {
+        let keys = KeyState {
+            escape_pressed: Arc::new(Mutex::new(false)),
+            rewind_pressed: Arc::new(Mutex::new(false)),
+            keymap,
+            keypad,
+        };
+
+        let terminal = match backend {
+            InputBackend::Rdev => {
+                let keys = keys.clone();
+                // Spawn a background thread to listen for key events
+                std::thread::spawn(move || {
+                    if let Err(error) = listen(move |event| match event.event_type {
+                        EventType::KeyPress(key) => keys.set(key, true),
+                        EventType::KeyRelease(key) => keys.set(key, false),
+                        _ => {}
+                    }) {
+                        eprintln!("Error listening for key events: {:?}", error);
+                    }
+                });
+                None
+            }
+            InputBackend::Crossterm => {
+                let reports_releases = supports_keyboard_enhancement().unwrap_or(false);
+                if reports_releases {
+                    execute!(
+                        std::io::stdout(),
+                        PushKeyboardEnhancementFlags(
+                            KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                                | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+                        )
+                    )?;
+                }
+                Some(TerminalKeys {
+                    reports_releases,
+                    deadlines: HashMap::new(),
+                })
+            }
+        };
+
+        Ok(KeyListener { keys, terminal })
+    }
+
+    /// Handles a key event read from the terminal. Only the crossterm backend
+    /// uses these events.
+    pub fn handle_event(&mut self, event: &KeyEvent) {
+        let Some(terminal) = &mut self.terminal else {
+            return;
+        };
+        let Some(key) = rdev_key(event.code) else {
+            return;
+        };
+        if terminal.reports_releases {
+            self.keys.set(key, event.kind != KeyEventKind::Release);
+        } else if event.kind != KeyEventKind::Release {
+            let now = Instant::now();
+            let hold = if terminal.deadlines.contains_key(&key) {
+                REPEAT_PRESS_HOLD
+            } else {
+                FIRST_PRESS_HOLD
+            };
+            terminal.deadlines.insert(key, now + hold);
+            self.keys.set(key, true);
+        }
+    }
+
+    /// Releases the keys whose press has timed out, on terminals that do not
+    /// report key releases. Call once per frame after handling the events.
+    pub fn release_expired(&mut self) {
+        let Some(terminal) = &mut self.terminal else {
+            return;
+        };
+        let now = Instant::now();
+        let keys = &self.keys;
+        terminal.deadlines.retain(|&key, &mut deadline| {
+            let held = deadline > now;
+            if !held {
+                keys.set(key, false);
+            }
+            held
+        });
+    }
+
+    /// Checks if the Escape key is currently pressed.
+    pub fn is_escape_pressed(&self) -> bool {
+        *self.keys.escape_pressed.lock().unwrap()
+    }
+
+    /// Checks if the rewind key (Backspace) is currently held.
+    pub fn is_rewind_pressed(&self) -> bool {
+        *self.keys.rewind_pressed.lock().unwrap()
+    }
+}
+
+impl Drop for KeyListener {
+    fn drop(&mut self) {
+        if self.terminal.as_ref().is_some_and(|t| t.reports_releases) {
+            let _ = execute!(std::io::stdout(), PopKeyboardEnhancementFlags);
+        }
+    }
+}
+
+/// Converts a terminal key to the `rdev` key of the same name in
+/// [`HOST_KEYS`], or to Escape or Backspace.
+fn rdev_key(code: KeyCode) -> Option<RdevKey> {
+    match code {
+        KeyCode::Esc => Some(RdevKey::Escape),
+        KeyCode::Backspace => Some(RdevKey::Backspace),
+        KeyCode::Up => Some(RdevKey::UpArrow),
+        KeyCode::Down => Some(RdevKey::DownArrow),
+        KeyCode::Left => Some(RdevKey::LeftArrow),
+        KeyCode::Right => Some(RdevKey::RightArrow),
+        KeyCode::Enter => Some(RdevKey::Return),
+        KeyCode::Tab => Some(RdevKey::Tab),
+        KeyCode::Char(' ') => Some(RdevKey::Space),
+        KeyCode::Char(c) => host_key(c.encode_utf8(&mut [0; 4])),
+        _ => None,
+    }
+}
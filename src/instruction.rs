@@ -7,8 +7,10 @@
 
 use anyhow::anyhow;
 
+use crate::disasm::Syntax;
 use crate::state::{
-    Address, Chip8State, Key, Register, DISPLAY_HEIGHT, DISPLAY_WIDTH, FONT_ADDR, FONT_HEIGHT,
+    AUDIO_PATTERN_SIZE, Address, BIG_FONT_ADDR, BIG_FONT_HEIGHT, Chip8State, FONT_ADDR,
+    FONT_HEIGHT, Key, Platform, Register,
 };
 
 /// Trait defining the execution interface for CHIP-8 instructions.
@@ -19,6 +21,9 @@ use crate::state::{
 pub trait Instruction {
     /// Executes the instruction, possibly modifying the provided CHIP-8 state.
     fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()>;
+
+    /// Renders the instruction as assembly in the given syntax.
+    fn mnemonic(&self, syntax: Syntax) -> String;
 }
 
 /// Decodes a raw 16-bit instruction word into an executable instruction object.
@@ -36,13 +41,29 @@ pub trait Instruction {
 /// OPCODE X NN    (8-bit immediate value)
 /// OPCODE NNN     (12-bit address)
 /// ```
-pub fn decode(raw: u16) -> anyhow::Result<Box<dyn Instruction>> {
+///
+/// # Platforms
+/// The SUPER-CHIP extensions (`00CN`, `00FB`-`00FF`, `FX30`, `FX75`, `FX85`) are
+/// only decoded when `platform` supports them. On the original CHIP-8 they are
+/// machine language routines and are rejected like any other `0NNN` call.
+/// Likewise, the XO-CHIP extensions (`00DN`, `5XY2`, `5XY3`, `F000 NNNN`, `FN01`,
+/// `F002`, `FX3A`) are only decoded on XO-CHIP.
+pub fn decode(raw: u16, platform: Platform) -> anyhow::Result<Box<dyn Instruction>> {
     let decoded = DecodedInstruction::new(raw);
+    let schip = platform.has_schip_extensions();
+    let xochip = platform.has_xochip_extensions();
 
     match decoded.opcode {
         0x0 => match decoded.nnn {
             0x0E0 => Ok(Box::new(ClearScreen)),
             0x0EE => Ok(Box::new(SubroutineReturn)),
+            0x0C0..=0x0CF if schip => Ok(Box::new(ScrollDown(decoded))),
+            0x0D0..=0x0DF if xochip => Ok(Box::new(ScrollUp(decoded))),
+            0x0FB if schip => Ok(Box::new(ScrollRight)),
+            0x0FC if schip => Ok(Box::new(ScrollLeft)),
+            0x0FD if schip => Ok(Box::new(Exit)),
+            0x0FE if schip => Ok(Box::new(LowResolution)),
+            0x0FF if schip => Ok(Box::new(HighResolution)),
             _ => Err(anyhow!(
                 "Unsupported request for execute machine language routine: {:#04X}",
                 raw
@@ -52,7 +73,11 @@ pub fn decode(raw: u16) -> anyhow::Result<Box<dyn Instruction>> {
         0x2 => Ok(Box::new(SubroutineCall(decoded))),
         0x3 => Ok(Box::new(JumpEqX(decoded))),
         0x4 => Ok(Box::new(JumpNeqX(decoded))),
-        0x5 => Ok(Box::new(JumpXEqY(decoded))),
+        0x5 => match decoded.n {
+            0x2 if xochip => Ok(Box::new(SaveRange(decoded))),
+            0x3 if xochip => Ok(Box::new(LoadRange(decoded))),
+            _ => Ok(Box::new(JumpXEqY(decoded))),
+        },
         0x6 => Ok(Box::new(SetImmediate(decoded))),
         0x7 => Ok(Box::new(Add(decoded))),
         0x8 => match decoded.n {
@@ -78,14 +103,21 @@ pub fn decode(raw: u16) -> anyhow::Result<Box<dyn Instruction>> {
             _ => Err(anyhow!("Unsupported opcode for 0xE: {:#04X}", raw)),
         },
         0xF => match decoded.nn {
+            0x00 if xochip && decoded.x == 0 => Ok(Box::new(LongIndex)),
+            0x01 if xochip => Ok(Box::new(SelectPlanes(decoded))),
+            0x02 if xochip && decoded.x == 0 => Ok(Box::new(LoadAudioPattern)),
             0x07 => Ok(Box::new(SetVxFromTimer(decoded))),
             0x15 => Ok(Box::new(SetDelayTimer(decoded))),
             0x18 => Ok(Box::new(SetSoundTimer(decoded))),
             0x1E => Ok(Box::new(AddToIndex(decoded))),
             0x29 => Ok(Box::new(FontChar(decoded))),
+            0x30 if schip => Ok(Box::new(BigFontChar(decoded))),
             0x33 => Ok(Box::new(BinaryCodedDecimal(decoded))),
+            0x3A if xochip => Ok(Box::new(SetPitch(decoded))),
             0x55 => Ok(Box::new(Store(decoded))),
             0x65 => Ok(Box::new(Load(decoded))),
+            0x75 if schip => Ok(Box::new(SaveFlags(decoded))),
+            0x85 if schip => Ok(Box::new(LoadFlags(decoded))),
             0x0A => Ok(Box::new(GetKey(decoded))),
             _ => Err(anyhow!("Unsupported opcode for 0xF: {:#04X}", raw)),
         },
@@ -133,13 +165,151 @@ impl DecodedInstruction {
 /// Clears the entire display screen.
 ///
 /// Implements the CHIP-8 instruction `00E0` which sets all pixels on the
-/// 64×32 display to off (black).
+/// display to off (black).
 struct ClearScreen;
 impl Instruction for ClearScreen {
     fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
         state.clear_display();
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => "clear".to_string(),
+            Syntax::Cowgod => "cls".to_string(),
+        }
+    }
+}
+
+/// Scrolls the display down by N pixels.
+///
+/// Implements the SUPER-CHIP instruction `00CN` which moves every row of the
+/// display down by N pixels. Rows scrolled in at the top are blank.
+struct ScrollDown(DecodedInstruction);
+impl Instruction for ScrollDown {
+    fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
+        state.display.scroll_down(usize::from(self.0.n));
+        Ok(())
+    }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("scroll-down {}", self.0.n),
+            Syntax::Cowgod => format!("scd {}", self.0.n),
+        }
+    }
+}
+
+/// Scrolls the display up by N pixels.
+///
+/// Implements the XO-CHIP instruction `00DN` which moves every row of the
+/// selected planes up by N pixels. Rows scrolled in at the bottom are blank.
+struct ScrollUp(DecodedInstruction);
+impl Instruction for ScrollUp {
+    fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
+        state.display.scroll_up(usize::from(self.0.n));
+        Ok(())
+    }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("scroll-up {}", self.0.n),
+            Syntax::Cowgod => format!("scu {}", self.0.n),
+        }
+    }
+}
+
+/// Scrolls the display right by 4 pixels.
+///
+/// Implements the SUPER-CHIP instruction `00FB`. Columns scrolled in at the
+/// left are blank.
+struct ScrollRight;
+impl Instruction for ScrollRight {
+    fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
+        state.display.scroll_right(4);
+        Ok(())
+    }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => "scroll-right".to_string(),
+            Syntax::Cowgod => "scr".to_string(),
+        }
+    }
+}
+
+/// Scrolls the display left by 4 pixels.
+///
+/// Implements the SUPER-CHIP instruction `00FC`. Columns scrolled in at the
+/// right are blank.
+struct ScrollLeft;
+impl Instruction for ScrollLeft {
+    fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
+        state.display.scroll_left(4);
+        Ok(())
+    }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => "scroll-left".to_string(),
+            Syntax::Cowgod => "scl".to_string(),
+        }
+    }
+}
+
+/// Exits the interpreter.
+///
+/// Implements the SUPER-CHIP instruction `00FD` which halts the machine. No
+/// further instructions are executed once the machine is halted.
+struct Exit;
+impl Instruction for Exit {
+    fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
+        state.halted = true;
+        Ok(())
+    }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => "exit".to_string(),
+            Syntax::Cowgod => "exit".to_string(),
+        }
+    }
+}
+
+/// Switches the display to 64×32 low resolution mode.
+///
+/// Implements the SUPER-CHIP instruction `00FE`. The display is cleared.
+struct LowResolution;
+impl Instruction for LowResolution {
+    fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
+        state.display.set_hires(false);
+        Ok(())
+    }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => "lores".to_string(),
+            Syntax::Cowgod => "low".to_string(),
+        }
+    }
+}
+
+/// Switches the display to 128×64 high resolution mode.
+///
+/// Implements the SUPER-CHIP instruction `00FF`. The display is cleared.
+struct HighResolution;
+impl Instruction for HighResolution {
+    fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
+        state.display.set_hires(true);
+        Ok(())
+    }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => "hires".to_string(),
+            Syntax::Cowgod => "high".to_string(),
+        }
+    }
 }
 
 /// Unconditional jump to a specific memory address.
@@ -152,6 +322,13 @@ impl Instruction for Jump {
         state.pc = self.0.nnn;
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("jump {:#05x}", self.0.nnn),
+            Syntax::Cowgod => format!("jp {:#05x}", self.0.nnn),
+        }
+    }
 }
 
 /// Calls a subroutine at the specified address.
@@ -169,6 +346,13 @@ impl Instruction for SubroutineCall {
         state.pc = self.0.nnn;
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!(":call {:#05x}", self.0.nnn),
+            Syntax::Cowgod => format!("call {:#05x}", self.0.nnn),
+        }
+    }
 }
 
 /// Returns from a subroutine call.
@@ -190,6 +374,13 @@ impl Instruction for SubroutineReturn {
             Err(anyhow!("Stack underflow: No return address available"))
         }
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => "return".to_string(),
+            Syntax::Cowgod => "ret".to_string(),
+        }
+    }
 }
 
 /// Conditional skip if register Vx equals immediate value.
@@ -197,16 +388,23 @@ impl Instruction for SubroutineReturn {
 /// Implements the CHIP-8 instruction `3XNN` which compares the value
 /// in register Vx with the immediate value NN. If they are equal,
 /// the next instruction is skipped by advancing the program counter
-/// by 2 additional bytes.
+/// by 2 additional bytes (4 when skipping an XO-CHIP `F000 NNNN`).
 struct JumpEqX(DecodedInstruction);
 impl Instruction for JumpEqX {
     fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
         let reg_x = Register::from_index(self.0.x)?;
         if state.registers.read(reg_x) == self.0.nn {
-            state.pc += 2;
+            state.skip_next_instruction()?;
         }
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("if v{:x} != {:#04x} then", self.0.x, self.0.nn),
+            Syntax::Cowgod => format!("se v{:x}, {:#04x}", self.0.x, self.0.nn),
+        }
+    }
 }
 
 /// Conditional skip if register Vx does not equal immediate value.
@@ -220,10 +418,17 @@ impl Instruction for JumpNeqX {
     fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
         let reg_x = Register::from_index(self.0.x)?;
         if state.registers.read(reg_x) != self.0.nn {
-            state.pc += 2;
+            state.skip_next_instruction()?;
         }
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("if v{:x} == {:#04x} then", self.0.x, self.0.nn),
+            Syntax::Cowgod => format!("sne v{:x}, {:#04x}", self.0.x, self.0.nn),
+        }
+    }
 }
 
 /// Conditional skip if register Vx equals register Vy.
@@ -237,10 +442,17 @@ impl Instruction for JumpXEqY {
         let reg_x = Register::from_index(self.0.x)?;
         let reg_y = Register::from_index(self.0.y)?;
         if state.registers.read(reg_x) == state.registers.read(reg_y) {
-            state.pc += 2;
+            state.skip_next_instruction()?;
         }
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("if v{:x} != v{:x} then", self.0.x, self.0.y),
+            Syntax::Cowgod => format!("se v{:x}, v{:x}", self.0.x, self.0.y),
+        }
+    }
 }
 
 /// Conditional skip if register Vx does not equal register Vy.
@@ -254,10 +466,78 @@ impl Instruction for JumpXNeqY {
         let reg_x = Register::from_index(self.0.x)?;
         let reg_y = Register::from_index(self.0.y)?;
         if state.registers.read(reg_x) != state.registers.read(reg_y) {
-            state.pc += 2;
+            state.skip_next_instruction()?;
+        }
+        Ok(())
+    }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("if v{:x} == v{:x} then", self.0.x, self.0.y),
+            Syntax::Cowgod => format!("sne v{:x}, v{:x}", self.0.x, self.0.y),
+        }
+    }
+}
+
+/// Saves the registers Vx through Vy to memory starting at the index register.
+///
+/// Implements the XO-CHIP instruction `5XY2`. The range is inclusive and may run
+/// in either direction: if X is greater than Y the registers are stored in
+/// descending order. The index register is not modified.
+struct SaveRange(DecodedInstruction);
+impl Instruction for SaveRange {
+    fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
+        for (offset, reg_idx) in register_range(self.0.x, self.0.y).enumerate() {
+            let value = state.registers.read(Register::from_index(reg_idx)?);
+            state.memory.write(state.index + offset, value)?;
+        }
+        Ok(())
+    }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("save v{:x} - v{:x}", self.0.x, self.0.y),
+            Syntax::Cowgod => format!("save v{:x}, v{:x}", self.0.x, self.0.y),
+        }
+    }
+}
+
+/// Loads the registers Vx through Vy from memory starting at the index register.
+///
+/// Implements the XO-CHIP instruction `5XY3`. The range is inclusive and may run
+/// in either direction: if X is greater than Y the registers are loaded in
+/// descending order. The index register is not modified.
+struct LoadRange(DecodedInstruction);
+impl Instruction for LoadRange {
+    fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
+        for (offset, reg_idx) in register_range(self.0.x, self.0.y).enumerate() {
+            let value = state.memory.read(state.index + offset)?;
+            state.registers.write(Register::from_index(reg_idx)?, value);
         }
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("load v{:x} - v{:x}", self.0.x, self.0.y),
+            Syntax::Cowgod => format!("load v{:x}, v{:x}", self.0.x, self.0.y),
+        }
+    }
+}
+
+/// Adds `n` to the index register, which is 16 bits wide on every interpreter
+/// and so wraps past 0xFFFF.
+fn index_plus(index: Address, n: usize) -> Address {
+    index.wrapping_add(n) & 0xFFFF
+}
+
+/// Returns the register indices from `x` to `y` inclusive, in either direction.
+fn register_range(x: usize, y: usize) -> Box<dyn Iterator<Item = usize>> {
+    if x <= y {
+        Box::new(x..=y)
+    } else {
+        Box::new((y..=x).rev())
+    }
 }
 
 /// Sets register Vx to an immediate 8-bit value.
@@ -272,6 +552,13 @@ impl Instruction for SetImmediate {
         state.registers.write(reg_x, self.0.nn);
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("v{:x} := {:#04x}", self.0.x, self.0.nn),
+            Syntax::Cowgod => format!("ld v{:x}, {:#04x}", self.0.x, self.0.nn),
+        }
+    }
 }
 
 /// Adds an immediate 8-bit value to register Vx.
@@ -290,6 +577,13 @@ impl Instruction for Add {
             .write(reg_x, reg_x_val.wrapping_add(self.0.nn));
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("v{:x} += {:#04x}", self.0.x, self.0.nn),
+            Syntax::Cowgod => format!("add v{:x}, {:#04x}", self.0.x, self.0.nn),
+        }
+    }
 }
 
 /// Copies the value from register Vy to register Vx.
@@ -305,13 +599,20 @@ impl Instruction for SetXToY {
         state.registers.write(reg_x, value_y);
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("v{:x} := v{:x}", self.0.x, self.0.y),
+            Syntax::Cowgod => format!("ld v{:x}, v{:x}", self.0.x, self.0.y),
+        }
+    }
 }
 
 /// Performs bitwise OR operation between registers Vx and Vy.
 ///
 /// Implements the CHIP-8 instruction `8XY1` which performs a bitwise OR
 /// operation on the values in registers Vx and Vy, storing the result
-/// in Vx. As a side effect, register VF is always set to 0.
+/// in Vx. With the VF reset quirk, register VF is then set to 0.
 struct BinaryOr(DecodedInstruction);
 impl Instruction for BinaryOr {
     fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
@@ -321,16 +622,25 @@ impl Instruction for BinaryOr {
         let value_y = state.registers.read(reg_y);
 
         state.registers.write(reg_x, value_x | value_y);
-        state.registers.write(Register::VF, 0);
+        if state.settings.quirks.vf_reset {
+            state.registers.write(Register::VF, 0);
+        }
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("v{:x} |= v{:x}", self.0.x, self.0.y),
+            Syntax::Cowgod => format!("or v{:x}, v{:x}", self.0.x, self.0.y),
+        }
+    }
 }
 
 /// Performs bitwise AND operation between registers Vx and Vy.
 ///
 /// Implements the CHIP-8 instruction `8XY2` which performs a bitwise AND
 /// operation on the values in registers Vx and Vy, storing the result
-/// in Vx. As a side effect, register VF is always set to 0.
+/// in Vx. With the VF reset quirk, register VF is then set to 0.
 struct BinaryAnd(DecodedInstruction);
 impl Instruction for BinaryAnd {
     fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
@@ -340,16 +650,25 @@ impl Instruction for BinaryAnd {
         let value_y = state.registers.read(reg_y);
 
         state.registers.write(reg_x, value_x & value_y);
-        state.registers.write(Register::VF, 0);
+        if state.settings.quirks.vf_reset {
+            state.registers.write(Register::VF, 0);
+        }
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("v{:x} &= v{:x}", self.0.x, self.0.y),
+            Syntax::Cowgod => format!("and v{:x}, v{:x}", self.0.x, self.0.y),
+        }
+    }
 }
 
 /// Performs bitwise XOR operation between registers Vx and Vy.
 ///
 /// Implements the CHIP-8 instruction `8XY3` which performs a bitwise XOR
 /// (exclusive OR) operation on the values in registers Vx and Vy, storing
-/// the result in Vx. As a side effect, register VF is always set to 0.
+/// the result in Vx. With the VF reset quirk, register VF is then set to 0.
 struct LogicalXor(DecodedInstruction);
 impl Instruction for LogicalXor {
     fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
@@ -359,9 +678,18 @@ impl Instruction for LogicalXor {
         let value_y = state.registers.read(reg_y);
 
         state.registers.write(reg_x, value_x ^ value_y);
-        state.registers.write(Register::VF, 0);
+        if state.settings.quirks.vf_reset {
+            state.registers.write(Register::VF, 0);
+        }
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("v{:x} ^= v{:x}", self.0.x, self.0.y),
+            Syntax::Cowgod => format!("xor v{:x}, v{:x}", self.0.x, self.0.y),
+        }
+    }
 }
 
 /// Adds register Vy to register Vx with carry detection.
@@ -388,6 +716,13 @@ impl Instruction for BinaryAdd {
         }
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("v{:x} += v{:x}", self.0.x, self.0.y),
+            Syntax::Cowgod => format!("add v{:x}, v{:x}", self.0.x, self.0.y),
+        }
+    }
 }
 
 /// Subtracts register Vy from register Vx with borrow detection.
@@ -413,6 +748,13 @@ impl Instruction for SubtractYFromX {
         }
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("v{:x} -= v{:x}", self.0.x, self.0.y),
+            Syntax::Cowgod => format!("sub v{:x}, v{:x}", self.0.x, self.0.y),
+        }
+    }
 }
 
 /// Subtracts register Vx from register Vy with borrow detection.
@@ -438,6 +780,13 @@ impl Instruction for SubtractXFromY {
         }
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("v{:x} =- v{:x}", self.0.x, self.0.y),
+            Syntax::Cowgod => format!("subn v{:x}, v{:x}", self.0.x, self.0.y),
+        }
+    }
 }
 
 /// Performs a right shift operation on register Vy, storing result in Vx.
@@ -445,19 +794,29 @@ impl Instruction for SubtractXFromY {
 /// Implements the CHIP-8 instruction `8XY6` which shifts the value in register
 /// Vy one bit to the right and stores the result in register Vx. The least
 /// significant bit (LSB) of the original value is stored in register VF before
-/// the shift operation.
+/// the shift operation. With the shifting quirk, Vx is shifted in place instead.
 struct RightShift(DecodedInstruction);
 impl Instruction for RightShift {
     fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
         let reg_x = Register::from_index(self.0.x)?;
         let reg_y = Register::from_index(self.0.y)?;
-        let value_y = state.registers.read(reg_y);
-        let value_x = value_y;
+        let value_x = if state.settings.quirks.shift_in_place {
+            state.registers.read(reg_x)
+        } else {
+            state.registers.read(reg_y)
+        };
 
         state.registers.write(reg_x, value_x >> 1);
         state.registers.write(Register::VF, value_x & 0x01); // Set VF to LSB before shift
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("v{:x} >>= v{:x}", self.0.x, self.0.y),
+            Syntax::Cowgod => format!("shr v{:x}, v{:x}", self.0.x, self.0.y),
+        }
+    }
 }
 
 /// Performs a left shift operation on register Vy, storing result in Vx.
@@ -465,19 +824,29 @@ impl Instruction for RightShift {
 /// Implements the CHIP-8 instruction `8XYE` which shifts the value in register
 /// Vy one bit to the left and stores the result in register Vx. The most
 /// significant bit (MSB) of the original value is stored in register VF before
-/// the shift operation.
+/// the shift operation. With the shifting quirk, Vx is shifted in place instead.
 struct LeftShift(DecodedInstruction);
 impl Instruction for LeftShift {
     fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
         let reg_x = Register::from_index(self.0.x)?;
         let reg_y = Register::from_index(self.0.y)?;
-        let value_y = state.registers.read(reg_y);
-        let value_x = value_y;
+        let value_x = if state.settings.quirks.shift_in_place {
+            state.registers.read(reg_x)
+        } else {
+            state.registers.read(reg_y)
+        };
 
         state.registers.write(reg_x, value_x << 1);
         state.registers.write(Register::VF, (value_x & 0x80) >> 7); // Set VF to MSB before shift
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("v{:x} <<= v{:x}", self.0.x, self.0.y),
+            Syntax::Cowgod => format!("shl v{:x}, v{:x}", self.0.x, self.0.y),
+        }
+    }
 }
 
 /// Sets the index register to a specific memory address.
@@ -491,19 +860,39 @@ impl Instruction for SetIndex {
         state.index = self.0.nnn;
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("i := {:#05x}", self.0.nnn),
+            Syntax::Cowgod => format!("ld i, {:#05x}", self.0.nnn),
+        }
+    }
 }
 
 /// Jumps to address NNN plus the value in register V0.
 ///
 /// Implements the CHIP-8 instruction `BNNN` which sets the program counter
 /// to the address NNN plus the value stored in register V0. This allows
-/// for computed jumps based on runtime values.
+/// for computed jumps based on runtime values. With the jumping quirk, the
+/// instruction is read as `BXNN` and the offset comes from Vx instead.
 struct JumpWithOffset(DecodedInstruction);
 impl Instruction for JumpWithOffset {
     fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
-        state.pc = usize::from(state.registers.read(Register::V0)) + self.0.nnn;
+        let offset_reg = if state.settings.quirks.jump_with_vx {
+            Register::from_index(self.0.x)?
+        } else {
+            Register::V0
+        };
+        state.pc = usize::from(state.registers.read(offset_reg)) + self.0.nnn;
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("jump0 {:#05x}", self.0.nnn),
+            Syntax::Cowgod => format!("jp v0, {:#05x}", self.0.nnn),
+        }
+    }
 }
 
 /// Generates a random number and applies a bitmask.
@@ -515,10 +904,17 @@ struct Random(DecodedInstruction);
 impl Instruction for Random {
     fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
         let reg_x = Register::from_index(self.0.x)?;
-        let random_value = rand::random::<u8>() & self.0.nn;
+        let random_value = state.rng.next_u8() & self.0.nn;
         state.registers.write(reg_x, random_value);
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("v{:x} := random {:#04x}", self.0.x, self.0.nn),
+            Syntax::Cowgod => format!("rnd v{:x}, {:#04x}", self.0.x, self.0.nn),
+        }
+    }
 }
 
 /// Draws a sprite to the display with collision detection.
@@ -527,6 +923,11 @@ impl Instruction for Random {
 /// starting at memory location I at coordinates (Vx, Vy) on the display.
 /// Each sprite is 8 pixels wide and N pixels tall. Pixels are XORed with
 /// existing pixels, and VF is set to 1 if any pixels are erased (collision).
+/// On SUPER-CHIP, `DXY0` draws a 16×16 sprite from 32 bytes at I.
+///
+/// # Display Wait
+/// With the display wait quirk, a draw in low resolution mode ends the current
+/// frame so that the next instruction runs after the following vertical blank.
 ///
 /// # Collision Detection
 /// If any existing pixel is turned off by the XOR operation, VF is set to 1.
@@ -539,16 +940,26 @@ impl Instruction for Display {
 
         state.registers.write(Register::VF, 0);
         if state.draw_sprite(
-            usize::from(x) % DISPLAY_WIDTH,
-            usize::from(y) % DISPLAY_HEIGHT,
+            usize::from(x) % state.display.width(),
+            usize::from(y) % state.display.height(),
             self.0.n,
         )? {
             state.registers.write(Register::VF, 1);
         } else {
             state.registers.write(Register::VF, 0);
         }
+        if state.settings.quirks.display_wait && !state.display.is_hires() {
+            state.awaiting_vblank = true;
+        }
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("sprite v{:x} v{:x} {}", self.0.x, self.0.y, self.0.n),
+            Syntax::Cowgod => format!("drw v{:x}, v{:x}, {}", self.0.x, self.0.y, self.0.n),
+        }
+    }
 }
 
 /// Skips the next instruction if the specified key is pressed.
@@ -566,10 +977,17 @@ impl Instruction for SkipIfKeyPressed {
         let is_key_pressed = state.keypad.is_key_pressed(pressed_key);
 
         if is_key_pressed {
-            state.pc += 2;
+            state.skip_next_instruction()?;
         }
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("if v{:x} -key then", self.0.x),
+            Syntax::Cowgod => format!("skp v{:x}", self.0.x),
+        }
+    }
 }
 
 /// Skips the next instruction if the specified key is not pressed.
@@ -587,10 +1005,17 @@ impl Instruction for SkipIfKeyNotPressed {
         let is_key_pressed = state.keypad.is_key_pressed(pressed_key);
 
         if !is_key_pressed {
-            state.pc += 2;
+            state.skip_next_instruction()?;
         }
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("if v{:x} key then", self.0.x),
+            Syntax::Cowgod => format!("sknp v{:x}", self.0.x),
+        }
+    }
 }
 
 /// Sets register Vx to the current value of the delay timer.
@@ -605,6 +1030,13 @@ impl Instruction for SetVxFromTimer {
         state.registers.write(reg_x, state.delay_timer);
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("v{:x} := delay", self.0.x),
+            Syntax::Cowgod => format!("ld v{:x}, dt", self.0.x),
+        }
+    }
 }
 
 /// Sets the delay timer to the value in register Vx.
@@ -619,6 +1051,13 @@ impl Instruction for SetDelayTimer {
         state.delay_timer = state.registers.read(reg_x);
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("delay := v{:x}", self.0.x),
+            Syntax::Cowgod => format!("ld dt, v{:x}", self.0.x),
+        }
+    }
 }
 
 /// Sets the sound timer to the value in register Vx.
@@ -633,21 +1072,35 @@ impl Instruction for SetSoundTimer {
         state.sound_timer = state.registers.read(reg_x);
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("buzzer := v{:x}", self.0.x),
+            Syntax::Cowgod => format!("ld st, v{:x}", self.0.x),
+        }
+    }
 }
 
 /// Adds the value in register Vx to the index register.
 ///
 /// Implements the CHIP-8 instruction `FX1E` which adds the value in
 /// register Vx to the current value of the index register (I).
-/// Uses wrapping arithmetic to handle overflow.
+/// The 16-bit index register wraps around from 0xFFFF to 0.
 struct AddToIndex(DecodedInstruction);
 impl Instruction for AddToIndex {
     fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
         let reg_x = Register::from_index(self.0.x)?;
         let value_x = state.registers.read(reg_x);
-        state.index = state.index.wrapping_add(usize::from(value_x));
+        state.index = index_plus(state.index, usize::from(value_x));
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("i += v{:x}", self.0.x),
+            Syntax::Cowgod => format!("add i, v{:x}", self.0.x),
+        }
+    }
 }
 
 /// Sets the index register to the location of a hexadecimal character sprite.
@@ -663,6 +1116,35 @@ impl Instruction for FontChar {
         state.index = usize::from(value_x & 0x0F) * FONT_HEIGHT + FONT_ADDR;
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("i := hex v{:x}", self.0.x),
+            Syntax::Cowgod => format!("ld f, v{:x}", self.0.x),
+        }
+    }
+}
+
+/// Sets the index register to the location of a large hexadecimal character sprite.
+///
+/// Implements the SUPER-CHIP instruction `FX30` which sets the index register
+/// to the memory location of the 8×10 sprite for the hexadecimal digit stored
+/// in register Vx. Only the lower 4 bits of Vx are used (0-F).
+struct BigFontChar(DecodedInstruction);
+impl Instruction for BigFontChar {
+    fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
+        let reg_x = Register::from_index(self.0.x)?;
+        let value_x = state.registers.read(reg_x);
+        state.index = usize::from(value_x & 0x0F) * BIG_FONT_HEIGHT + BIG_FONT_ADDR;
+        Ok(())
+    }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("i := bighex v{:x}", self.0.x),
+            Syntax::Cowgod => format!("ld hf, v{:x}", self.0.x),
+        }
+    }
 }
 
 /// Stores the binary-coded decimal representation of register Vx.
@@ -682,14 +1164,21 @@ impl Instruction for BinaryCodedDecimal {
         }
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("bcd v{:x}", self.0.x),
+            Syntax::Cowgod => format!("ld b, v{:x}", self.0.x),
+        }
+    }
 }
 
 /// Stores registers V0 through Vx in memory starting at the index register.
 ///
 /// Implements the CHIP-8 instruction `FX55` which stores the values from registers
 /// V0 through Vx (inclusive) into memory starting at the address stored in the
-/// index register (I). After the operation, the index register is incremented
-/// by x+1 to point to the next available memory location.
+/// index register (I). With the memory quirk, the index register is then
+/// incremented by x+1 to point to the next available memory location.
 struct Store(DecodedInstruction);
 impl Instruction for Store {
     fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
@@ -701,17 +1190,26 @@ impl Instruction for Store {
             let value = state.registers.read(reg);
             state.memory.write(i, value)?;
         }
-        state.index = state.index.wrapping_add(self.0.x + 1);
+        if state.settings.quirks.memory_increment {
+            state.index = index_plus(state.index, self.0.x + 1);
+        }
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("save v{:x}", self.0.x),
+            Syntax::Cowgod => format!("ld [i], v{:x}", self.0.x),
+        }
+    }
 }
 
 /// Loads memory values into registers V0 through Vx from the index register location.
 ///
 /// Implements the CHIP-8 instruction `FX65` which loads values from memory starting
 /// at the address stored in the index register (I) into registers V0 through Vx
-/// (inclusive). After the operation, the index register is incremented by x+1
-/// to point to the next available memory location.
+/// (inclusive). With the memory quirk, the index register is then incremented
+/// by x+1 to point to the next available memory location.
 struct Load(DecodedInstruction);
 impl Instruction for Load {
     fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
@@ -720,9 +1218,18 @@ impl Instruction for Load {
             let reg = Register::from_index(i)?;
             state.registers.write(reg, value);
         }
-        state.index = state.index.wrapping_add(self.0.x + 1);
+        if state.settings.quirks.memory_increment {
+            state.index = index_plus(state.index, self.0.x + 1);
+        }
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("load v{:x}", self.0.x),
+            Syntax::Cowgod => format!("ld v{:x}, [i]", self.0.x),
+        }
+    }
 }
 
 /// Waits for a key press and stores the pressed key value in register Vx.
@@ -757,4 +1264,145 @@ impl Instruction for GetKey {
 
         Ok(())
     }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("v{:x} := key", self.0.x),
+            Syntax::Cowgod => format!("ld v{:x}, k", self.0.x),
+        }
+    }
+}
+
+/// Saves registers V0 through Vx to the RPL user flags.
+///
+/// Implements the SUPER-CHIP instruction `FX75`. The HP-48 only provides 8 flag
+/// registers, so X must be at most 7; XO-CHIP lifts this limit to all 16.
+struct SaveFlags(DecodedInstruction);
+impl Instruction for SaveFlags {
+    fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
+        if self.0.x >= state.settings.platform.rpl_flag_count() {
+            return Err(anyhow!("RPL flag index out of range: V{:X}", self.0.x));
+        }
+        for i in 0..=self.0.x {
+            state.rpl_flags[i] = state.registers.read(Register::from_index(i)?);
+        }
+        Ok(())
+    }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("saveflags v{:x}", self.0.x),
+            Syntax::Cowgod => format!("ld r, v{:x}", self.0.x),
+        }
+    }
+}
+
+/// Loads registers V0 through Vx from the RPL user flags.
+///
+/// Implements the SUPER-CHIP instruction `FX85`. The HP-48 only provides 8 flag
+/// registers, so X must be at most 7; XO-CHIP lifts this limit to all 16.
+struct LoadFlags(DecodedInstruction);
+impl Instruction for LoadFlags {
+    fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
+        if self.0.x >= state.settings.platform.rpl_flag_count() {
+            return Err(anyhow!("RPL flag index out of range: V{:X}", self.0.x));
+        }
+        for i in 0..=self.0.x {
+            state
+                .registers
+                .write(Register::from_index(i)?, state.rpl_flags[i]);
+        }
+        Ok(())
+    }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("loadflags v{:x}", self.0.x),
+            Syntax::Cowgod => format!("ld v{:x}, r", self.0.x),
+        }
+    }
+}
+
+/// Loads a 16-bit address into the index register.
+///
+/// Implements the XO-CHIP instruction `F000 NNNN`, the only 4-byte instruction.
+/// The address is the word following the opcode, which the program counter is
+/// already pointing at when this executes; it is consumed by advancing the
+/// program counter by 2 more bytes. For the same reason the mnemonic does not
+/// include the address; the disassembler appends it.
+struct LongIndex;
+impl Instruction for LongIndex {
+    fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
+        state.index = usize::from(state.memory.read_word(state.pc)?);
+        state.pc += 2;
+        Ok(())
+    }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => "i := long".to_string(),
+            Syntax::Cowgod => "ld i, long".to_string(),
+        }
+    }
+}
+
+/// Selects the display planes used by drawing, clearing and scrolling.
+///
+/// Implements the XO-CHIP instruction `FN01` where N is a bitmask of planes:
+/// 1 selects plane 0, 2 selects plane 1, 3 selects both and 0 selects none.
+struct SelectPlanes(DecodedInstruction);
+impl Instruction for SelectPlanes {
+    fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
+        state.display.select_planes(self.0.x as u8);
+        Ok(())
+    }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("plane {}", self.0.x),
+            Syntax::Cowgod => format!("plane {}", self.0.x),
+        }
+    }
+}
+
+/// Loads the audio pattern buffer from memory.
+///
+/// Implements the XO-CHIP instruction `F002` which copies 16 bytes starting at
+/// the index register into the audio pattern buffer. Each bit is one sample of
+/// a 1-bit waveform played while the sound timer is non-zero.
+struct LoadAudioPattern;
+impl Instruction for LoadAudioPattern {
+    fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
+        for i in 0..AUDIO_PATTERN_SIZE {
+            state.audio_pattern[i] = state.memory.read(state.index + i)?;
+        }
+        Ok(())
+    }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => "audio".to_string(),
+            Syntax::Cowgod => "audio".to_string(),
+        }
+    }
+}
+
+/// Sets the audio pitch register to the value in register Vx.
+///
+/// Implements the XO-CHIP instruction `FX3A`. The audio pattern plays back at
+/// `4000 * 2^((Vx - 64) / 48)` samples per second.
+struct SetPitch(DecodedInstruction);
+impl Instruction for SetPitch {
+    fn execute(&self, state: &mut Chip8State) -> anyhow::Result<()> {
+        let reg_x = Register::from_index(self.0.x)?;
+        state.pitch = state.registers.read(reg_x);
+        Ok(())
+    }
+
+    fn mnemonic(&self, syntax: Syntax) -> String {
+        match syntax {
+            Syntax::Octo => format!("pitch := v{:x}", self.0.x),
+            Syntax::Cowgod => format!("pitch v{:x}", self.0.x),
+        }
+    }
 }
@@ -0,0 +1,55 @@
+//! CHIP-8 Emulator Library
+//!
+//! The emulator core as a reusable library. The [`Machine`] type runs CHIP-8
+//! programs headlessly on top of [`Chip8State`], the instruction [`decode`]r and
+//! the [`Instruction`] trait, which makes it suitable for integration tests,
+//! bots and alternate frontends.
+//!
+//! # Features
+//!
+//! - **`frontend`** (default): the terminal frontend used by the `chip8` binary,
+//!   pulling in `ratatui`, `crossterm`, `rodio`, `rdev` and `clap`, and `serde`
+//!   for the config file and ROM database, and `png` for screenshots. Disable
+//!   default features to depend on the headless core only.
+//!
+//! [`decode`]: instruction::decode
+//! [`Instruction`]: instruction::Instruction
+
+pub mod analysis;
+pub mod asm;
+pub mod debugger;
+pub mod disasm;
+pub mod headless;
+pub mod instruction;
+pub mod machine;
+pub mod movie;
+pub mod rewind;
+pub mod rng;
+pub mod savestate;
+pub mod state;
+
+#[cfg(feature = "frontend")]
+pub mod capture;
+#[cfg(feature = "frontend")]
+pub mod config;
+#[cfg(feature = "frontend")]
+pub mod debug_panel;
+#[cfg(feature = "frontend")]
+pub mod emulator;
+#[cfg(feature = "frontend")]
+pub mod graphics;
+#[cfg(feature = "frontend")]
+pub mod input;
+#[cfg(feature = "frontend")]
+pub mod phosphor;
+#[cfg(feature = "frontend")]
+pub mod render;
+#[cfg(feature = "frontend")]
+pub mod romdb;
+#[cfg(feature = "frontend")]
+pub mod screenshot;
+#[cfg(feature = "frontend")]
+pub mod theme;
+
+pub use machine::Machine;
+pub use state::{Chip8State, Key, Keypad, Settings};
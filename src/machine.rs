@@ -0,0 +1,243 @@
+//! Headless CHIP-8 Machine
+//!
+//! This module provides [`Machine`], a CHIP-8 virtual machine with no terminal,
+//! audio or input device attached. It drives the fetch/decode/execute cycle on
+//! top of [`Chip8State`] and exposes the framebuffer, timers and keypad so that
+//! frontends, integration tests and bots can all run the same core.
+
+use anyhow::anyhow;
+
+use crate::debugger::{Debugger, RunStatus, StopReason, WatchedValues};
+use crate::instruction::{Instruction, decode};
+use crate::savestate::{RomHash, load_state, rom_hash, save_state};
+use crate::state::{Chip8State, FrameBuffer, Key, Keypad, Settings};
+
+/// A headless CHIP-8 virtual machine.
+///
+/// The machine owns the complete [`Chip8State`] and advances it one instruction
+/// or one frame at a time. It never sleeps, draws or plays sound; the caller
+/// decides how fast to run it and what to do with the framebuffer and sound
+/// timer afterwards.
+///
+/// # Example
+/// ```no_run
+/// use chip8::{Key, Machine, Settings};
+///
+/// let rom = std::fs::read("tests/2-ibm-logo.ch8")?;
+/// let mut machine = Machine::new(Settings::new(60, 700, "tests/2-ibm-logo.ch8".into()));
+/// machine.load_rom(&rom)?;
+///
+/// machine.press_key(Key::Key5);
+/// for _ in 0..60 {
+///     machine.tick_timers();
+///     machine.run_frame()?;
+/// }
+/// println!("top-left pixel lit: {}", machine.pixel(0, 0));
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct Machine {
+    state: Chip8State,
+    debugger: Debugger,
+    rom_hash: RomHash,
+}
+
+impl Machine {
+    /// Creates a new machine with freshly initialized state.
+    pub fn new(settings: Settings) -> Self {
+        Machine {
+            state: Chip8State::new(settings),
+            debugger: Debugger::new(),
+            rom_hash: rom_hash(&[]),
+        }
+    }
+
+    /// Loads ROM data into memory at the program start address.
+    ///
+    /// The ROM's hash is remembered to tag and check save states.
+    pub fn load_rom(&mut self, rom: &[u8]) -> anyhow::Result<()> {
+        self.state.memory.load_rom(rom)?;
+        self.rom_hash = rom_hash(rom);
+        Ok(())
+    }
+
+    /// Returns the SHA-1 hash of the loaded ROM.
+    pub fn rom_hash(&self) -> &RomHash {
+        &self.rom_hash
+    }
+
+    /// Serializes the complete machine state into the save state format.
+    pub fn save_state(&self) -> anyhow::Result<Vec<u8>> {
+        save_state(&self.state, &self.rom_hash)
+    }
+
+    /// Restores a save state, which must have been made with the loaded ROM.
+    pub fn load_state(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        load_state(&mut self.state, data, &self.rom_hash)
+    }
+
+    /// Returns a shared reference to the underlying machine state.
+    pub fn state(&self) -> &Chip8State {
+        &self.state
+    }
+
+    /// Returns a mutable reference to the underlying machine state.
+    pub fn state_mut(&mut self) -> &mut Chip8State {
+        &mut self.state
+    }
+
+    /// Returns the breakpoint state consulted by [`Machine::run_frame`].
+    pub fn debugger(&self) -> &Debugger {
+        &self.debugger
+    }
+
+    /// Returns the breakpoint state for editing.
+    pub fn debugger_mut(&mut self) -> &mut Debugger {
+        &mut self.debugger
+    }
+
+    /// Number of instructions executed by each call to [`Machine::run_frame`].
+    ///
+    /// Derived from the configured instructions per second divided by the frame
+    /// rate, rounded down. Earlier versions of the emulator loop ran one more
+    /// instruction than this each frame. Settings that would run no instruction
+    /// at all, with fewer instructions per second than frames or a frame rate
+    /// of 0, run one instruction per frame.
+    pub fn instructions_per_frame(&self) -> u64 {
+        let settings = &self.state.settings;
+        settings
+            .ips
+            .checked_div(settings.frame_rate)
+            .unwrap_or(0)
+            .max(1)
+    }
+
+    /// Fetches and decodes the next instruction from memory.
+    ///
+    /// This method reads a 16-bit instruction from the current program counter location,
+    /// advances the program counter by 2 bytes, and decodes the raw instruction into
+    /// an executable instruction object.
+    fn fetch_instruction(&mut self) -> anyhow::Result<Box<dyn Instruction>> {
+        if self.state.pc + 1 >= self.state.memory.size() {
+            return Err(anyhow!("Program counter out of bounds"));
+        }
+        let raw = self.state.memory.read_word(self.state.pc)?;
+
+        // Move the program counter to next instruction
+        self.state.pc += 2;
+
+        decode(raw, self.state.settings.platform)
+    }
+
+    /// Executes a single instruction.
+    ///
+    /// Does nothing once the machine has halted. Breakpoints are not checked,
+    /// but if the instruction triggers a watchpoint the hit is returned as
+    /// [`RunStatus::Stopped`].
+    pub fn step(&mut self) -> anyhow::Result<RunStatus> {
+        if self.state.halted {
+            return Ok(RunStatus::Completed);
+        }
+        let pc = self.state.pc;
+        self.state
+            .memory
+            .set_tracing(self.debugger.has_memory_watchpoints());
+        let before = self
+            .debugger
+            .has_watchpoints()
+            .then(|| WatchedValues::capture(&self.state));
+
+        let instruction = self.fetch_instruction()?;
+        instruction.execute(&mut self.state)?;
+
+        if let Some(before) = before {
+            let after = WatchedValues::capture(&self.state);
+            let accesses = self.state.memory.take_accesses();
+            if let Some(hit) = self
+                .debugger
+                .check_watchpoints(pc, &before, &after, &accesses)
+            {
+                return Ok(RunStatus::Stopped(StopReason::Watchpoint(hit)));
+            }
+        }
+        Ok(RunStatus::Completed)
+    }
+
+    /// Executes one frame's worth of instructions.
+    ///
+    /// The frame ends early if the machine halts, or after a sprite draw when
+    /// the display wait quirk is enabled. Timers are not touched; call
+    /// [`Machine::tick_timers`] once per frame to advance them.
+    ///
+    /// Before each instruction the [`Debugger`] is consulted, and the frame
+    /// stops without executing it if the program counter is on a breakpoint.
+    /// The frame also stops right after an instruction that triggers a
+    /// watchpoint.
+    pub fn run_frame(&mut self) -> anyhow::Result<RunStatus> {
+        self.state.awaiting_vblank = false;
+        for _ in 0..self.instructions_per_frame() {
+            if self.state.halted {
+                break;
+            }
+            if let Some(reason) = self.debugger.check(self.state.pc) {
+                return Ok(RunStatus::Stopped(reason));
+            }
+            if let RunStatus::Stopped(reason) = self.step()? {
+                return Ok(RunStatus::Stopped(reason));
+            }
+            if self.state.awaiting_vblank {
+                self.state.awaiting_vblank = false;
+                break;
+            }
+        }
+        Ok(RunStatus::Completed)
+    }
+
+    /// Returns true once the program has executed the SUPER-CHIP exit instruction.
+    pub fn is_halted(&self) -> bool {
+        self.state.halted
+    }
+
+    /// Decrements the delay and sound timers by one tick.
+    ///
+    /// CHIP-8 timers count down at 60 Hz, so this is normally called once per
+    /// frame.
+    pub fn tick_timers(&mut self) {
+        self.state.delay_timer = self.state.delay_timer.saturating_sub(1);
+        self.state.sound_timer = self.state.sound_timer.saturating_sub(1);
+    }
+
+    /// Returns true while the sound timer is non-zero and a tone should play.
+    pub fn is_sound_active(&self) -> bool {
+        self.state.sound_timer > 0
+    }
+
+    /// Returns the display framebuffer.
+    pub fn display(&self) -> &FrameBuffer {
+        &self.state.display
+    }
+
+    /// Returns whether the pixel at `(x, y)` is lit.
+    ///
+    /// Coordinates outside the current display resolution are reported as unlit.
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        self.state.display.get(x, y)
+    }
+
+    /// Returns a handle to the machine's keypad.
+    ///
+    /// The handle shares state with the machine, so it can be moved to another
+    /// thread that feeds key events.
+    pub fn keypad(&self) -> Keypad {
+        self.state.keypad.clone()
+    }
+
+    /// Marks a CHIP-8 key as pressed.
+    pub fn press_key(&self, key: Key) {
+        self.state.keypad.press_key(key);
+    }
+
+    /// Marks a CHIP-8 key as released.
+    pub fn release_key(&self, key: Key) {
+        self.state.keypad.release_key(key);
+    }
+}
@@ -5,11 +5,12 @@
 //!
 //! # Features
 //!
-//! - **Complete Instruction Set**: All 35 CHIP-8 instructions implemented
+//! - **Complete Instruction Set**: All 35 CHIP-8 instructions, plus the
+//!   SUPER-CHIP 1.1 and XO-CHIP extensions
 //! - **Terminal Display**: 64×32 pixel game screen rendered in terminal
 //! - **Audio Support**: Authentic beep sound using 440 Hz sine wave
 //! - **Configurable Timing**: Adjustable frame rate and instruction speed
-//! - **Keyboard Input**: Standard QWERTY to CHIP-8 keypad mapping
+//! - **Keyboard Input**: QWERTY to CHIP-8 keypad mapping, configurable per ROM
 //! - **ROM Loading**: Support for standard CHIP-8 ROM files
 //!
 //! # Usage
@@ -20,13 +21,65 @@
 //! chip8 --rom-path rom/tests/games/2-ibm-logo.ch8
 //! ```
 //!
-//! Optional parameters:
+//! `chip8 run --rom-path <ROM>` is equivalent.
+//!
+//! Optional parameters, which default to the values in the config file:
 //! - `--frame-rate`: Display refresh rate (default: 60 Hz)
 //! - `--ips`: Instructions per second (default: 700)
+//! - `--platform`: Instruction set to emulate, `chip8`, `schip` or `xochip` (default: chip8)
+//! - `--quirks`: Interpreter quirks preset (default: the platform's own preset)
+//! - `--vf-reset`, `--memory-increment`, `--clipping`, `--shift-in-place`,
+//!   `--jump-with-vx`, `--display-wait`: Override individual quirks with `true` or `false`
+//! - `--debug`: Start paused with the debugger panel shown
+//! - `--break`, `--watch`: Set breakpoints and watchpoints in debug mode
+//! - `--load-state`: Restore a save state before starting
+//! - `--rewind-frames`: Number of frames kept for rewinding (default: 600)
+//! - `--record`, `--replay`: Record an input movie, or replay one and verify
+//!   every frame
+//! - `--seed`: Seed the random number generator (default: random)
+//! - `--rng`: Random number generator, `xorshift`, `lcg` or `vip` (default: xorshift)
+//! - `--input`: Keyboard input backend, `rdev` or `crossterm` (default:
+//!   crossterm over SSH, rdev otherwise)
+//! - `--mute`: Disable sound
+//! - `--theme`: Colour theme, `default`, `amber`, `green`, `lcd` or `octo`
+//! - `--palette`, `--background`, `--foreground`: Replace the theme's pixel
+//!   colours
+//! - `--color-depth`: Colours the terminal can show, `truecolor`, `256` or
+//!   `16` (default: detected from `COLORTERM` and `TERM`)
+//! - `--renderer`: Draw each pixel as a `block`, pairs of pixels as
+//!   `half-block` characters or 2×4 pixels as `braille` dots (default: block)
+//! - `--scale`: Integer display scale (default: the largest that fits)
+//! - `--persistence`: Keep pixels visible after they are turned off to reduce
+//!   flicker, `fade` or `merge` (default: off)
+//! - `--decay-frames`: Frames pixels fade for with `--persistence fade`
+//!   (default: 4)
+//! - `--graphics`: Draw the display as a bitmap with `kitty` graphics or
+//!   `sixel`, or `auto` to use either if the terminal supports it (default: off)
+//! - `--graphics-scale`: Bitmap pixels per display pixel (default: the largest
+//!   that fits)
+//! - `--screenshot-format`: Image format of screenshots, `png` or `pbm`
+//!   (default: png)
+//! - `--screenshot-scale`: Scale of PNG screenshots (default: 8)
+//! - `--screenshot-at-frame`: Save a screenshot after the given number of
+//!   frames
+//! - `--capture`: Capture the run to a `.gif` or `.y4m` video
+//! - `--headless`, `--frames`: Run for a number of frames without a terminal,
+//!   sound or keyboard and print the final display
+//! - `--press`: Hold a key in headless mode, `KEY@FRAME` or `KEY@FIRST-LAST`
+//! - `--dump`: How headless mode prints the display, `ascii` or `hash`
+//!   (default: ascii)
+//! - `--expect`: Compare the headless display with a file, exiting with
+//!   status 1 if it differs
+//! - `--capture-format`: Video format of captures started with F11, `gif` or
+//!   `y4m` (default: gif)
+//! - `--capture-scale`: Scale of captured videos (default: 4)
+//! - `--capture-audio`: Also write the sound of captures to a WAV file
+//! - `--config`: Config file to read instead of `~/.config/chip8/config.toml`
+//! - `--database`: ROM database to search before the built-in one
 //!
 //! # Controls
 //!
-//! The CHIP-8 keypad is mapped to QWERTY keys:
+//! The CHIP-8 keypad is mapped to QWERTY keys by default:
 //!
 //! ```text
 //! CHIP-8 Keypad    QWERTY Keyboard
@@ -36,36 +89,838 @@
 //! A 0 B F          Z X C V
 //! ```
 //!
+//! The `[keymap]` section of the config file binds other host keys, globally
+//! or for a single ROM, and known ROMs add the arrow keys and other bindings
+//! from the ROM database. See the Keymaps section of the README.
+//!
 //! Press **Escape** to exit the emulator.
+//!
+//! **Shift+F1** to **Shift+F9** save the machine state to slots 1-9, stored
+//! next to the ROM as `<rom>.state1` to `<rom>.state9`, and **F1** to **F9**
+//! load them back. Hold **Backspace** to rewind. **F11** starts and stops
+//! capturing a video and **F12** saves a screenshot, both next to the ROM and
+//! named after the ROM and the time.
+//!
+//! # Configuration
+//!
+//! Defaults for every option above, plus the keymap and audio, can
+//! be set in a TOML config file, globally or per ROM. Command line options
+//! take precedence over the ROM's section, which takes precedence over the
+//! ROM's entry in the ROM database, which takes precedence over the global
+//! section. Print the effective configuration with:
+//!
+//! ```bash
+//! chip8 config dump --rom-path pong.ch8
+//! ```
+//!
+//! # Disassembly
+//!
+//! Print the disassembly of a ROM in Octo or Cowgod syntax:
+//!
+//! ```bash
+//! chip8 disasm rom/tests/games/2-ibm-logo.ch8 --syntax cowgod
+//! ```
+//!
+//! `--start` sets the address the ROM is loaded at and `--platform` enables the
+//! SUPER-CHIP and XO-CHIP instructions. Bytes that do not decode are printed as
+//! `db` data. `--trace` follows the control flow from the start address to
+//! separate code from data and prints a labelled listing, and `--dot` prints
+//! the control-flow graph for Graphviz.
+//!
+//! # Assembly
+//!
+//! Assemble a source file in Octo or Cowgod syntax into a ROM:
+//!
+//! ```bash
+//! chip8 asm game.8o -o game.ch8 --platform xochip
+//! ```
+//!
+//! The assembler accepts the output of `chip8 disasm --trace`, plus labels,
+//! `:const`/`equ` constants, `db`/`dw` data, quoted sprite rows such as
+//! `db "..####.."` and `include "file"`. The program must fit in the memory of
+//! the `--platform` it targets.
+//!
+//! # Debugging
+//!
+//! In debug mode, **p** pauses and continues, **n** single-steps, **o** steps
+//! over a subroutine call, **g** runs to the cursor and **b** toggles a
+//! breakpoint at the cursor. **m** prompts for a watchpoint and **u** clears
+//! all watchpoints. The cursor moves with the arrow keys, Page Up,
+//! Page Down and Home.
 
-use crate::emulator::Emulator;
-use crate::state::{DEFAULT_FRAME_RATE, DEFAULT_INSTRUCTIONS_PER_SECOND, Settings};
-use clap::Parser;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
-mod emulator;
-mod instruction;
-mod state;
+use anyhow::Context;
+use chip8::Machine;
+use chip8::analysis::trace;
+use chip8::asm::assemble_file;
+use chip8::capture::{CaptureFormat, DEFAULT_CAPTURE_SCALE};
+use chip8::config::Config;
+use chip8::debugger::{Watchpoint, parse_address};
+use chip8::disasm::{Syntax, disassemble};
+use chip8::emulator::Emulator;
+use chip8::graphics::GraphicsMode;
+use chip8::headless::{DumpFormat, KeyPress, run_batch};
+use chip8::input::InputBackend;
+use chip8::movie::Movie;
+use chip8::phosphor::{DEFAULT_DECAY_FRAMES, MAX_DECAY_FRAMES, Persistence};
+use chip8::render::Renderer;
+use chip8::rewind::DEFAULT_REWIND_FRAMES;
+use chip8::rng::RngKind;
+use chip8::romdb::{KnownRom, RomDatabase};
+use chip8::savestate::rom_hash;
+use chip8::screenshot::{DEFAULT_SCREENSHOT_SCALE, ScreenshotFormat};
+use chip8::state::{
+    Address, DEFAULT_FRAME_RATE, DEFAULT_INSTRUCTIONS_PER_SECOND, PC_START_ADDR, Platform,
+    QuirksPreset,
+};
+use chip8::theme::{ColorDepth, ThemeName};
+use clap::{CommandFactory, Parser, Subcommand, error::ErrorKind};
 
 #[doc(hidden)]
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-struct Args {
-    #[arg(short, long, default_value_t = DEFAULT_FRAME_RATE, help = "Frame rate in frames per second")]
-    frame_rate: u64,
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    run: Option<RunArgs>,
+}
 
-    #[arg(short, long, default_value_t = DEFAULT_INSTRUCTIONS_PER_SECOND, help = "Instructions per second")]
-    ips: u64,
+#[doc(hidden)]
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run a ROM in the terminal (the default when no subcommand is given)
+    Run(RunArgs),
+
+    /// Print the disassembly of a ROM
+    Disasm(DisasmArgs),
+
+    /// Assemble Octo or Cowgod source into a ROM
+    Asm(AsmArgs),
+
+    /// Inspect the configuration
+    Config(ConfigArgs),
+}
 
+#[doc(hidden)]
+#[derive(clap::Args, Debug)]
+// Flattened options leave the group's members empty, so name the ROM path,
+// which every run has, to make `Cli::run` present
+#[group(id = "run", multiple = true, args = ["rom_path"])]
+struct RunArgs {
     #[arg(short, long, help = "Path to the ROM file to run")]
     rom_path: String,
+
+    #[command(flatten)]
+    options: OptionArgs,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Restore a save state made with the same ROM before starting"
+    )]
+    load_state: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["debug", "replay", "load_state"],
+        help = "Record the keypad input of every frame into a movie file"
+    )]
+    record: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["debug", "load_state"],
+        help = "Replay a movie file, checking every frame against the recording"
+    )]
+    replay: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Save a screenshot next to the ROM after N frames have run"
+    )]
+    screenshot_at_frame: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Capture the run to a .gif or .y4m video file"
+    )]
+    capture: Option<PathBuf>,
+
+    #[arg(
+        long,
+        requires = "frames",
+        conflicts_with_all = ["debug", "record", "replay", "screenshot_at_frame", "capture"],
+        help = "Run without a terminal, sound or keyboard and print the final display"
+    )]
+    headless: bool,
+
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "headless",
+        help = "Number of frames to run in headless mode"
+    )]
+    frames: Option<u64>,
+
+    #[arg(
+        long = "press",
+        value_name = "KEY@FRAMES",
+        requires = "headless",
+        help = "Hold a key during a frame such as 5@30, or frames such as 5@30-40, in headless mode (repeatable)"
+    )]
+    presses: Vec<KeyPress>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        default_value_t = DumpFormat::Ascii,
+        requires = "headless",
+        help = "How headless mode prints the final display"
+    )]
+    dump: DumpFormat,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        requires = "headless",
+        help = "Compare the final display with a file and exit with status 1 if it differs"
+    )]
+    expect: Option<PathBuf>,
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
-    let settings = Settings::new(args.frame_rate, args.ips, args.rom_path);
-    let mut emulator = Emulator::new(settings)?;
+/// Options that can also be set in the config file. Unset options fall back
+/// to the config file and then to the built-in defaults.
+#[doc(hidden)]
+#[derive(clap::Args, Debug)]
+struct OptionArgs {
+    #[arg(
+        short,
+        long,
+        help = format!("Frame rate in frames per second [default: {}]", DEFAULT_FRAME_RATE)
+    )]
+    frame_rate: Option<u64>,
+
+    #[arg(
+        short,
+        long,
+        help = format!("Instructions per second [default: {}]", DEFAULT_INSTRUCTIONS_PER_SECOND)
+    )]
+    ips: Option<u64>,
+
+    #[arg(
+        short,
+        long,
+        value_enum,
+        help = "Platform whose instruction set the ROM targets [default: chip8]"
+    )]
+    platform: Option<Platform>,
+
+    #[arg(
+        short,
+        long,
+        value_enum,
+        help = "Quirks preset [default: the platform's preset]"
+    )]
+    quirks: Option<QuirksPreset>,
+
+    #[arg(
+        long,
+        value_name = "BOOL",
+        help_heading = "Quirks",
+        help = "Logic instructions reset VF"
+    )]
+    vf_reset: Option<bool>,
+
+    #[arg(
+        long,
+        value_name = "BOOL",
+        help_heading = "Quirks",
+        help = "FX55/FX65 increment the index register"
+    )]
+    memory_increment: Option<bool>,
+
+    #[arg(
+        long,
+        value_name = "BOOL",
+        help_heading = "Quirks",
+        help = "Clip sprites at the screen edges instead of wrapping"
+    )]
+    clipping: Option<bool>,
+
+    #[arg(
+        long,
+        value_name = "BOOL",
+        help_heading = "Quirks",
+        help = "8XY6/8XYE shift Vx in place, ignoring Vy"
+    )]
+    shift_in_place: Option<bool>,
+
+    #[arg(
+        long,
+        value_name = "BOOL",
+        help_heading = "Quirks",
+        help = "BNNN jumps to XNN plus Vx"
+    )]
+    jump_with_vx: Option<bool>,
+
+    #[arg(
+        long,
+        value_name = "BOOL",
+        help_heading = "Quirks",
+        help = "Sprite draws wait for the vertical blank in low resolution"
+    )]
+    display_wait: Option<bool>,
+
+    #[arg(long, help = "Start paused with the debugger panel shown")]
+    debug: bool,
+
+    #[arg(
+        long = "break",
+        value_name = "ADDR",
+        value_parser = parse_address,
+        help = "Set a breakpoint at a program address in debug mode (repeatable)"
+    )]
+    breakpoints: Vec<Address>,
+
+    #[arg(
+        long = "watch",
+        value_name = "SPEC",
+        help = "Set a watchpoint such as 'v3 == 0x10', 'i' or 'w 0x300-0x30f' in debug mode (repeatable)"
+    )]
+    watchpoints: Vec<Watchpoint>,
+
+    #[arg(
+        long,
+        value_name = "FRAMES",
+        help = format!(
+            "Number of frames kept for rewinding with Backspace (0 disables rewind) [default: {}]",
+            DEFAULT_REWIND_FRAMES
+        )
+    )]
+    rewind_frames: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "SEED",
+        value_parser = parse_seed,
+        help = "Seed the random number generator for reproducible runs (decimal or 0x-prefixed hex)"
+    )]
+    seed: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "KIND",
+        value_enum,
+        help = "Random number generator used by CXNN [default: xorshift]"
+    )]
+    rng: Option<RngKind>,
+
+    #[arg(long, help = "Disable sound")]
+    mute: bool,
+
+    #[arg(
+        long,
+        value_name = "BACKEND",
+        value_enum,
+        help = "Keyboard input backend [default: crossterm over SSH, rdev otherwise]"
+    )]
+    input: Option<InputBackend>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Colour theme of the screen and panels [default: default]"
+    )]
+    theme: Option<ThemeName>,
+
+    #[arg(
+        long,
+        value_name = "COLORS",
+        value_delimiter = ',',
+        help = "Four comma-separated colours for the background, plane 1, plane 2 and both planes, replacing the theme's"
+    )]
+    palette: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "COLOR",
+        help = "Background colour: a name, #rrggbb or 256-colour index"
+    )]
+    background: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "COLOR",
+        help = "Pixel colour: a name, #rrggbb or 256-colour index"
+    )]
+    foreground: Option<String>,
 
-    emulator.run()?;
+    #[arg(
+        long,
+        value_name = "DEPTH",
+        value_enum,
+        help = "Colours the terminal can show [default: from COLORTERM and TERM]"
+    )]
+    color_depth: Option<ColorDepth>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "How pixels are drawn: one block, two half blocks or eight Braille dots per cell [default: block]"
+    )]
+    renderer: Option<Renderer>,
+
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "Integer display scale [default: the largest that fits the terminal]"
+    )]
+    scale: Option<u16>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Keep pixels visible after they are turned off to reduce flicker [default: off]"
+    )]
+    persistence: Option<Persistence>,
+
+    #[arg(
+        long,
+        value_name = "FRAMES",
+        value_parser = clap::value_parser!(u8).range(1..=i64::from(MAX_DECAY_FRAMES)),
+        help = "Frames pixels fade for with --persistence fade [default: 4]"
+    )]
+    decay_frames: Option<u8>,
+
+    #[arg(
+        long,
+        value_name = "MODE",
+        value_enum,
+        help = "Draw the display as a bitmap with Kitty graphics or Sixel, or auto to use either if the terminal supports it [default: off]"
+    )]
+    graphics: Option<GraphicsMode>,
+
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = "Bitmap pixels per display pixel with --graphics [default: the largest that fits the terminal]"
+    )]
+    graphics_scale: Option<u16>,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_enum,
+        help = "Image format of screenshots [default: png]"
+    )]
+    screenshot_format: Option<ScreenshotFormat>,
+
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = format!("Image pixels per display pixel in PNG screenshots [default: {}]", DEFAULT_SCREENSHOT_SCALE)
+    )]
+    screenshot_scale: Option<u16>,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_enum,
+        help = "Video format of captures started with F11 [default: gif]"
+    )]
+    capture_format: Option<CaptureFormat>,
+
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u16).range(1..),
+        help = format!("Image pixels per display pixel in captured videos [default: {}]", DEFAULT_CAPTURE_SCALE)
+    )]
+    capture_scale: Option<u16>,
+
+    #[arg(
+        long,
+        help = "Also write the sound of captures to a WAV file next to the video"
+    )]
+    capture_audio: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Read settings from this TOML file instead of ~/.config/chip8/config.toml"
+    )]
+    config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Look ROMs up in this programs.json from the CHIP-8 database before the built-in one"
+    )]
+    database: Option<PathBuf>,
+}
+
+#[doc(hidden)]
+#[derive(clap::Args, Debug)]
+struct ConfigArgs {
+    #[command(subcommand)]
+    command: ConfigCommand,
+}
+
+#[doc(hidden)]
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Print the effective configuration as TOML
+    Dump(DumpArgs),
+}
+
+#[doc(hidden)]
+#[derive(clap::Args, Debug)]
+struct DumpArgs {
+    #[arg(short, long, help = "ROM whose section of the config file to apply")]
+    rom_path: Option<PathBuf>,
+
+    #[command(flatten)]
+    options: OptionArgs,
+}
+
+#[doc(hidden)]
+#[derive(clap::Args, Debug)]
+struct DisasmArgs {
+    #[arg(help = "Path to the ROM file to disassemble")]
+    rom_path: PathBuf,
+
+    #[arg(short, long, value_parser = parse_address, default_value_t = PC_START_ADDR, help = "Address the first ROM byte is loaded at")]
+    start: Address,
+
+    #[arg(short = 'y', long, value_enum, default_value_t = Syntax::Octo, help = "Assembly syntax of the mnemonics")]
+    syntax: Syntax,
+
+    #[arg(short, long, value_enum, default_value_t = Platform::Chip8, help = "Platform whose instruction set the ROM targets")]
+    platform: Platform,
+
+    #[arg(
+        short,
+        long,
+        help = "Trace control flow from the start address to separate code from data, with labels"
+    )]
+    trace: bool,
+
+    #[arg(
+        long,
+        help = "Print the traced control-flow graph in Graphviz DOT format"
+    )]
+    dot: bool,
+}
+
+#[doc(hidden)]
+#[derive(clap::Args, Debug)]
+struct AsmArgs {
+    #[arg(help = "Path to the assembly source file")]
+    source_path: PathBuf,
+
+    #[arg(
+        short,
+        long,
+        help = "Path of the ROM to write (default: the source path with a .ch8 extension)"
+    )]
+    output: Option<PathBuf>,
+
+    #[arg(short, long, value_parser = parse_address, default_value_t = PC_START_ADDR, help = "Address the first ROM byte is loaded at")]
+    start: Address,
+
+    #[arg(short, long, value_enum, default_value_t = Platform::Chip8, help = "Platform whose memory the ROM must fit in")]
+    platform: Platform,
+}
+
+impl OptionArgs {
+    /// The options given on the command line as a config layer.
+    fn to_config(&self) -> Config {
+        let mut config = Config::default();
+        config.timing.frame_rate = self.frame_rate;
+        config.timing.ips = self.ips;
+        config.machine.platform = self.platform;
+        config.machine.rng = self.rng;
+        config.machine.seed = self.seed;
+        config.machine.rewind_frames = self.rewind_frames;
+        config.quirks.preset = self.quirks;
+        config.quirks.vf_reset = self.vf_reset;
+        config.quirks.memory_increment = self.memory_increment;
+        config.quirks.clipping = self.clipping;
+        config.quirks.shift_in_place = self.shift_in_place;
+        config.quirks.jump_with_vx = self.jump_with_vx;
+        config.quirks.display_wait = self.display_wait;
+        config.audio.enabled = self.mute.then_some(false);
+        config.debugger.enabled = self.debug.then_some(true);
+        if !self.breakpoints.is_empty() {
+            config.debugger.breakpoints = Some(
+                self.breakpoints
+                    .iter()
+                    .map(|addr| format!("{:#05x}", addr))
+                    .collect(),
+            );
+        }
+        if !self.watchpoints.is_empty() {
+            config.debugger.watchpoints =
+                Some(self.watchpoints.iter().map(ToString::to_string).collect());
+        }
+        config.input.backend = self.input;
+        config.palette.theme = self.theme;
+        config.palette.colors = (!self.palette.is_empty()).then(|| self.palette.clone());
+        config.palette.background = self.background.clone();
+        config.palette.foreground = self.foreground.clone();
+        config.palette.depth = self.color_depth;
+        config.display.renderer = self.renderer;
+        config.display.scale = self.scale.map(usize::from);
+        config.display.persistence = self.persistence;
+        config.display.decay_frames = self.decay_frames;
+        config.graphics.protocol = self.graphics;
+        config.graphics.scale = self.graphics_scale.map(usize::from);
+        config.screenshot.format = self.screenshot_format;
+        config.screenshot.scale = self.screenshot_scale.map(usize::from);
+        config.capture.format = self.capture_format;
+        config.capture.scale = self.capture_scale.map(usize::from);
+        config.capture.audio = self.capture_audio.then_some(true);
+        config.database.path = self.database.clone();
+        config
+    }
+
+    /// Reads the config file, looks `rom` up in the ROM database and resolves
+    /// the configuration for running it, or for no ROM in particular.
+    fn effective_config(&self, rom: Option<&Path>) -> anyhow::Result<(Config, Option<KnownRom>)> {
+        let file = Config::load_or_default(self.config.as_deref())?;
+        let hash = rom
+            .map(|path| {
+                std::fs::read(path)
+                    .map(|data| rom_hash(&data))
+                    .with_context(|| format!("Cannot read ROM {}", path.display()))
+            })
+            .transpose()?;
+        let known = match &hash {
+            Some(hash) => {
+                let mut database = match self.database.as_ref().or(file.database.path.as_ref()) {
+                    Some(path) => RomDatabase::load(path)?,
+                    None => RomDatabase::default(),
+                };
+                database.extend(RomDatabase::bundled()?);
+                database.lookup(hash)
+            }
+            None => None,
+        };
+        let config = file.effective(&self.to_config(), rom.zip(hash.as_ref()), known.as_ref())?;
+        Ok((config, known))
+    }
+}
+
+fn main() -> anyhow::Result<ExitCode> {
+    let cli = Cli::parse();
+    let command = match (cli.command, cli.run) {
+        (Some(command), _) => command,
+        (None, Some(run)) => Command::Run(run),
+        (None, None) => Cli::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "a ROM path or a subcommand is required",
+            )
+            .exit(),
+    };
+
+    let result = match command {
+        Command::Run(args) if args.headless => return headless(args),
+        Command::Run(args) => run(args),
+        Command::Disasm(args) => disasm(args),
+        Command::Asm(args) => asm(args),
+        Command::Config(args) => match args.command {
+            ConfigCommand::Dump(args) => config_dump(args),
+        },
+    };
+    result.map(|()| ExitCode::SUCCESS)
+}
+
+/// Runs a ROM in the terminal emulator.
+fn run(args: RunArgs) -> anyhow::Result<()> {
+    let (config, known) = args
+        .options
+        .effective_config(Some(Path::new(&args.rom_path)))?;
+    if config.debugger.enabled != Some(true)
+        && (!args.options.breakpoints.is_empty() || !args.options.watchpoints.is_empty())
+    {
+        anyhow::bail!("--break and --watch need the debugger, enable it with --debug");
+    }
+    // Printed once the terminal is restored, as the emulator clears it
+    let hint = known.is_none().then(|| {
+        format!(
+            "{} is not in the ROM database, so it ran with the default settings. If it \
+             misbehaved, try other --platform, --quirks or --ips values, or pass the community \
+             CHIP-8 database with --database.",
+            args.rom_path
+        )
+    });
+    let mut emulator = Emulator::new(config.settings(args.rom_path)?, config.frontend_options())?;
+    if let Some(known) = &known {
+        emulator.set_title(known.title());
+    }
+    emulator.set_input_backend(config.input_backend());
+    emulator.set_keymap(config.keymap()?);
+    emulator.set_theme(config.theme()?);
+    emulator.set_renderer(
+        config.display.renderer.unwrap_or_default(),
+        config.display.scale,
+    );
+    emulator.set_persistence(
+        config.display.persistence.unwrap_or_default(),
+        config.display.decay_frames.unwrap_or(DEFAULT_DECAY_FRAMES),
+    );
+    emulator.set_graphics(
+        config.graphics.protocol.unwrap_or_default(),
+        config.graphics.scale,
+    );
+    emulator.set_screenshots(
+        config.screenshot.format.unwrap_or_default(),
+        config.screenshot.scale.unwrap_or(DEFAULT_SCREENSHOT_SCALE),
+        args.screenshot_at_frame,
+    );
+    emulator.set_capture(
+        config.capture.format.unwrap_or_default(),
+        config.capture.scale.unwrap_or(DEFAULT_CAPTURE_SCALE),
+        config.capture.audio == Some(true),
+    );
+    if let Some(path) = args.capture {
+        emulator.capture_to(path)?;
+    }
+    // Breakpoints would stop every frame without the debugger panel to resume
+    if config.debugger.enabled == Some(true) {
+        for addr in config.breakpoints()? {
+            emulator.debugger_mut().add_breakpoint(addr);
+        }
+        for watchpoint in config.watchpoints()? {
+            emulator.debugger_mut().add_watchpoint(watchpoint);
+        }
+    }
+    if let Some(path) = &args.load_state {
+        emulator.load_state_file(path)?;
+    }
+    if let Some(path) = &args.replay {
+        emulator.replay_movie(Movie::load(path)?)?;
+    }
+    if let Some(path) = args.record {
+        emulator.record_movie(path);
+    }
+
+    let result = emulator.run();
+    if let Some(hint) = hint {
+        eprintln!("{}", hint);
+    }
+    result?;
+
+    match emulator.replay_progress() {
+        Some((played, total)) if played == total => {
+            println!("Replay matched all {} frames", total)
+        }
+        Some((played, total)) => println!(
+            "Replay stopped after {} of {} frames, all of which matched",
+            played, total
+        ),
+        None => {}
+    }
 
     Ok(())
 }
+
+/// Runs a ROM for a fixed number of frames without a terminal and prints the
+/// final display, checking it against the expected output if given.
+fn headless(args: RunArgs) -> anyhow::Result<ExitCode> {
+    let (config, _) = args
+        .options
+        .effective_config(Some(Path::new(&args.rom_path)))?;
+    let rom = std::fs::read(&args.rom_path)
+        .with_context(|| format!("Cannot read ROM {}", args.rom_path))?;
+    let mut machine = Machine::new(config.settings(args.rom_path.clone())?);
+    machine.load_rom(&rom)?;
+    if let Some(path) = &args.load_state {
+        let data = std::fs::read(path)
+            .with_context(|| format!("Cannot read save state {}", path.display()))?;
+        machine.load_state(&data)?;
+    }
+
+    let report = run_batch(&mut machine, args.frames.unwrap_or_default(), &args.presses)?;
+    let dump = args.dump.dump(machine.display());
+    print!("{}", dump);
+    eprintln!(
+        "Ran {} frames{}, sound on for {} frames",
+        report.frames,
+        if report.halted { " until exit" } else { "" },
+        report.sound_frames
+    );
+
+    if let Some(path) = &args.expect {
+        let expected = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read expected output {}", path.display()))?;
+        if expected.trim_end() != dump.trim_end() {
+            eprintln!("The display does not match {}", path.display());
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints the configuration a run would use.
+fn config_dump(args: DumpArgs) -> anyhow::Result<()> {
+    let (config, known) = args.options.effective_config(args.rom_path.as_deref())?;
+    if let Some(known) = known {
+        println!("# {} (settings from the ROM database)", known.title());
+    }
+    print!("{}", config.to_toml()?);
+    Ok(())
+}
+
+/// Prints the address, raw instruction word and mnemonic of every instruction
+/// in a ROM, or with `--trace`/`--dot` a labelled listing or control-flow graph.
+fn disasm(args: DisasmArgs) -> anyhow::Result<()> {
+    let rom = std::fs::read(&args.rom_path)?;
+    if args.trace || args.dot {
+        let analysis = trace(&rom, args.start, args.platform, args.syntax);
+        if args.dot {
+            print!("{}", analysis.to_dot());
+        } else {
+            print!("{}", analysis.listing());
+        }
+        return Ok(());
+    }
+    for line in disassemble(&rom, args.start, args.platform, args.syntax) {
+        println!(
+            "{:04X}  {:<9}  {}",
+            line.address,
+            line.hex_words(),
+            line.text
+        );
+    }
+    Ok(())
+}
+
+/// Assembles a source file and writes the ROM.
+fn asm(args: AsmArgs) -> anyhow::Result<()> {
+    let rom = assemble_file(&args.source_path, args.start, args.platform)?;
+    let output = args
+        .output
+        .unwrap_or_else(|| args.source_path.with_extension("ch8"));
+    std::fs::write(&output, &rom)?;
+    println!("Wrote {} bytes to {}", rom.len(), output.display());
+    Ok(())
+}
+
+/// Parses a decimal or `0x`-prefixed hexadecimal seed.
+fn parse_seed(s: &str) -> anyhow::Result<u64> {
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|_| anyhow::anyhow!("Invalid seed: {}", s))
+}
@@ -0,0 +1,422 @@
+//! Input Movies
+//!
+//! A movie records everything needed to reproduce a run exactly: the ROM hash,
+//! the platform, quirks and instructions per frame, the random number
+//! generator and its seed, and the keypad state at the start of every frame.
+//! Each frame also stores a hash of the display after it ran, so a replay can
+//! verify that it produces the same frames and report the first one that
+//! differs.
+//!
+//! # Format
+//!
+//! Movies are plain text so that they can be diffed and kept next to tests:
+//!
+//! ```text
+//! chip8-movie 2
+//! rom 4cd6d5fa6a1b5a0d2f6c3dd1c7e8f2bb0b4b1e0c
+//! platform chip8
+//! quirks 23
+//! instructions-per-frame 11
+//! rng xorshift
+//! seed 9e3779b97f4a7c15
+//! frames
+//! 0000 5b1c0a9e83f2d417
+//! 0020 5b1c0a9e83f2d417
+//! ```
+//!
+//! Each frame line holds the pressed keys as a hexadecimal bitmask (bit N set
+//! while key N is held) followed by the display hash.
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{Context, anyhow, bail};
+
+use crate::machine::Machine;
+use crate::rng::RngKind;
+use crate::savestate::{RomHash, hex, quirk_bits, quirks_from_bits};
+use crate::state::{FrameBuffer, NUM_PLANES, Platform, Quirks};
+
+/// Version of the movie format written by [`Movie`]'s `Display` impl.
+pub const MOVIE_VERSION: u32 = 2;
+
+/// Header line identifying a movie file.
+const MAGIC: &str = "chip8-movie";
+
+/// A recorded input movie.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Movie {
+    /// SHA-1 hash of the ROM the movie was recorded with.
+    pub rom_hash: RomHash,
+    pub platform: Platform,
+    pub quirks: Quirks,
+    pub instructions_per_frame: u64,
+    /// Random number generator used by `CXNN`.
+    pub rng: RngKind,
+    /// State of the random number generator when the first frame started.
+    pub seed: u64,
+    pub frames: Vec<MovieFrame>,
+}
+
+/// Input and expected output of one frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MovieFrame {
+    /// Keys held during the frame, bit N for key N.
+    pub keys: u16,
+    /// Hash of the display after the frame, see [`display_hash`].
+    pub display_hash: u64,
+}
+
+/// The first frame at which a replay produced a different display.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Desync {
+    /// Zero-based frame number.
+    pub frame: usize,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+impl fmt::Display for Desync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Replay desynced at frame {}: expected display hash {:016x}, got {:016x}",
+            self.frame, self.expected, self.actual
+        )
+    }
+}
+
+impl Movie {
+    /// Starts an empty movie for the machine's loaded ROM and current
+    /// settings, taking the random number generator's state as the seed.
+    pub fn new(machine: &Machine) -> Self {
+        let state = machine.state();
+        Movie {
+            rom_hash: *machine.rom_hash(),
+            platform: state.settings.platform,
+            quirks: state.settings.quirks,
+            instructions_per_frame: machine.instructions_per_frame(),
+            rng: state.rng.kind(),
+            seed: state.rng.state(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Reads a movie file.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read movie {}", path.display()))?;
+        text.parse()
+            .with_context(|| format!("Invalid movie {}", path.display()))
+    }
+
+    /// Writes the movie to a file.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, self.to_string())
+            .with_context(|| format!("Cannot write movie {}", path.display()))
+    }
+
+    /// Prepares a machine with the movie's ROM loaded for replay: checks the
+    /// ROM and platform, and applies the quirks and random seed.
+    ///
+    /// The instructions per frame are part of the settings the machine was
+    /// created with and must match as well.
+    pub fn start(&self, machine: &mut Machine) -> anyhow::Result<()> {
+        if machine.rom_hash() != &self.rom_hash {
+            bail!(
+                "Movie was recorded with a different ROM (SHA-1 {}, this ROM is {})",
+                hex(&self.rom_hash),
+                hex(machine.rom_hash())
+            );
+        }
+        if machine.state().settings.platform != self.platform {
+            bail!(
+                "Movie was recorded on platform {:?}, but {:?} is running",
+                self.platform,
+                machine.state().settings.platform
+            );
+        }
+        if machine.instructions_per_frame() != self.instructions_per_frame {
+            bail!(
+                "Movie was recorded at {} instructions per frame, but {} are configured",
+                self.instructions_per_frame,
+                machine.instructions_per_frame()
+            );
+        }
+        let state = machine.state_mut();
+        state.settings.quirks = self.quirks;
+        state.rng = self.rng.create(self.seed);
+        Ok(())
+    }
+
+    /// Replays frame `frame` on a machine, returning a desync if the display
+    /// does not match the recording.
+    pub fn play_frame(
+        &self,
+        frame: usize,
+        machine: &mut Machine,
+    ) -> anyhow::Result<Option<Desync>> {
+        let expected = self
+            .frames
+            .get(frame)
+            .ok_or_else(|| anyhow!("Movie has no frame {}", frame))?;
+        let actual = run_frame(machine, expected.keys)?;
+        Ok((actual != expected.display_hash).then_some(Desync {
+            frame,
+            expected: expected.display_hash,
+            actual,
+        }))
+    }
+
+    /// Replays the whole movie on a machine with the movie's ROM loaded,
+    /// stopping at the first frame that desyncs.
+    pub fn replay(&self, machine: &mut Machine) -> anyhow::Result<Option<Desync>> {
+        self.start(machine)?;
+        for frame in 0..self.frames.len() {
+            if let Some(desync) = self.play_frame(frame, machine)? {
+                return Ok(Some(desync));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Runs a frame with the keys currently held on the machine's keypad and
+    /// appends it to the movie.
+    pub fn record_frame(&mut self, machine: &mut Machine) -> anyhow::Result<()> {
+        let keys = machine.keypad().pressed_mask();
+        let display_hash = run_frame(machine, keys)?;
+        self.frames.push(MovieFrame { keys, display_hash });
+        Ok(())
+    }
+}
+
+/// Runs one frame exactly as recorded movies do: sets the keypad, ticks the
+/// timers, runs the frame's instructions and hashes the resulting display.
+fn run_frame(machine: &mut Machine, keys: u16) -> anyhow::Result<u64> {
+    machine.keypad().set_pressed_mask(keys);
+    machine.tick_timers();
+    machine.run_frame()?;
+    Ok(display_hash(machine.display()))
+}
+
+/// Hashes the resolution mode and the contents of every display plane with
+/// 64-bit FNV-1a.
+pub fn display_hash(display: &FrameBuffer) -> u64 {
+    let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+    let mut feed = |byte: u8| {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+    };
+    feed(u8::from(display.is_hires()));
+    for plane in 0..NUM_PLANES {
+        display.plane_bytes(plane).into_iter().for_each(&mut feed);
+    }
+    hash
+}
+
+impl fmt::Display for Movie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {}", MAGIC, MOVIE_VERSION)?;
+        writeln!(f, "rom {}", hex(&self.rom_hash))?;
+        writeln!(f, "platform {}", platform_name(self.platform))?;
+        writeln!(f, "quirks {:02x}", quirk_bits(&self.quirks))?;
+        writeln!(f, "instructions-per-frame {}", self.instructions_per_frame)?;
+        writeln!(f, "rng {}", rng_name(self.rng))?;
+        writeln!(f, "seed {:016x}", self.seed)?;
+        writeln!(f, "frames")?;
+        for frame in &self.frames {
+            writeln!(f, "{:04x} {:016x}", frame.keys, frame.display_hash)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Movie {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut lines = s.lines().enumerate().map(|(idx, line)| (idx + 1, line));
+        let mut header = |key: &str| -> anyhow::Result<String> {
+            let (number, line) = lines
+                .next()
+                .ok_or_else(|| anyhow!("Missing '{}' line", key))?;
+            let value = line
+                .strip_prefix(key)
+                .and_then(|rest| rest.strip_prefix(' '))
+                .ok_or_else(|| anyhow!("Line {}: expected '{} ...'", number, key))?;
+            Ok(value.to_string())
+        };
+
+        let version = header(MAGIC)?;
+        if version != MOVIE_VERSION.to_string() {
+            bail!("Unsupported movie version {}", version);
+        }
+        let rom = header("rom")?;
+        let rom_hash = parse_hash(&rom).ok_or_else(|| anyhow!("Invalid ROM hash: {}", rom))?;
+        let platform = header("platform")?;
+        let platform = match platform.as_str() {
+            "chip8" => Platform::Chip8,
+            "schip" => Platform::SuperChip,
+            "xochip" => Platform::XoChip,
+            _ => bail!("Invalid platform: {}", platform),
+        };
+        let quirks = header("quirks")?;
+        let quirks =
+            u8::from_str_radix(&quirks, 16).map_err(|_| anyhow!("Invalid quirks: {}", quirks))?;
+        let ipf = header("instructions-per-frame")?;
+        let instructions_per_frame = ipf
+            .parse()
+            .map_err(|_| anyhow!("Invalid instructions per frame: {}", ipf))?;
+        let rng = header("rng")?;
+        let rng = match rng.as_str() {
+            "xorshift" => RngKind::Xorshift,
+            "lcg" => RngKind::Lcg,
+            "vip" => RngKind::Vip,
+            _ => bail!("Invalid random number generator: {}", rng),
+        };
+        let seed = header("seed")?;
+        let seed = u64::from_str_radix(&seed, 16).map_err(|_| anyhow!("Invalid seed: {}", seed))?;
+
+        match lines.next() {
+            Some((_, "frames")) => {}
+            Some((number, _)) => bail!("Line {}: expected 'frames'", number),
+            None => bail!("Missing 'frames' line"),
+        }
+        let frames = lines
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(number, line)| {
+                let (keys, hash) = line
+                    .split_once(' ')
+                    .ok_or_else(|| anyhow!("Line {}: expected '<keys> <hash>'", number))?;
+                Ok(MovieFrame {
+                    keys: u16::from_str_radix(keys, 16)
+                        .map_err(|_| anyhow!("Line {}: invalid keys '{}'", number, keys))?,
+                    display_hash: u64::from_str_radix(hash, 16)
+                        .map_err(|_| anyhow!("Line {}: invalid hash '{}'", number, hash))?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Movie {
+            rom_hash,
+            platform,
+            quirks: quirks_from_bits(quirks),
+            instructions_per_frame,
+            rng,
+            seed,
+            frames,
+        })
+    }
+}
+
+/// Command line name of a platform.
+fn platform_name(platform: Platform) -> &'static str {
+    match platform {
+        Platform::Chip8 => "chip8",
+        Platform::SuperChip => "schip",
+        Platform::XoChip => "xochip",
+    }
+}
+
+/// Name of a random number generator in movie files.
+fn rng_name(rng: RngKind) -> &'static str {
+    match rng {
+        RngKind::Xorshift => "xorshift",
+        RngKind::Lcg => "lcg",
+        RngKind::Vip => "vip",
+    }
+}
+
+/// Parses a 40-digit hexadecimal SHA-1 hash.
+fn parse_hash(text: &str) -> Option<RomHash> {
+    if text.len() != 40 {
+        return None;
+    }
+    let mut hash = [0; 20];
+    for (idx, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(text.get(idx * 2..idx * 2 + 2)?, 16).ok()?;
+    }
+    Some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::Settings;
+
+    /// Draws a digit at a random position every loop, clearing the screen
+    /// first while key 5 is held.
+    const ROM: [u8; 16] = [
+        0xC0, 0x3F, // v0 := random 0x3f
+        0xC1, 0x1F, // v1 := random 0x1f
+        0x62, 0x05, // v2 := 5
+        0xE2, 0xA1, // if v2 key then
+        0x00, 0xE0, //   clear
+        0xF0, 0x29, // i := hex v0
+        0xD0, 0x15, // sprite v0 v1 5
+        0x12, 0x00, // jump 0x200
+    ];
+
+    fn machine(seed: u64) -> Machine {
+        let mut settings = Settings::new(60, 600, "movie.ch8".to_string());
+        settings.seed = Some(seed);
+        let mut machine = Machine::new(settings);
+        machine.load_rom(&ROM).unwrap();
+        machine
+    }
+
+    /// Records 30 frames, holding key 5 during frames 10 to 12.
+    fn record() -> Movie {
+        let mut machine = machine(42);
+        let mut movie = Movie::new(&machine);
+        for frame in 0..30 {
+            let keys = if (10..=12).contains(&frame) {
+                1 << 5
+            } else {
+                0
+            };
+            machine.keypad().set_pressed_mask(keys);
+            movie.record_frame(&mut machine).unwrap();
+        }
+        movie
+    }
+
+    #[test]
+    fn parsed_movie_replays_without_desync() {
+        let text = record().to_string();
+        assert!(text.starts_with("chip8-movie 2\n"), "{}", text);
+        let movie: Movie = text.parse().unwrap();
+        assert_eq!(movie, record());
+        assert_eq!(movie.frames.len(), 30);
+        // The movie's seed replaces the machine's own
+        assert_eq!(movie.replay(&mut machine(7)).unwrap(), None);
+    }
+
+    #[test]
+    fn tampered_display_hash_desyncs() {
+        let movie = record();
+        let text = movie.to_string();
+        let line = format!(
+            "{:04x} {:016x}",
+            movie.frames[20].keys, movie.frames[20].display_hash
+        );
+        let tampered = format!(
+            "{:04x} {:016x}",
+            movie.frames[20].keys,
+            movie.frames[20].display_hash ^ 1
+        );
+        let tampered: Movie = text.replacen(&line, &tampered, 1).parse().unwrap();
+        let desync = tampered.replay(&mut machine(42)).unwrap().unwrap();
+        assert_eq!(desync.frame, 20);
+        assert_eq!(desync.actual, movie.frames[20].display_hash);
+    }
+
+    #[test]
+    fn tampered_keys_desync() {
+        let mut movie = record();
+        movie.frames[11].keys = 0;
+        let desync = movie.replay(&mut machine(42)).unwrap().unwrap();
+        assert_eq!(desync.frame, 11);
+    }
+}
@@ -0,0 +1,124 @@
+//! Phosphor Persistence
+//!
+//! CHIP-8 programs move sprites by erasing them with an XOR draw and drawing
+//! them again, so sprites are often missing from the frame shown and games
+//! flicker. A [`Phosphor`] filter keeps its own copy of the display and shows
+//! pixels for a little longer than the program keeps them lit, like the slow
+//! phosphor of old CRT monitors:
+//!
+//! - [`Persistence::Fade`] dims pixels over a number of frames after they are
+//!   turned off.
+//! - [`Persistence::Merge`] shows the pixels lit in either of the last two
+//!   frames.
+//!
+//! The filter only changes what is drawn. The machine's
+//! [`FrameBuffer`](crate::state::FrameBuffer), and with it sprite collisions,
+//! never sees it.
+
+use crate::state::FrameBuffer;
+
+/// Frames a pixel fades for by default with [`Persistence::Fade`].
+pub const DEFAULT_DECAY_FRAMES: u8 = 4;
+
+/// Longest fade supported, which keeps the number of distinct colours on
+/// screen within what Sixel graphics can show.
+pub const MAX_DECAY_FRAMES: u8 = 60;
+
+/// How long pixels stay visible after the program turns them off.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Persistence {
+    /// Show the display as it is.
+    #[default]
+    Off,
+
+    /// Dim pixels gradually after they are turned off.
+    Fade,
+
+    /// Show the pixels lit in the current or the previous frame.
+    Merge,
+}
+
+/// Display filter keeping pixels visible after they are turned off.
+#[derive(Clone, Debug)]
+pub struct Phosphor {
+    persistence: Persistence,
+    decay_frames: u8,
+    width: usize,
+    height: usize,
+    /// Colour index of each pixel in the current frame.
+    current: Vec<u8>,
+    /// Colour index of each pixel in the previous frame.
+    previous: Vec<u8>,
+    /// Colour index each pixel was last lit in, and the frames of fading left.
+    afterglow: Vec<(u8, u8)>,
+}
+
+impl Phosphor {
+    /// Creates a filter. `decay_frames` is how many frames pixels fade for
+    /// with [`Persistence::Fade`], at least 1.
+    pub fn new(persistence: Persistence, decay_frames: u8) -> Self {
+        Phosphor {
+            persistence,
+            decay_frames: decay_frames.clamp(1, MAX_DECAY_FRAMES),
+            width: 0,
+            height: 0,
+            current: Vec::new(),
+            previous: Vec::new(),
+            afterglow: Vec::new(),
+        }
+    }
+
+    /// Takes the next frame of `display`. Called once per emulated frame.
+    /// Switching resolution starts over with an empty afterglow.
+    pub fn update(&mut self, display: &FrameBuffer) {
+        let (width, height) = (display.width(), display.height());
+        if (width, height) != (self.width, self.height) {
+            self.width = width;
+            self.height = height;
+            self.current = vec![0; width * height];
+            self.previous = vec![0; width * height];
+            self.afterglow = vec![(0, 0); width * height];
+        }
+        std::mem::swap(&mut self.current, &mut self.previous);
+        for y in 0..height {
+            for x in 0..width {
+                let idx = y * width + x;
+                let color = display.color(x, y);
+                self.current[idx] = color;
+                let (glow_color, frames) = &mut self.afterglow[idx];
+                if color != 0 {
+                    *glow_color = color;
+                    *frames = self.decay_frames;
+                } else if self.previous[idx] == 0 {
+                    *frames = frames.saturating_sub(1);
+                }
+            }
+        }
+    }
+
+    /// The colour index of the pixel at `(x, y)` and its brightness, from 0
+    /// for invisible to 255 for fully lit. Pixels outside the last frame taken
+    /// are unlit.
+    pub fn pixel(&self, x: usize, y: usize) -> (u8, u8) {
+        if x >= self.width || y >= self.height {
+            return (0, 0);
+        }
+        let idx = y * self.width + x;
+        let current = self.current[idx];
+        match self.persistence {
+            Persistence::Off => (current, 255),
+            Persistence::Merge => (current | self.previous[idx], 255),
+            Persistence::Fade if current != 0 => (current, 255),
+            Persistence::Fade => {
+                let (color, frames) = self.afterglow[idx];
+                if frames == 0 {
+                    (0, 0)
+                } else {
+                    // Already dimmed in the first frame after turning off
+                    let brightness = u16::from(frames) * 255 / (u16::from(self.decay_frames) + 1);
+                    (color, brightness as u8)
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Display Renderers
+//!
+//! A [`Renderer`] turns the pixels of the CHIP-8 display into lines of
+//! coloured terminal cells. Terminal cells are about twice as tall as they are
+//! wide, so the renderers trade colour fidelity for resolution differently:
+//!
+//! | Renderer     | Pixels per cell | Colours per cell     |
+//! |--------------|-----------------|----------------------|
+//! | `block`      | 1×1             | 1                    |
+//! | `half-block` | 1×2             | 2 (foreground, back) |
+//! | `braille`    | 2×4             | 1 plus background    |
+//!
+//! Every renderer can scale the display by an integer factor first, and
+//! [`Renderer::fit_scale`] picks the largest factor that fits the terminal.
+//! The block renderer can also draw dimmed pixels with the shaded blocks
+//! `░▒▓`, see [`Renderer::render_shaded`].
+
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+
+/// How display pixels are drawn into terminal cells.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Renderer {
+    /// One full block `█` per pixel, so pixels are twice as tall as wide.
+    #[default]
+    Block,
+
+    /// Two pixels stacked in each cell with the `▀` and `▄` half blocks, so
+    /// pixels are square.
+    HalfBlock,
+
+    /// Eight pixels in each cell as the dots of a Braille character. Pixels
+    /// are small and square, but all dots of a cell share one colour.
+    Braille,
+}
+
+/// Bit of each dot of a Braille character, by row and column.
+const BRAILLE_DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+impl Renderer {
+    /// Display pixels packed into one terminal cell, across and down.
+    fn cell_pixels(self) -> (usize, usize) {
+        match self {
+            Renderer::Block => (1, 1),
+            Renderer::HalfBlock => (1, 2),
+            Renderer::Braille => (2, 4),
+        }
+    }
+
+    /// Terminal columns and rows needed for a display of `width`×`height`
+    /// pixels at `scale`.
+    pub fn size(self, width: usize, height: usize, scale: usize) -> (u16, u16) {
+        let (across, down) = self.cell_pixels();
+        (
+            (width * scale).div_ceil(across) as u16,
+            (height * scale).div_ceil(down) as u16,
+        )
+    }
+
+    /// The largest scale, at least 1, at which a display of `width`×`height`
+    /// pixels fits into `columns`×`rows` terminal cells.
+    pub fn fit_scale(self, width: usize, height: usize, columns: u16, rows: u16) -> usize {
+        (2..)
+            .take_while(|&scale| {
+                let (needed_columns, needed_rows) = self.size(width, height, scale);
+                needed_columns <= columns && needed_rows <= rows
+            })
+            .last()
+            .unwrap_or(1)
+    }
+
+    /// Renders a display of `width`×`height` pixels, scaled by `scale`, as one
+    /// line per row of cells. `pixel` returns the colour of a display pixel,
+    /// and pixels of the `background` colour are left blank.
+    pub fn render(
+        self,
+        width: usize,
+        height: usize,
+        scale: usize,
+        background: Color,
+        pixel: impl Fn(usize, usize) -> Color,
+    ) -> Vec<Line<'static>> {
+        self.render_shaded(width, height, scale, background, |x, y| (pixel(x, y), 255))
+    }
+
+    /// Renders like [`Renderer::render`], with `pixel` also returning the
+    /// brightness of a pixel from 0 to 255. The block renderer draws dimmed
+    /// pixels with shaded blocks, and the others ignore the brightness.
+    pub fn render_shaded(
+        self,
+        width: usize,
+        height: usize,
+        scale: usize,
+        background: Color,
+        pixel: impl Fn(usize, usize) -> (Color, u8),
+    ) -> Vec<Line<'static>> {
+        let shaded = |x: usize, y: usize| {
+            if x < width * scale && y < height * scale {
+                pixel(x / scale, y / scale)
+            } else {
+                (background, 0)
+            }
+        };
+        let scaled = |x: usize, y: usize| shaded(x, y).0;
+        let (columns, rows) = self.size(width, height, scale);
+        let (across, down) = self.cell_pixels();
+        (0..usize::from(rows))
+            .map(|row| {
+                let cells = (0..usize::from(columns)).map(|column| {
+                    let (x, y) = (column * across, row * down);
+                    match self {
+                        Renderer::Block => {
+                            let (color, brightness) = shaded(x, y);
+                            shaded_block_cell(color, brightness, background)
+                        }
+                        Renderer::HalfBlock => {
+                            half_block_cell(scaled(x, y), scaled(x, y + 1), background)
+                        }
+                        Renderer::Braille => {
+                            braille_cell(|dx, dy| scaled(x + dx, y + dy), background)
+                        }
+                    }
+                });
+                styled_line(cells)
+            })
+            .collect()
+    }
+}
+
+/// A cell showing one pixel.
+fn block_cell(color: Color, background: Color) -> (char, Style) {
+    let style = Style::default().fg(color).bg(background);
+    if color == background {
+        (' ', style)
+    } else {
+        ('█', style)
+    }
+}
+
+/// A cell showing one pixel, with a shaded block if it is dimmed.
+fn shaded_block_cell(color: Color, brightness: u8, background: Color) -> (char, Style) {
+    let (glyph, style) = block_cell(color, background);
+    let glyph = match brightness {
+        _ if glyph == ' ' => ' ',
+        0 => ' ',
+        1..=84 => '░',
+        85..=169 => '▒',
+        170..=254 => '▓',
+        255 => glyph,
+    };
+    (glyph, style)
+}
+
+/// A cell showing a pixel above another. The background is always drawn as a
+/// cell background, since it may be the terminal's default colour, which is
+/// not available as a foreground.
+fn half_block_cell(top: Color, bottom: Color, background: Color) -> (char, Style) {
+    if top == bottom {
+        block_cell(top, background)
+    } else if top == background {
+        ('▄', Style::default().fg(bottom).bg(background))
+    } else {
+        ('▀', Style::default().fg(top).bg(bottom))
+    }
+}
+
+/// A cell showing 2×4 pixels as Braille dots, in the most common colour other
+/// than the background.
+fn braille_cell(pixel: impl Fn(usize, usize) -> Color, background: Color) -> (char, Style) {
+    let mut dots = 0;
+    let mut colors: Vec<(Color, usize)> = Vec::new();
+    for (dy, row) in BRAILLE_DOTS.iter().enumerate() {
+        for (dx, &bit) in row.iter().enumerate() {
+            let color = pixel(dx, dy);
+            if color == background {
+                continue;
+            }
+            dots |= bit;
+            match colors.iter_mut().find(|(seen, _)| *seen == color) {
+                Some((_, count)) => *count += 1,
+                None => colors.push((color, 1)),
+            }
+        }
+    }
+    let color = colors
+        .iter()
+        .max_by_key(|&&(_, count)| count)
+        .map_or(background, |&(color, _)| color);
+    let glyph = match dots {
+        0 => ' ',
+        dots => char::from_u32(0x2800 + u32::from(dots)).unwrap_or(' '),
+    };
+    (glyph, Style::default().fg(color).bg(background))
+}
+
+/// Joins cells into a line with one span per run of cells sharing a style.
+fn styled_line(cells: impl Iterator<Item = (char, Style)>) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut run = String::new();
+    let mut run_style = Style::default();
+    for (glyph, style) in cells {
+        if style != run_style && !run.is_empty() {
+            spans.push(Span::styled(std::mem::take(&mut run), run_style));
+        }
+        run_style = style;
+        run.push(glyph);
+    }
+    if !run.is_empty() {
+        spans.push(Span::styled(run, run_style));
+    }
+    Line::from(spans)
+}
@@ -0,0 +1,240 @@
+//! Rewind History
+//!
+//! A ring buffer of per-frame machine snapshots for playing a program
+//! backwards. Snapshots are [save states](crate::savestate), and only the most
+//! recent one is kept in full. Every older frame is stored as the difference
+//! to the frame after it: the two snapshots are XORed, which leaves zeros
+//! wherever nothing changed, and the result is run-length encoded. A frame
+//! usually changes a few registers, some display bytes and little memory, so
+//! each delta takes tens of bytes rather than the full 4-64KB of memory.
+
+use std::collections::VecDeque;
+
+/// Default number of frames kept, 10 seconds at 60 frames per second.
+pub const DEFAULT_REWIND_FRAMES: usize = 600;
+
+/// Bounded history of machine snapshots, newest last.
+pub struct Rewind {
+    /// Maximum number of frames that can be rewound.
+    capacity: usize,
+
+    /// Full snapshot of the most recent frame.
+    latest: Option<Vec<u8>>,
+
+    /// Compressed deltas recreating each earlier frame from the one after it,
+    /// oldest first.
+    deltas: VecDeque<Delta>,
+}
+
+/// Run-length encoded XOR of two consecutive snapshots.
+struct Delta {
+    /// Length of the earlier snapshot.
+    len: usize,
+
+    /// Alternating runs: a varint count of unchanged bytes, a varint count of
+    /// changed bytes, then the changed bytes XORed with the later snapshot.
+    runs: Vec<u8>,
+}
+
+impl Rewind {
+    /// Creates an empty history holding up to `capacity` frames to rewind.
+    /// A capacity of 0 disables rewinding.
+    pub fn new(capacity: usize) -> Self {
+        Rewind {
+            capacity,
+            latest: None,
+            deltas: VecDeque::new(),
+        }
+    }
+
+    /// Returns false if rewinding is disabled and snapshots are not worth
+    /// taking.
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Number of frames that can currently be rewound.
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    /// Returns true if there is no earlier frame to rewind to.
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    /// Forgets every stored frame.
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+    }
+
+    /// Records the snapshot of a new frame, dropping the oldest frame once
+    /// the history is full.
+    pub fn push(&mut self, snapshot: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some(previous) = self.latest.take() {
+            self.deltas.push_back(Delta::encode(&previous, &snapshot));
+            if self.deltas.len() > self.capacity {
+                self.deltas.pop_front();
+            }
+        }
+        self.latest = Some(snapshot);
+    }
+
+    /// Steps one frame back, returning the snapshot of the frame before the
+    /// most recent one, which becomes the most recent frame.
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        let delta = self.deltas.pop_back()?;
+        let latest = self.latest.as_ref()?;
+        let previous = delta.apply(latest);
+        self.latest = Some(previous.clone());
+        Some(previous)
+    }
+}
+
+impl Delta {
+    /// Encodes the difference that turns `current` back into `previous`.
+    fn encode(previous: &[u8], current: &[u8]) -> Self {
+        let len = previous.len().max(current.len());
+        let xor = |idx: usize| {
+            previous.get(idx).copied().unwrap_or(0) ^ current.get(idx).copied().unwrap_or(0)
+        };
+
+        let mut runs = Vec::new();
+        let mut idx = 0;
+        while idx < len {
+            let unchanged = (idx..len).take_while(|&i| xor(i) == 0).count();
+            idx += unchanged;
+            let changed = (idx..len).take_while(|&i| xor(i) != 0).count();
+            write_varint(&mut runs, unchanged);
+            write_varint(&mut runs, changed);
+            runs.extend((idx..idx + changed).map(xor));
+            idx += changed;
+        }
+        Delta {
+            len: previous.len(),
+            runs,
+        }
+    }
+
+    /// Recreates the earlier snapshot from the later one.
+    fn apply(&self, current: &[u8]) -> Vec<u8> {
+        let mut previous = current.to_vec();
+        previous.resize(previous.len().max(self.len), 0);
+
+        let mut runs = self.runs.as_slice();
+        let mut idx = 0;
+        while !runs.is_empty() {
+            idx += read_varint(&mut runs);
+            let changed = read_varint(&mut runs);
+            for (byte, diff) in previous[idx..idx + changed].iter_mut().zip(runs) {
+                *byte ^= diff;
+            }
+            runs = &runs[changed..];
+            idx += changed;
+        }
+        previous.truncate(self.len);
+        previous
+    }
+}
+
+/// Appends `value` as an unsigned LEB128 varint.
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Reads an unsigned LEB128 varint written by [`write_varint`].
+fn read_varint(input: &mut &[u8]) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    while let Some((&byte, rest)) = input.split_first() {
+        *input = rest;
+        value |= usize::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `len` bytes that differ from one call to the next with another `seed`.
+    fn snapshot(len: usize, seed: u8) -> Vec<u8> {
+        (0..len)
+            .map(|i| (i as u8).wrapping_mul(31).wrapping_add(seed))
+            .collect()
+    }
+
+    #[test]
+    fn delta_round_trips_snapshots_of_different_lengths() {
+        // A 4KB platform's state followed by a 64KB one, and back
+        let small = snapshot(4096 + 200, 1);
+        let large = snapshot(65536 + 200, 2);
+        assert_eq!(Delta::encode(&small, &large).apply(&large), small);
+        assert_eq!(Delta::encode(&large, &small).apply(&small), large);
+        assert_eq!(Delta::encode(&small, &small).apply(&small), small);
+    }
+
+    #[test]
+    fn varints_of_128_and_more_round_trip() {
+        let values = [0, 1, 127, 128, 255, 300, 16383, 16384, 65536, usize::MAX];
+        let mut out = Vec::new();
+        for value in values {
+            write_varint(&mut out, value);
+        }
+        let mut input = out.as_slice();
+        for value in values {
+            assert_eq!(read_varint(&mut input), value);
+        }
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn delta_with_long_runs_round_trips() {
+        // 1000 unchanged bytes, 300 changed ones, then 200 unchanged
+        let previous = vec![0; 1500];
+        let mut current = previous.clone();
+        current[1000..1300].fill(0xAA);
+        let delta = Delta::encode(&previous, &current);
+        assert!(delta.runs.len() < 320);
+        assert_eq!(delta.apply(&current), previous);
+    }
+
+    #[test]
+    fn pop_after_the_history_is_full() {
+        let mut rewind = Rewind::new(3);
+        for frame in 0..6 {
+            rewind.push(snapshot(64, frame));
+        }
+        assert_eq!(rewind.len(), 3);
+        for frame in (2..5).rev() {
+            assert_eq!(rewind.pop(), Some(snapshot(64, frame)));
+        }
+        assert!(rewind.is_empty());
+        assert_eq!(rewind.pop(), None);
+
+        // The history continues from the frame rewound to
+        rewind.push(snapshot(64, 9));
+        assert_eq!(rewind.pop(), Some(snapshot(64, 2)));
+    }
+
+    #[test]
+    fn disabled_history_keeps_nothing() {
+        let mut rewind = Rewind::new(0);
+        rewind.push(snapshot(64, 0));
+        rewind.push(snapshot(64, 1));
+        assert!(!rewind.is_enabled());
+        assert_eq!(rewind.pop(), None);
+    }
+}
@@ -0,0 +1,245 @@
+//! Random Number Generators
+//!
+//! `CXNN` draws its random bytes from a [`RandomGenerator`] owned by
+//! [`Chip8State`](crate::state::Chip8State). Every generator's whole state
+//! fits in a `u64`, so that it can be seeded from the command line and saved
+//! in save states and movies, which makes runs of games using `CXNN`
+//! reproducible.
+
+/// A source of random bytes for the `CXNN` instruction.
+pub trait RandomGenerator: Send {
+    /// Advances the generator and returns the next random byte.
+    fn next_u8(&mut self) -> u8;
+
+    /// Which generator this is.
+    fn kind(&self) -> RngKind;
+
+    /// Returns the current state, which [`RngKind::create`] restores.
+    fn state(&self) -> u64;
+}
+
+/// The available random number generators.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "frontend", derive(clap::ValueEnum))]
+pub enum RngKind {
+    /// A 64-bit xorshift generator with good statistical quality.
+    #[default]
+    Xorshift,
+
+    /// The linear congruential generator of the C standard's example `rand()`,
+    /// used by many early emulators.
+    Lcg,
+
+    /// The COSMAC VIP interpreter's generator, see [`VipRng`].
+    Vip,
+}
+
+impl RngKind {
+    /// Creates a generator of this kind in state `seed`.
+    pub fn create(self, seed: u64) -> Box<dyn RandomGenerator> {
+        match self {
+            RngKind::Xorshift => Box::new(Xorshift::new(seed)),
+            RngKind::Lcg => Box::new(Lcg { state: seed as u32 }),
+            RngKind::Vip => Box::new(VipRng { seed: seed as u16 }),
+        }
+    }
+
+    /// Identifier used in save states and movies.
+    pub(crate) fn id(self) -> u8 {
+        match self {
+            RngKind::Xorshift => 0,
+            RngKind::Lcg => 1,
+            RngKind::Vip => 2,
+        }
+    }
+
+    /// Looks up a generator by the identifier returned by [`RngKind::id`].
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(RngKind::Xorshift),
+            1 => Some(RngKind::Lcg),
+            2 => Some(RngKind::Vip),
+            _ => None,
+        }
+    }
+}
+
+/// 64-bit xorshift generator.
+pub struct Xorshift {
+    state: u64,
+}
+
+impl Xorshift {
+    /// Creates a generator from a seed. A zero seed, which would make xorshift
+    /// return zeros forever, is replaced by a fixed non-zero value.
+    pub fn new(seed: u64) -> Self {
+        Xorshift {
+            state: if seed == 0 {
+                0x9E37_79B9_7F4A_7C15
+            } else {
+                seed
+            },
+        }
+    }
+}
+
+impl RandomGenerator for Xorshift {
+    fn next_u8(&mut self) -> u8 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state >> 56) as u8
+    }
+
+    fn kind(&self) -> RngKind {
+        RngKind::Xorshift
+    }
+
+    fn state(&self) -> u64 {
+        self.state
+    }
+}
+
+/// Linear congruential generator `state = state * 1103515245 + 12345`,
+/// returning bits 16-23.
+pub struct Lcg {
+    state: u32,
+}
+
+impl RandomGenerator for Lcg {
+    fn next_u8(&mut self) -> u8 {
+        self.state = self.state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        (self.state >> 16) as u8
+    }
+
+    fn kind(&self) -> RngKind {
+        RngKind::Lcg
+    }
+
+    fn state(&self) -> u64 {
+        u64::from(self.state)
+    }
+}
+
+/// A generator modelled on the random number routine of the CHIP-8
+/// interpreter on the COSMAC VIP.
+///
+/// The seed is 16 bits wide, as in the VIP's register R9. Each `CXNN`
+/// increments the seed and adds the byte its low half indexes in
+/// [`VIP_TABLE`], the interpreter's own code, to its high half. That sum,
+/// rotated right through the carry and added to itself, becomes both the
+/// random number and the new high half. Like the original, the generator is
+/// poor and short cycles are common.
+pub struct VipRng {
+    seed: u16,
+}
+
+/// Page `0x01xx` of the COSMAC VIP's CHIP-8 interpreter, which its random
+/// number routine reads as a table.
+#[rustfmt::skip]
+const VIP_TABLE: [u8; 256] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x45, 0xA3, 0x98, 0x56, 0xD4, 0xF8, 0x81, 0xBC, 0xF8, 0x95, 0xAC,
+    0x22, 0xDC, 0x12, 0x56, 0xD4, 0x06, 0xB8, 0xD4, 0x06, 0xA8, 0xD4, 0x64, 0x0A, 0x01, 0xE6, 0x8A,
+    0xF4, 0xAA, 0x3B, 0x28, 0x9A, 0xFC, 0x01, 0xBA, 0xD4, 0xF8, 0x81, 0xBA, 0x06, 0xFA, 0x0F, 0xAA,
+    0x0A, 0xAA, 0xD4, 0xE6, 0x06, 0xBF, 0x93, 0xBE, 0xF8, 0x1B, 0xAE, 0x2A, 0x1A, 0xF8, 0x00, 0x5A,
+    0x0E, 0xF5, 0x3B, 0x4B, 0x56, 0x0A, 0xFC, 0x01, 0x5A, 0x30, 0x40, 0x4E, 0xF6, 0x3B, 0x3C, 0x9F,
+    0x56, 0x2A, 0x2A, 0xD4, 0x00, 0x22, 0x86, 0x52, 0xF8, 0xF0, 0xA7, 0x07, 0x5A, 0x87, 0xF3, 0x17,
+    0x1A, 0x3A, 0x5B, 0x12, 0xD4, 0x22, 0x86, 0x52, 0xF8, 0xF0, 0xA7, 0x0A, 0x57, 0x87, 0xF3, 0x17,
+    0x2A, 0x3A, 0x6B, 0x12, 0xD4, 0x15, 0x85, 0x22, 0x73, 0x95, 0x52, 0x25, 0x45, 0xA5, 0x86, 0xFA,
+    0x0F, 0xB5, 0xD4, 0x45, 0xE6, 0xF3, 0x3A, 0x82, 0x15, 0x15, 0xD4, 0x45, 0xE6, 0xF3, 0x3A, 0x88,
+    0xD4, 0x45, 0x07, 0x30, 0x8C, 0x45, 0x07, 0x30, 0x84, 0xE6, 0x62, 0x26, 0x45, 0xA3, 0x36, 0x88,
+    0xD4, 0x3E, 0x88, 0xD4, 0xF8, 0xF0, 0xA7, 0xE7, 0x45, 0xF4, 0xA5, 0x86, 0xFA, 0x0F, 0x3B, 0xB2,
+    0xFC, 0x01, 0xB5, 0xD4, 0x45, 0x56, 0xD4, 0x45, 0xE6, 0xF4, 0x56, 0xD4, 0x45, 0xFA, 0x0F, 0x3A,
+    0xC4, 0x07, 0x56, 0xD4, 0xAF, 0x22, 0xF8, 0xD3, 0x73, 0x8F, 0xF9, 0xF0, 0x52, 0xE6, 0x07, 0xD2,
+    0x56, 0xF8, 0xFF, 0xA6, 0xF8, 0x00, 0x7E, 0x56, 0xD4, 0x19, 0x89, 0xAE, 0x93, 0xBE, 0x99, 0xEE,
+    0xF4, 0x56, 0x76, 0xE6, 0xF4, 0xB9, 0x56, 0x45, 0xF2, 0x56, 0xD4, 0x45, 0xAA, 0x86, 0xFA, 0x0F,
+    0xBA, 0xD4, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+impl RandomGenerator for VipRng {
+    fn next_u8(&mut self) -> u8 {
+        self.seed = self.seed.wrapping_add(1);
+        let [high, low] = self.seed.to_be_bytes();
+        let (sum, carry) = high.overflowing_add(VIP_TABLE[usize::from(low)]);
+        let rotated = (sum >> 1) | (u8::from(carry) << 7);
+        let random = rotated.wrapping_add(sum);
+        self.seed = u16::from_be_bytes([random, low]);
+        random
+    }
+
+    fn kind(&self) -> RngKind {
+        RngKind::Vip
+    }
+
+    fn state(&self) -> u64 {
+        u64::from(self.seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(kind: RngKind, seed: u64, count: usize) -> Vec<u8> {
+        let mut rng = kind.create(seed);
+        (0..count).map(|_| rng.next_u8()).collect()
+    }
+
+    #[test]
+    fn xorshift_known_answers() {
+        assert_eq!(
+            bytes(RngKind::Xorshift, 0x0123_4567_89AB_CDEF, 8),
+            [0x3F, 0x60, 0xC6, 0xBD, 0xAC, 0x22, 0x79, 0xF7]
+        );
+        // A zero seed is replaced rather than returning zeros forever
+        assert_ne!(bytes(RngKind::Xorshift, 0, 8), [0; 8]);
+    }
+
+    #[test]
+    fn lcg_known_answers() {
+        // The low bytes of the C standard's example rand() seeded with 1:
+        // 16838, 5758, 10113, 17515, 31051, 5627, 23010, 7419
+        assert_eq!(
+            bytes(RngKind::Lcg, 1, 8),
+            [0xC6, 0x7E, 0x81, 0x6B, 0x4B, 0xFB, 0xE2, 0xFB]
+        );
+    }
+
+    #[test]
+    fn vip_known_answers() {
+        // R9 0x1234 steps to 0x1235, and 0x12 plus the table byte at 0x135,
+        // 0xBF, is 0xD1 without carry. 0xD1 rotated right is 0x68, and 0x68
+        // plus 0xD1 is 0x39, the random number and the new high half.
+        assert_eq!(
+            bytes(RngKind::Vip, 0x1234, 8),
+            [0x39, 0x32, 0x68, 0x10, 0x40, 0x65, 0xD6, 0x68]
+        );
+        // 0xF0 plus the byte at 0x1F1, 0xD4, carries into the rotation:
+        // 0xC4 becomes 0xE2, and 0xE2 plus 0xC4 is 0xA6
+        assert_eq!(bytes(RngKind::Vip, 0xF0F0, 2), [0xA6, 0xF9]);
+    }
+
+    #[test]
+    fn vip_table_is_the_interpreter_page() {
+        // The digit place values of FX33 at 0x11B and the start of the CXNN
+        // routine at 0x1D9
+        assert_eq!(VIP_TABLE[0x1B..0x1E], [0x64, 0x0A, 0x01]);
+        assert_eq!(VIP_TABLE[0xD9..0xDC], [0x19, 0x89, 0xAE]);
+    }
+
+    #[test]
+    fn same_seed_same_sequence() {
+        for kind in [RngKind::Xorshift, RngKind::Lcg, RngKind::Vip] {
+            assert_eq!(bytes(kind, 1234, 64), bytes(kind, 1234, 64), "{:?}", kind);
+
+            // Restoring the state continues the same sequence
+            let mut rng = kind.create(1234);
+            let _ = rng.next_u8();
+            let mut restored = kind.create(rng.state());
+            assert_eq!(rng.kind(), kind);
+            for _ in 0..64 {
+                assert_eq!(rng.next_u8(), restored.next_u8(), "{:?}", kind);
+            }
+        }
+    }
+}
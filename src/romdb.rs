@@ -0,0 +1,290 @@
+//! ROM Database
+//!
+//! Recommended settings for known ROMs, looked up by the SHA-1 hash of the
+//! ROM file. The database uses the `programs.json` format of the community
+//! [CHIP-8 database](https://github.com/chip-8/chip-8-database): a list of
+//! programs, each with a title, authors and its ROM files keyed by hash:
+//!
+//! ```json
+//! [
+//!   {
+//!     "title": "Pong",
+//!     "authors": ["Paul Vervalin"],
+//!     "roms": {
+//!       "<sha1>": {
+//!         "file": "pong.ch8",
+//!         "platforms": ["originalChip8", "superchip"],
+//!         "quirkyPlatforms": { "superchip": { "shift": false } },
+//!         "tickrate": 15,
+//!         "colors": { "pixels": ["#000000", "#ffcc00"] },
+//!         "keys": { "player1Up": 1, "player1Down": 4 }
+//!       }
+//!     }
+//!   }
+//! ]
+//! ```
+//!
+//! Fields this emulator has no use for, such as images or font styles, are
+//! ignored. A small database covering the test ROMs is built in, and the full
+//! community `programs.json` can be loaded on top of it with `--database`.
+//!
+//! A ROM's settings are a layer of the [configuration](crate::config), below
+//! the ROM's own section of the config file and above the global section:
+//!
+//! - The first platform listed that this emulator supports selects the
+//!   platform and its quirks preset, with the ROM's `quirkyPlatforms`
+//!   changes for that platform on top.
+//! - `tickrate`, the instructions per frame, sets the instructions per
+//!   second for the configured frame rate.
+//! - `colors.pixels` sets the background and foreground colours, or the whole
+//!   XO-CHIP palette if it has four colours or more.
+//! - `keys` adds the arrow keys to the CHIP-8 keys used for up, down, left
+//!   and right, space to the key for `a` and enter to the key for `b`.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::config::{Config, KeyNames, KeymapConfig};
+use crate::input::Keymap;
+use crate::savestate::{RomHash, hex};
+use crate::state::{Platform, QuirksPreset};
+
+/// Database of known ROMs.
+#[derive(Clone, Debug, Default)]
+pub struct RomDatabase {
+    programs: Vec<Program>,
+}
+
+/// A program and all known ROM files of it.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Program {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub release: Option<String>,
+    #[serde(default)]
+    pub authors: Vec<String>,
+
+    /// ROM files, keyed by SHA-1 hash.
+    #[serde(default)]
+    pub roms: BTreeMap<String, RomEntry>,
+}
+
+/// Recommended settings for one ROM file.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RomEntry {
+    #[serde(default)]
+    pub file: Option<String>,
+
+    /// Platforms the ROM runs on, best first, by database platform id.
+    #[serde(default)]
+    pub platforms: Vec<String>,
+
+    /// Quirk changes needed on some platforms, by database platform id and
+    /// database quirk name.
+    #[serde(default)]
+    pub quirky_platforms: BTreeMap<String, BTreeMap<String, bool>>,
+
+    /// Instructions per frame.
+    #[serde(default)]
+    pub tickrate: Option<u64>,
+
+    #[serde(default)]
+    pub colors: Option<RomColors>,
+
+    /// CHIP-8 key for each input, named `up`, `down`, `left`, `right`, `a`,
+    /// `b` or `player1Up` to `player2Right`.
+    #[serde(default)]
+    pub keys: BTreeMap<String, u8>,
+}
+
+/// Colours recommended for a ROM.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct RomColors {
+    /// `#rrggbb` colours of the background, plane 1, plane 2 and both planes.
+    #[serde(default)]
+    pub pixels: Vec<String>,
+}
+
+/// A ROM found in the database.
+#[derive(Clone, Debug)]
+pub struct KnownRom {
+    pub program: Program,
+    pub rom: RomEntry,
+}
+
+/// The built-in database.
+const BUNDLED: &str = include_str!("../data/programs.json");
+
+/// Host keys added for the inputs of [`RomEntry::keys`]. Player 2 inputs
+/// keep their keys on the QWERTY keypad.
+const INPUT_KEYS: [(&str, &str); 10] = [
+    ("up", "up"),
+    ("player1Up", "up"),
+    ("down", "down"),
+    ("player1Down", "down"),
+    ("left", "left"),
+    ("player1Left", "left"),
+    ("right", "right"),
+    ("player1Right", "right"),
+    ("a", "space"),
+    ("b", "enter"),
+];
+
+impl RomDatabase {
+    /// The built-in database.
+    pub fn bundled() -> anyhow::Result<Self> {
+        RomDatabase::parse(BUNDLED).context("Invalid built-in ROM database")
+    }
+
+    /// Reads a `programs.json` file.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read ROM database {}", path.display()))?;
+        RomDatabase::parse(&text)
+            .with_context(|| format!("Invalid ROM database {}", path.display()))
+    }
+
+    /// Parses the contents of a `programs.json` file.
+    pub fn parse(json: &str) -> anyhow::Result<Self> {
+        Ok(RomDatabase {
+            programs: serde_json::from_str(json)?,
+        })
+    }
+
+    /// Adds the programs of `other`, which are looked up after this
+    /// database's own.
+    pub fn extend(&mut self, other: RomDatabase) {
+        self.programs.extend(other.programs);
+    }
+
+    /// Looks up a ROM by its hash.
+    pub fn lookup(&self, hash: &RomHash) -> Option<KnownRom> {
+        let hash = hex(hash);
+        self.programs.iter().find_map(|program| {
+            let rom = program
+                .roms
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(&hash))
+                .map(|(_, rom)| rom)?;
+            Some(KnownRom {
+                program: program.clone(),
+                rom: rom.clone(),
+            })
+        })
+    }
+}
+
+impl KnownRom {
+    /// The title followed by the authors, if known.
+    pub fn title(&self) -> String {
+        if self.program.authors.is_empty() {
+            self.program.title.clone()
+        } else {
+            format!(
+                "{} by {}",
+                self.program.title,
+                self.program.authors.join(", ")
+            )
+        }
+    }
+
+    /// The ROM's settings as a config layer, with the tickrate converted to
+    /// instructions per second at `frame_rate`. The keymap is left empty, see
+    /// [`KnownRom::keymap_config`].
+    ///
+    /// If `platform` is set, because a higher layer chose the platform, the
+    /// quirks are those the database lists for that platform, if any.
+    pub fn config(&self, frame_rate: u64, platform: Option<Platform>) -> Config {
+        let mut config = Config::default();
+        if let Some((id, platform, preset)) = self.platform(platform) {
+            config.machine.platform = Some(platform);
+            config.quirks.preset = Some(preset);
+            if id == "modernChip8" {
+                config.quirks.vf_reset = Some(false);
+                config.quirks.display_wait = Some(false);
+            }
+            for (quirk, &value) in self.rom.quirky_platforms.get(id).into_iter().flatten() {
+                let quirks = &mut config.quirks;
+                match quirk.as_str() {
+                    "shift" => quirks.shift_in_place = Some(value),
+                    "memoryLeaveIUnchanged" => quirks.memory_increment = Some(!value),
+                    // I is incremented, if only by X instead of X + 1
+                    "memoryIncrementByX" if value => quirks.memory_increment = Some(true),
+                    "wrap" => quirks.clipping = Some(!value),
+                    "jump" => quirks.jump_with_vx = Some(value),
+                    "vblank" => quirks.display_wait = Some(value),
+                    "logic" => quirks.vf_reset = Some(value),
+                    _ => {}
+                }
+            }
+        }
+        config.timing.ips = self.rom.tickrate.map(|tickrate| tickrate * frame_rate);
+        let pixels = self.rom.colors.iter().flat_map(|colors| &colors.pixels);
+        if pixels.clone().count() >= 4 {
+            config.palette.colors = Some(pixels.take(4).cloned().collect());
+        } else {
+            let mut pixels = pixels.cloned();
+            config.palette.background = pixels.next();
+            config.palette.foreground = pixels.next();
+        }
+        config
+    }
+
+    /// Keymap changes binding the arrow keys, space and enter to the ROM's
+    /// inputs, in addition to the host keys `keymap` already binds to them.
+    pub fn keymap_config(&self, keymap: &Keymap) -> KeymapConfig {
+        let current = keymap.to_config();
+        let mut changes = KeymapConfig::new();
+        let mut used = Vec::new();
+        for (input, host) in INPUT_KEYS {
+            let Some(&key) = self.rom.keys.get(input) else {
+                continue;
+            };
+            if key > 0xF || used.contains(&host) {
+                continue;
+            }
+            used.push(host);
+            let hosts = changes
+                .entry(format!("{:x}", key))
+                .or_insert_with_key(|name| {
+                    current
+                        .get(name)
+                        .cloned()
+                        .unwrap_or(KeyNames::Many(Vec::new()))
+                });
+            if let KeyNames::Many(hosts) = hosts {
+                hosts.push(host.to_string());
+            }
+        }
+        changes
+    }
+
+    /// The first of the ROM's platforms this emulator supports, or the first
+    /// running on `wanted`, as the database id, the platform and its quirks
+    /// preset.
+    fn platform(&self, wanted: Option<Platform>) -> Option<(&str, Platform, QuirksPreset)> {
+        self.rom.platforms.iter().find_map(|id| {
+            let (platform, preset) = match id.as_str() {
+                "originalChip8" | "hybridVIP" | "modernChip8" => {
+                    (Platform::Chip8, QuirksPreset::Vip)
+                }
+                "chip48" => (Platform::Chip8, QuirksPreset::Chip48),
+                "superchip1" | "superchip" => (Platform::SuperChip, QuirksPreset::SchipLegacy),
+                "xochip" => (Platform::XoChip, QuirksPreset::XoChip),
+                _ => return None,
+            };
+            (wanted.is_none() || wanted == Some(platform)).then_some((
+                id.as_str(),
+                platform,
+                preset,
+            ))
+        })
+    }
+}
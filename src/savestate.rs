@@ -0,0 +1,341 @@
+//! Save States
+//!
+//! Serializes the complete [`Chip8State`] into a versioned binary format so
+//! that a running program can be written to disk and restored later. The
+//! header records the SHA-1 hash of the ROM the state was made with, and a
+//! state is only ever restored on top of the same ROM.
+//!
+//! # Format
+//!
+//! All multi-byte integers are little-endian.
+//!
+//! | Field            | Size        | Contents                                      |
+//! |------------------|-------------|-----------------------------------------------|
+//! | Magic            | 4           | `C8SS`                                        |
+//! | Version          | 2           | [`SAVE_STATE_VERSION`]                        |
+//! | ROM hash         | 20          | SHA-1 of the ROM file                         |
+//! | Platform         | 1           | 0 CHIP-8, 1 SUPER-CHIP, 2 XO-CHIP             |
+//! | Quirks           | 1           | One bit per quirk, in [`Quirks`] field order  |
+//! | PC, index        | 2 + 2       |                                               |
+//! | Stack            | 2 + 2 × n   | Depth, then return addresses                  |
+//! | Timers           | 1 + 1       | Delay, sound                                  |
+//! | Registers        | 16          | V0-VF                                         |
+//! | RPL flags        | 16          |                                               |
+//! | Audio pattern    | 16          |                                               |
+//! | Pitch            | 1           |                                               |
+//! | Flags            | 1           | Bit 0 halted, 1 awaiting vblank, 2 hires      |
+//! | Selected planes  | 1           |                                               |
+//! | Display planes   | 2 × 1024    | 128×64 bits per plane, MSB first              |
+//! | RNG              | 1 + 8       | Generator kind, then its state                |
+//! | Memory           | 4 + n       | Length, then contents                         |
+
+use anyhow::{Context, anyhow, bail};
+
+use crate::rng::RngKind;
+use crate::state::{
+    Chip8State, HIRES_DISPLAY_HEIGHT, HIRES_DISPLAY_WIDTH, NUM_PLANES, NUM_REGISTERS, Platform,
+    Quirks, Register,
+};
+
+/// Version of the save state format written by [`save_state`].
+pub const SAVE_STATE_VERSION: u16 = 2;
+
+/// Magic bytes at the start of every save state.
+const MAGIC: &[u8; 4] = b"C8SS";
+
+/// Bytes in one serialized display plane.
+const PLANE_BYTES: usize = HIRES_DISPLAY_WIDTH * HIRES_DISPLAY_HEIGHT / 8;
+
+/// SHA-1 hash identifying a ROM.
+pub type RomHash = [u8; 20];
+
+/// Computes the SHA-1 hash of ROM data.
+pub fn rom_hash(rom: &[u8]) -> RomHash {
+    sha1_smol::Sha1::from(rom).digest().bytes()
+}
+
+/// Serializes the machine state, recording `rom_hash` in the header.
+///
+/// The keypad and the emulator settings other than the platform and quirks are
+/// not part of the state.
+pub fn save_state(state: &Chip8State, rom_hash: &RomHash) -> anyhow::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(state.memory.size() + 2 * PLANE_BYTES + 128);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&SAVE_STATE_VERSION.to_le_bytes());
+    out.extend_from_slice(rom_hash);
+
+    out.push(platform_id(state.settings.platform));
+    out.push(quirk_bits(&state.settings.quirks));
+    out.extend_from_slice(&address(state.pc)?.to_le_bytes());
+    out.extend_from_slice(&address(state.index)?.to_le_bytes());
+
+    let depth = u16::try_from(state.stack.len()).context("Call stack too deep to save")?;
+    out.extend_from_slice(&depth.to_le_bytes());
+    for &addr in &state.stack {
+        out.extend_from_slice(&address(addr)?.to_le_bytes());
+    }
+
+    out.push(state.delay_timer);
+    out.push(state.sound_timer);
+    out.extend_from_slice(&state.registers.values());
+    out.extend_from_slice(&state.rpl_flags);
+    out.extend_from_slice(&state.audio_pattern);
+    out.push(state.pitch);
+    out.push(
+        u8::from(state.halted)
+            | u8::from(state.awaiting_vblank) << 1
+            | u8::from(state.display.is_hires()) << 2,
+    );
+    out.push(state.display.selected_planes());
+    for plane in 0..NUM_PLANES {
+        out.extend_from_slice(&state.display.plane_bytes(plane));
+    }
+    out.push(state.rng.kind().id());
+    out.extend_from_slice(&state.rng.state().to_le_bytes());
+
+    let memory = state.memory.data();
+    out.extend_from_slice(&(memory.len() as u32).to_le_bytes());
+    out.extend_from_slice(memory);
+    Ok(out)
+}
+
+/// Restores a state written by [`save_state`].
+///
+/// Fails without modifying `state` if the data is not a save state, was
+/// written by a different format version, was made for a ROM other than the
+/// one hashing to `rom_hash`, or targets a different platform.
+pub fn load_state(state: &mut Chip8State, data: &[u8], rom_hash: &RomHash) -> anyhow::Result<()> {
+    let mut reader = Reader { data };
+    if reader.bytes(MAGIC.len())? != MAGIC {
+        bail!("Not a CHIP-8 save state");
+    }
+    let version = reader.u16()?;
+    if version != SAVE_STATE_VERSION {
+        bail!(
+            "Unsupported save state version {} (expected {})",
+            version,
+            SAVE_STATE_VERSION
+        );
+    }
+    let saved_hash = reader.bytes(rom_hash.len())?;
+    if saved_hash != rom_hash {
+        bail!(
+            "Save state was made for a different ROM (SHA-1 {}, this ROM is {})",
+            hex(saved_hash),
+            hex(rom_hash)
+        );
+    }
+
+    let platform = match reader.u8()? {
+        0 => Platform::Chip8,
+        1 => Platform::SuperChip,
+        2 => Platform::XoChip,
+        id => bail!("Invalid platform {} in save state", id),
+    };
+    if platform != state.settings.platform {
+        bail!(
+            "Save state was made for platform {:?}, but {:?} is running",
+            platform,
+            state.settings.platform
+        );
+    }
+    let quirks = quirks_from_bits(reader.u8()?);
+    let pc = usize::from(reader.u16()?);
+    let index = usize::from(reader.u16()?);
+    let depth = reader.u16()?;
+    let stack = (0..depth)
+        .map(|_| reader.u16().map(usize::from))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let delay_timer = reader.u8()?;
+    let sound_timer = reader.u8()?;
+    let registers = reader.array::<NUM_REGISTERS>()?;
+    let rpl_flags = reader.array()?;
+    let audio_pattern = reader.array()?;
+    let pitch = reader.u8()?;
+    let flags = reader.u8()?;
+    let selected_planes = reader.u8()?;
+    let planes = (0..NUM_PLANES)
+        .map(|_| reader.bytes(PLANE_BYTES))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let rng_kind = reader.u8()?;
+    let rng_kind = RngKind::from_id(rng_kind)
+        .ok_or_else(|| anyhow!("Invalid random number generator {} in save state", rng_kind))?;
+    let rng_state = u64::from_le_bytes(reader.array()?);
+    let memory_len = u32::from_le_bytes(reader.array()?) as usize;
+    if memory_len != state.memory.size() {
+        bail!(
+            "Save state has {} bytes of memory, expected {}",
+            memory_len,
+            state.memory.size()
+        );
+    }
+    let memory = reader.bytes(memory_len)?;
+    if !reader.data.is_empty() {
+        bail!("Unexpected data at the end of the save state");
+    }
+
+    state.settings.quirks = quirks;
+    state.pc = pc;
+    state.index = index;
+    state.stack = stack;
+    state.delay_timer = delay_timer;
+    state.sound_timer = sound_timer;
+    for (idx, value) in registers.into_iter().enumerate() {
+        state.registers.write(Register::from_index(idx)?, value);
+    }
+    state.rpl_flags = rpl_flags;
+    state.audio_pattern = audio_pattern;
+    state.pitch = pitch;
+    state.halted = flags & 0b001 != 0;
+    state.awaiting_vblank = flags & 0b010 != 0;
+    state.display.set_hires(flags & 0b100 != 0);
+    state.display.select_planes(selected_planes);
+    for (plane, bytes) in planes.into_iter().enumerate() {
+        state.display.set_plane_bytes(plane, bytes);
+    }
+    state.rng = rng_kind.create(rng_state);
+    state.memory.restore(memory);
+    Ok(())
+}
+
+/// Sequential reader over save state bytes.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    /// Takes the next `len` bytes.
+    fn bytes(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(anyhow!("Save state is truncated"));
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    /// Takes the next `N` bytes as an array.
+    fn array<const N: usize>(&mut self) -> anyhow::Result<[u8; N]> {
+        Ok(self.bytes(N)?.try_into()?)
+    }
+
+    fn u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> anyhow::Result<u16> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+}
+
+/// Converts an address to the 16 bits stored in a save state.
+fn address(addr: usize) -> anyhow::Result<u16> {
+    u16::try_from(addr).map_err(|_| anyhow!("Address {:#x} out of range", addr))
+}
+
+fn platform_id(platform: Platform) -> u8 {
+    match platform {
+        Platform::Chip8 => 0,
+        Platform::SuperChip => 1,
+        Platform::XoChip => 2,
+    }
+}
+
+/// Packs the quirk flags into one byte, in field order starting at bit 0.
+pub(crate) fn quirk_bits(quirks: &Quirks) -> u8 {
+    [
+        quirks.vf_reset,
+        quirks.memory_increment,
+        quirks.clipping,
+        quirks.shift_in_place,
+        quirks.jump_with_vx,
+        quirks.display_wait,
+    ]
+    .iter()
+    .enumerate()
+    .fold(0, |bits, (bit, &set)| bits | u8::from(set) << bit)
+}
+
+pub(crate) fn quirks_from_bits(bits: u8) -> Quirks {
+    let bit = |n: u8| bits & (1 << n) != 0;
+    Quirks {
+        vf_reset: bit(0),
+        memory_increment: bit(1),
+        clipping: bit(2),
+        shift_in_place: bit(3),
+        jump_with_vx: bit(4),
+        display_wait: bit(5),
+    }
+}
+
+/// Formats a hash as lowercase hexadecimal.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::machine::Machine;
+    use crate::state::Settings;
+
+    /// A machine running `rom` at 1000 instructions per frame.
+    fn machine(rom: &[u8]) -> Machine {
+        let mut settings = Settings::new(60, 60_000, "test.ch8".to_string());
+        settings.seed = Some(1);
+        let mut machine = Machine::new(settings);
+        machine.load_rom(rom).unwrap();
+        machine
+    }
+
+    #[test]
+    fn index_past_0xffff_round_trips() {
+        // v0 := 0xff, then i += v0 forever
+        let rom = [0x60, 0xFF, 0xF0, 0x1E, 0x12, 0x02];
+        let mut running = machine(&rom);
+        running.run_frame().unwrap();
+        let index = running.state().index;
+        assert!(index <= 0xFFFF, "index {:#x} is wider than 16 bits", index);
+        // One frame runs the load and then 500 additions
+        assert_eq!(index, (500 * 0xFF) & 0xFFFF);
+
+        let saved = running.save_state().unwrap();
+        let mut restored = machine(&rom);
+        restored.load_state(&saved).unwrap();
+        assert_eq!(restored.state().index, index);
+        assert_eq!(restored.state().pc, running.state().pc);
+        assert_eq!(restored.save_state().unwrap(), saved);
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let rom = [0x12, 0x00];
+        let mut saved = machine(&rom).save_state().unwrap();
+        saved[4..6].copy_from_slice(&(super::SAVE_STATE_VERSION + 1).to_le_bytes());
+        let error = machine(&rom).load_state(&saved).unwrap_err();
+        assert!(
+            error.to_string().contains("Unsupported save state version"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn states_of_other_roms_are_rejected() {
+        let saved = machine(&[0x12, 0x00]).save_state().unwrap();
+        let mut other = machine(&[0x12, 0x02]);
+        let before = other.save_state().unwrap();
+        let error = other.load_state(&saved).unwrap_err();
+        assert!(error.to_string().contains("different ROM"), "{}", error);
+        assert_eq!(other.save_state().unwrap(), before);
+    }
+
+    #[test]
+    fn truncated_states_are_rejected() {
+        let rom = [0x12, 0x00];
+        let saved = machine(&rom).save_state().unwrap();
+        let error = machine(&rom)
+            .load_state(&saved[..saved.len() - 1])
+            .unwrap_err();
+        assert!(error.to_string().contains("truncated"), "{}", error);
+    }
+}
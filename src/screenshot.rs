@@ -0,0 +1,172 @@
+//! Screenshots
+//!
+//! Saves the CHIP-8 display as an image at its native resolution: 64×32, or
+//! 128×64 in the SUPER-CHIP high resolution mode. Two formats are supported:
+//!
+//! - PNG, an indexed colour image in the theme's palette with every pixel
+//!   scaled by an integer factor, keeping the XO-CHIP plane colours.
+//! - Plain PBM, an ASCII black and white bitmap with lit pixels, in any plane,
+//!   as `1`. Easy to diff and to check into test fixtures.
+//!
+//! Screenshots are named after the ROM and the time they are taken, next to
+//! the ROM file, see [`screenshot_path`].
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use ratatui::style::Color;
+
+use crate::state::FrameBuffer;
+use crate::theme::palette_rgb;
+
+/// Default scale of PNG screenshots.
+pub const DEFAULT_SCREENSHOT_SCALE: usize = 8;
+
+/// Pixels per line of plain PBM files.
+const PBM_LINE_PIXELS: usize = 64;
+
+/// Image format of screenshots.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ScreenshotFormat {
+    /// PNG in the palette colours.
+    #[default]
+    Png,
+
+    /// Plain (ASCII) PBM, black and white.
+    Pbm,
+}
+
+impl ScreenshotFormat {
+    /// File name extension of the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ScreenshotFormat::Png => "png",
+            ScreenshotFormat::Pbm => "pbm",
+        }
+    }
+}
+
+/// Path of a screenshot of `rom` taken now: `pong.ch8` gives
+/// `pong-20250102-134501-250.png` next to it, with the UTC date and time down
+/// to milliseconds.
+pub fn screenshot_path(rom: &Path, format: ScreenshotFormat) -> PathBuf {
+    timestamped_path(rom, format.extension())
+}
+
+/// Path next to `rom` named after it and the current time, with `extension`.
+pub(crate) fn timestamped_path(rom: &Path, extension: &str) -> PathBuf {
+    let stem = rom
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "chip8".to_string());
+    rom.with_file_name(format!(
+        "{}-{}.{}",
+        stem,
+        timestamp(SystemTime::now()),
+        extension
+    ))
+}
+
+/// Saves `display` to `path` in `format`. PNG images use `palette`, indexed by
+/// plane combination, and are scaled by `scale`; PBM images are not scaled.
+pub fn save_screenshot(
+    display: &FrameBuffer,
+    path: &Path,
+    format: ScreenshotFormat,
+    palette: &[Color; 4],
+    scale: usize,
+) -> anyhow::Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Cannot create screenshot {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+    match format {
+        ScreenshotFormat::Png => write_png(&mut out, display, palette, scale)?,
+        ScreenshotFormat::Pbm => write_pbm(&mut out, display)?,
+    }
+    out.flush()
+        .with_context(|| format!("Cannot write screenshot {}", path.display()))
+}
+
+/// Writes `display` as an 8-bit indexed PNG with each pixel scaled to
+/// `scale`×`scale`. The terminal's default colour is written as black for the
+/// background and white for the planes.
+pub fn write_png(
+    out: impl Write,
+    display: &FrameBuffer,
+    palette: &[Color; 4],
+    scale: usize,
+) -> anyhow::Result<()> {
+    let scale = scale.max(1);
+    let (width, height) = (display.width() * scale, display.height() * scale);
+    let mut encoder = png::Encoder::new(out, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    let plte: Vec<u8> = palette_rgb(palette)
+        .iter()
+        .flat_map(|&(r, g, b)| [r, g, b])
+        .collect();
+    encoder.set_palette(plte);
+
+    let data: Vec<u8> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| display.color(x / scale, y / scale)))
+        .collect();
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Writes `display` as a plain PBM, one line per display row, or two in high
+/// resolution mode to keep lines within the format's 70 characters.
+pub fn write_pbm(mut out: impl Write, display: &FrameBuffer) -> std::io::Result<()> {
+    writeln!(out, "P1")?;
+    writeln!(out, "{} {}", display.width(), display.height())?;
+    for y in 0..display.height() {
+        let row: Vec<u8> = (0..display.width())
+            .map(|x| if display.get(x, y) { b'1' } else { b'0' })
+            .collect();
+        for line in row.chunks(PBM_LINE_PIXELS) {
+            out.write_all(line)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Formats `time` as `YYYYMMDD-HHMMSS-mmm` in UTC.
+fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = (secs / 86400, secs % 86400);
+
+    // Civil date from days since 1970-01-01, after Howard Hinnant's
+    // days_from_civil inverse, with years starting in March
+    let days = days as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}-{:03}",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
@@ -5,30 +5,40 @@
 //! virtual machine's architecture including memory management, register handling,
 //! input processing, and display management.
 
+use std::cell::RefCell;
 use std::collections::HashSet;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
 use anyhow::anyhow;
-use bitvec::{array::BitArray, BitArr};
-use rdev::{listen, EventType, Key as RdevKey};
+use bitvec::{BitArr, array::BitArray};
+
+use crate::rng::{RandomGenerator, RngKind};
 
 /// Timer value type for delay and sound timers.
 /// Timers in CHIP-8 count down at 60 Hz from their initial value to zero.
 pub type Timer = u8;
 
 /// Memory address type for the CHIP-8 system.
-/// Addresses range from 0x000 to 0xFFF (4096 bytes total).
+/// Addresses range from 0x000 to 0xFFF (4096 bytes total), or up to 0xFFFF on XO-CHIP.
 pub type Address = usize;
 
 /// Call stack for storing return addresses during subroutine calls.
 /// CHIP-8 supports nested subroutines with a stack-based return mechanism.
 pub type CallStack = Vec<Address>;
 
+/// Backing storage for one display plane, sized for the largest resolution.
+/// Pixels are stored row-major, one bit per pixel (true = on, false = off).
+type PixelPlane = BitArr!(for HIRES_DISPLAY_WIDTH * HIRES_DISPLAY_HEIGHT);
+
 /// Total memory size of the CHIP-8 system in bytes.
 /// The original CHIP-8 system had 4KB of RAM.
 pub const MEM_SIZE: usize = 4096;
 
+/// Total memory size of the XO-CHIP system in bytes.
+/// XO-CHIP extends the address space to 64KB.
+pub const XO_MEM_SIZE: usize = 0x10000;
+
 /// Starting address where the built-in font set is stored in memory.
 /// Font data occupies addresses 0x50-0x9F (80 bytes for 16 characters).
 pub const FONT_ADDR: Address = 0x50;
@@ -37,6 +47,14 @@ pub const FONT_ADDR: Address = 0x50;
 /// Each CHIP-8 font character is 4 pixels wide and 5 pixels tall.
 pub const FONT_HEIGHT: usize = 5;
 
+/// Starting address where the SUPER-CHIP large font set is stored in memory.
+/// Large font data occupies addresses 0xA0-0x13F (160 bytes for 16 characters).
+pub const BIG_FONT_ADDR: Address = 0xA0;
+
+/// Height of each large font character in pixels.
+/// Each SUPER-CHIP large font character is 8 pixels wide and 10 pixels tall.
+pub const BIG_FONT_HEIGHT: usize = 10;
+
 /// Default starting address for program execution.
 pub const PC_START_ADDR: Address = 0x200;
 
@@ -51,6 +69,25 @@ pub const DISPLAY_WIDTH: usize = 64;
 /// Height of the CHIP-8 display in pixels.
 pub const DISPLAY_HEIGHT: usize = 32;
 
+/// Width of the SUPER-CHIP high-resolution display in pixels.
+pub const HIRES_DISPLAY_WIDTH: usize = 128;
+
+/// Height of the SUPER-CHIP high-resolution display in pixels.
+pub const HIRES_DISPLAY_HEIGHT: usize = 64;
+
+/// Number of RPL user flag registers available to `FX75`/`FX85` on SUPER-CHIP.
+/// The HP-48 calculators running SUPER-CHIP exposed 8 of them; XO-CHIP allows 16.
+pub const NUM_RPL_FLAGS: usize = 8;
+
+/// Number of display bitplanes. XO-CHIP draws to two planes, giving four colours.
+pub const NUM_PLANES: usize = 2;
+
+/// Size of the XO-CHIP audio pattern buffer in bytes (128 1-bit samples).
+pub const AUDIO_PATTERN_SIZE: usize = 16;
+
+/// Default XO-CHIP audio pitch register value, which plays the pattern at 4000 Hz.
+pub const DEFAULT_PITCH: u8 = 64;
+
 /// Default frame rate for the emulator in frames per second.
 /// This controls how often the display is refreshed and timers are decremented.
 pub const DEFAULT_FRAME_RATE: u64 = 60;
@@ -61,7 +98,7 @@ pub const DEFAULT_INSTRUCTIONS_PER_SECOND: u64 = 700;
 
 /// Memory subsystem for the CHIP-8 emulator.
 ///
-/// Manages the 4KB memory space of the CHIP-8 system, including:
+/// Manages the 4KB memory space of the CHIP-8 system (64KB on XO-CHIP), including:
 /// - Built-in font data loaded at startup
 /// - ROM/program data loaded at runtime
 /// - General-purpose memory for program use
@@ -69,18 +106,45 @@ pub const DEFAULT_INSTRUCTIONS_PER_SECOND: u64 = 700;
 /// Memory layout:
 /// - 0x000-0x1FF: Reserved for interpreter (not used in this implementation)
 /// - 0x050-0x09F: Built-in font set (16 characters, 5 bytes each)
-/// - 0x200-0xFFF: Program ROM and RAM
+/// - 0x0A0-0x13F: Built-in large font set (16 characters, 10 bytes each)
+/// - 0x200-0xFFF: Program ROM and RAM (0x200-0xFFFF on XO-CHIP)
+///
+/// When tracing is enabled, every byte read or written through [`Memory::read`],
+/// [`Memory::write`] and [`Memory::read_sprite`] is recorded so that watchpoints
+/// can inspect what an instruction touched. Instruction fetches through
+/// [`Memory::read_word`] are never recorded.
 pub struct Memory {
-    data: [u8; MEM_SIZE],
+    data: Vec<u8>,
+    tracing: bool,
+    accesses: RefCell<Vec<MemoryAccess>>,
+}
+
+/// Direction of a recorded memory access.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// A single byte access recorded while memory tracing is enabled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MemoryAccess {
+    pub kind: AccessKind,
+    pub addr: Address,
+    /// Value before the access. Equal to `new` for reads.
+    pub old: u8,
+    /// Value after the access.
+    pub new: u8,
 }
 
 impl Memory {
-    /// Creates a new Memory instance with built-in font data pre-loaded.
+    /// Creates a new Memory instance of `size` bytes with built-in font data pre-loaded.
     ///
     /// The font data contains hexadecimal digit sprites (0-F) that are
     /// commonly used by CHIP-8 programs for displaying numbers and letters.
-    /// Each character is 4 pixels wide and 5 pixels tall.
-    pub fn new() -> Self {
+    /// Each character is 4 pixels wide and 5 pixels tall. A second, large
+    /// 8×10 font set is also loaded for the SUPER-CHIP `FX30` instruction.
+    pub fn new(size: usize) -> Self {
         let font_data = [
             0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
             0x20, 0x60, 0x20, 0x20, 0x70, // 1
@@ -99,38 +163,107 @@ impl Memory {
             0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
             0xF0, 0x80, 0xF0, 0x80, 0x80, // F
         ];
+        let big_font_data = [
+            0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+            0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+            0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+            0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+            0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+            0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+            0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+            0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+            0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+            0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+            0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+            0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+            0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+            0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+            0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+            0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+        ];
         let data = {
-            let mut data = [0; MEM_SIZE];
+            let mut data = vec![0; size];
             data[FONT_ADDR..FONT_ADDR + font_data.len()].copy_from_slice(&font_data);
+            data[BIG_FONT_ADDR..BIG_FONT_ADDR + big_font_data.len()]
+                .copy_from_slice(&big_font_data);
             data
         };
 
-        Memory { data }
+        Memory {
+            data,
+            tracing: false,
+            accesses: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Total size of the address space in bytes.
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns the raw memory contents without recording an access.
+    pub fn data(&self) -> &[u8] {
+        &self.data
     }
 
     /// Reads a single byte from memory at the specified address.
     pub fn read(&self, addr: Address) -> anyhow::Result<u8> {
-        if addr >= MEM_SIZE {
+        if addr >= self.size() {
+            return Err(anyhow!("Memory read out of bounds: {}", addr));
+        }
+        let value = self.data[addr];
+        self.record(AccessKind::Read, addr, value, value);
+        Ok(value)
+    }
+
+    /// Reads a big-endian 16-bit instruction word without recording an access.
+    pub fn read_word(&self, addr: Address) -> anyhow::Result<u16> {
+        if addr + 1 >= self.size() {
             return Err(anyhow!("Memory read out of bounds: {}", addr));
         }
-        Ok(self.data[addr])
+        Ok(u16::from_be_bytes([self.data[addr], self.data[addr + 1]]))
     }
 
     /// Writes a single byte to memory at the specified address.
     pub fn write(&mut self, addr: Address, value: u8) -> anyhow::Result<()> {
-        if addr >= MEM_SIZE {
+        if addr >= self.size() {
             return Err(anyhow!("Memory write out of bounds: {}", addr));
         }
+        self.record(AccessKind::Write, addr, self.data[addr], value);
         self.data[addr] = value;
         Ok(())
     }
 
+    /// Enables or disables recording of memory accesses, discarding any
+    /// accesses recorded so far.
+    pub fn set_tracing(&mut self, enabled: bool) {
+        self.tracing = enabled;
+        self.accesses.get_mut().clear();
+    }
+
+    /// Returns and clears the accesses recorded since the last call.
+    pub fn take_accesses(&self) -> Vec<MemoryAccess> {
+        self.accesses.take()
+    }
+
+    /// Records an access if tracing is enabled.
+    fn record(&self, kind: AccessKind, addr: Address, old: u8, new: u8) {
+        if self.tracing {
+            self.accesses.borrow_mut().push(MemoryAccess {
+                kind,
+                addr,
+                old,
+                new,
+            });
+        }
+    }
+
     /// Loads a ROM file into memory starting at the program counter start address.
     ///
     /// ROM data is loaded starting at address 0x200, which is the traditional
     /// program start location for CHIP-8 systems.
     pub fn load_rom(&mut self, rom: &[u8]) -> anyhow::Result<()> {
-        if rom.len() > MEM_SIZE - PC_START_ADDR {
+        if rom.len() > self.size() - PC_START_ADDR {
             return Err(anyhow!("ROM too large to fit in memory"));
         }
         self.data[PC_START_ADDR..PC_START_ADDR + rom.len()].copy_from_slice(rom);
@@ -141,10 +274,12 @@ impl Memory {
     ///
     /// Sprites in CHIP-8 are variable-height (1-15 rows) and fixed-width (8 pixels).
     /// Each row is represented by a single byte where each bit corresponds to a pixel.
+    /// SUPER-CHIP 16×16 sprites use two bytes per row and are read as 32 bytes, and
+    /// XO-CHIP reads one sprite per selected bitplane back to back.
     ///
     /// # Arguments
     /// * `index` - Starting memory address of the sprite data
-    /// * `rows` - Number of rows (bytes) to read for the sprite (1-15)
+    /// * `rows` - Number of bytes to read for the sprite
     ///
     /// # Returns
     /// * `Ok(&[u8])` - Slice containing the sprite data
@@ -152,11 +287,26 @@ impl Memory {
     pub fn read_sprite(&self, index: Address, rows: u8) -> anyhow::Result<&[u8]> {
         let sprite_slice = index..index + rows as usize;
 
-        if sprite_slice.end > MEM_SIZE {
+        if sprite_slice.end > self.size() {
             return Err(anyhow!("Sprite data out of bounds"));
         }
+        for addr in sprite_slice.clone() {
+            self.record(AccessKind::Read, addr, self.data[addr], self.data[addr]);
+        }
         Ok(&self.data[sprite_slice])
     }
+
+    /// Replaces the whole memory contents, e.g. when restoring a save state.
+    pub(crate) fn restore(&mut self, data: &[u8]) {
+        self.data = data.to_vec();
+        self.accesses.get_mut().clear();
+    }
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self::new(MEM_SIZE)
+    }
 }
 
 /// Enumeration of all 16 general-purpose registers in the CHIP-8 system.
@@ -164,7 +314,7 @@ impl Memory {
 /// CHIP-8 has 16 8-bit registers named V0 through VF. Register VF is commonly
 /// used as a flag register by arithmetic and logical operations to indicate
 /// carry, borrow, or collision conditions.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Register {
     V0,
     V1,
@@ -234,10 +384,21 @@ impl RegisterBank {
     pub fn write(&mut self, reg: Register, value: u8) {
         self.registers[reg as usize] = value;
     }
+
+    /// Returns a copy of all register values, V0 first.
+    pub fn values(&self) -> [u8; NUM_REGISTERS] {
+        self.registers
+    }
+}
+
+impl Default for RegisterBank {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// Enumeration of all 16 keys in the CHIP-8 hexadecimal keypad.
-#[derive(PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Key {
     Key0,
     Key1,
@@ -279,140 +440,29 @@ impl Key {
             _ => Err(anyhow!("Invalid key index: {}", index)),
         }
     }
-
-    /// Converts an rdev keyboard key to the corresponding CHIP-8 key.
-    ///
-    /// This function implements the keyboard mapping from modern QWERTY layout
-    /// to the CHIP-8 hexadecimal keypad. The mapping follows a common convention
-    /// used by many CHIP-8 emulators for ergonomic key placement.
-    ///
-    /// # Keyboard Mapping
-    /// | Keyboard | CHIP-8 |
-    /// |----------|--------|
-    /// | 1        | 1      |
-    /// | 2        | 2      |
-    /// | 3        | 3      |
-    /// | 4        | C      |
-    /// | Q        | 4      |
-    /// | W        | 5      |
-    /// | E        | 6      |
-    /// | R        | D      |
-    /// | A        | 7      |
-    /// | S        | 8      |
-    /// | D        | 9      |
-    /// | F        | E      |
-    /// | Z        | A      |
-    /// | X        | 0      |
-    /// | C        | B      |
-    /// | V        | F      |
-    pub fn from_rdev(key: rdev::Key) -> Option<Key> {
-        match key {
-            RdevKey::Num1 => Some(Key::Key1),
-            RdevKey::Num2 => Some(Key::Key2),
-            RdevKey::Num3 => Some(Key::Key3),
-            RdevKey::Num4 => Some(Key::KeyC),
-            RdevKey::KeyQ => Some(Key::Key4),
-            RdevKey::KeyW => Some(Key::Key5),
-            RdevKey::KeyE => Some(Key::Key6),
-            RdevKey::KeyR => Some(Key::KeyD),
-            RdevKey::KeyA => Some(Key::Key7),
-            RdevKey::KeyS => Some(Key::Key8),
-            RdevKey::KeyD => Some(Key::Key9),
-            RdevKey::KeyF => Some(Key::KeyE),
-            RdevKey::KeyZ => Some(Key::KeyA),
-            RdevKey::KeyX => Some(Key::Key0),
-            RdevKey::KeyC => Some(Key::KeyB),
-            RdevKey::KeyV => Some(Key::KeyF),
-            _ => None,
-        }
-    }
 }
 
-/// Input handling system for the CHIP-8 hexadecimal keypad.
-///
-/// The `Keypad` struct manages key input state for the 16-key CHIP-8 keypad using
-/// a background thread that listens for global keyboard events. This approach allows
-/// the emulator to capture input even when not in focus and provides real-time
-/// key state tracking.
-///
-/// # Architecture
-///
-/// The keypad uses a multi-threaded design:
-/// - A background thread continuously listens for keyboard events using `rdev`
-/// - Key states are stored in thread-safe collections (`Arc<Mutex<_>>`)
-/// - The main emulator thread can query key states without blocking
-///
-/// # Key Mapping
+/// Input state for the CHIP-8 hexadecimal keypad.
 ///
-/// Physical keyboard keys are mapped to CHIP-8 keys using the standard layout:
-/// ```text
-/// Keyboard    CHIP-8
-/// 1 2 3 4  →  1 2 3 C
-/// Q W E R  →  4 5 6 D  
-/// A S D F  →  7 8 9 E
-/// Z X C V  →  A 0 B F
-/// ```
-///
-/// The Escape key is handled separately for emulator control.
+/// The `Keypad` struct tracks which of the 16 CHIP-8 keys are currently held
+/// down. It has no knowledge of where key events come from: a frontend, a test
+/// harness or a bot injects input through `press_key` and `release_key`.
 ///
 /// # Thread Safety
 ///
-/// All public methods are thread-safe and can be called from multiple threads
-/// without external synchronization. Internal state is protected by mutexes.
+/// Key state is stored behind an `Arc<Mutex<_>>`, and cloning a `Keypad` yields a
+/// handle to the same underlying state. This lets an input source running on a
+/// background thread update the keys that the emulation thread reads.
+#[derive(Clone, Default)]
 pub struct Keypad {
     /// Thread-safe storage for currently pressed CHIP-8 keys.
-    /// Updated by the background listener thread and read by the main emulator thread.
     pressed_keys: Arc<Mutex<HashSet<Key>>>,
-
-    /// Thread-safe flag indicating if the Escape key is currently pressed.
-    /// Used for emulator control (typically to exit the program).
-    escape_pressed: Arc<Mutex<bool>>,
 }
 
 impl Keypad {
-    /// Creates a new `Keypad` instance and starts the background key listener.
-    ///
-    /// This constructor immediately spawns a background thread that will listen
-    /// for global keyboard events throughout the lifetime of the `Keypad` instance.
-    /// The thread will continue running until the program terminates.
+    /// Creates a new `Keypad` instance with no keys pressed.
     pub fn new() -> Self {
-        let pressed_keys = Arc::new(Mutex::new(HashSet::new()));
-        let escape_pressed = Arc::new(Mutex::new(false));
-        let pressed_keys_clone = pressed_keys.clone();
-        let escape_pressed_clone = escape_pressed.clone();
-
-        // Spawn a background thread to listen for key events
-        std::thread::spawn(move || {
-            if let Err(error) = listen(move |event| {
-                let mut keys = pressed_keys_clone.lock().unwrap();
-                let mut escape = escape_pressed_clone.lock().unwrap();
-
-                match event.event_type {
-                    EventType::KeyPress(key) => {
-                        if key == RdevKey::Escape {
-                            *escape = true;
-                        } else if let Some(chip8_key) = Key::from_rdev(key) {
-                            keys.insert(chip8_key);
-                        }
-                    }
-                    EventType::KeyRelease(key) => {
-                        if key == RdevKey::Escape {
-                            *escape = false;
-                        } else if let Some(chip8_key) = Key::from_rdev(key) {
-                            keys.remove(&chip8_key);
-                        }
-                    }
-                    _ => {}
-                }
-            }) {
-                eprintln!("Error listening for key events: {:?}", error);
-            }
-        });
-
-        Keypad {
-            pressed_keys,
-            escape_pressed,
-        }
+        Keypad::default()
     }
 
     /// Checks if a specific CHIP-8 key is currently pressed (non-blocking).
@@ -420,20 +470,417 @@ impl Keypad {
         self.pressed_keys.lock().unwrap().contains(&key)
     }
 
-    /// Manually releases a specific CHIP-8 key from the pressed state.
+    /// Marks a specific CHIP-8 key as pressed.
+    pub fn press_key(&self, key: Key) {
+        self.pressed_keys.lock().unwrap().insert(key);
+    }
+
+    /// Releases a specific CHIP-8 key from the pressed state.
     ///
-    /// This method allows the emulator to programmatically release a key,
-    /// which is useful for implementing certain CHIP-8 instructions that
-    /// expect keys to be "consumed" after being read.
+    /// Besides being called by input sources, this allows the emulator to
+    /// programmatically release a key, which is useful for implementing certain
+    /// CHIP-8 instructions that expect keys to be "consumed" after being read.
     pub fn release_key(&self, key: Key) {
         let mut keys = self.pressed_keys.lock().unwrap();
 
         keys.remove(&key);
     }
 
-    /// Checks if the Escape key is currently pressed.
-    pub fn is_escape_pressed(&self) -> bool {
-        *self.escape_pressed.lock().unwrap()
+    /// Releases every CHIP-8 key.
+    pub fn release_all(&self) {
+        self.pressed_keys.lock().unwrap().clear();
+    }
+
+    /// Returns the pressed keys as a bitmask, with bit N set while key N is
+    /// held.
+    pub fn pressed_mask(&self) -> u16 {
+        self.pressed_keys
+            .lock()
+            .unwrap()
+            .iter()
+            .fold(0, |mask, &key| mask | 1 << key as u16)
+    }
+
+    /// Presses exactly the keys whose bits are set in `mask`, releasing all
+    /// others.
+    pub fn set_pressed_mask(&self, mask: u16) {
+        let mut keys = self.pressed_keys.lock().unwrap();
+        keys.clear();
+        for index in 0..16 {
+            if mask & (1 << index) != 0 {
+                keys.insert(Key::from_index(index).unwrap());
+            }
+        }
+    }
+}
+
+/// CHIP-8 platform variant that determines the supported instruction set.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "frontend", derive(clap::ValueEnum))]
+pub enum Platform {
+    /// The original CHIP-8 interpreter for the COSMAC VIP.
+    #[default]
+    #[cfg_attr(feature = "frontend", value(name = "chip8"))]
+    Chip8,
+
+    /// SUPER-CHIP 1.1 for the HP-48 calculators, adding a 128×64 high-resolution
+    /// mode, scrolling, 16×16 sprites, a large font and RPL flag registers.
+    #[cfg_attr(feature = "frontend", value(name = "schip"))]
+    SuperChip,
+
+    /// XO-CHIP, the Octo extension of SUPER-CHIP with 64KB of memory, two
+    /// display bitplanes and programmable audio patterns.
+    #[cfg_attr(feature = "frontend", value(name = "xochip"))]
+    XoChip,
+}
+
+impl Platform {
+    /// Returns true if the platform supports the SUPER-CHIP instruction extensions.
+    pub fn has_schip_extensions(self) -> bool {
+        matches!(self, Platform::SuperChip | Platform::XoChip)
+    }
+
+    /// Returns true if the platform supports the XO-CHIP instruction extensions.
+    pub fn has_xochip_extensions(self) -> bool {
+        matches!(self, Platform::XoChip)
+    }
+
+    /// Size of the platform's address space in bytes.
+    pub fn memory_size(self) -> usize {
+        if self.has_xochip_extensions() {
+            XO_MEM_SIZE
+        } else {
+            MEM_SIZE
+        }
+    }
+
+    /// Number of RPL user flags that `FX75`/`FX85` can access.
+    pub fn rpl_flag_count(self) -> usize {
+        if self.has_xochip_extensions() {
+            NUM_REGISTERS
+        } else {
+            NUM_RPL_FLAGS
+        }
+    }
+}
+
+/// Framebuffer with switchable low and high resolution modes and two bitplanes.
+///
+/// In low resolution the display is 64×32 pixels, as on the original CHIP-8.
+/// SUPER-CHIP programs can switch to a 128×64 high resolution mode. Switching
+/// modes clears the screen. The backing storage is always large enough for the
+/// high resolution mode so that switching never reallocates.
+///
+/// XO-CHIP adds a second bitplane. Drawing, clearing and scrolling only affect
+/// the planes selected with `FN01`; plane 0 alone is selected by default, which
+/// makes the framebuffer behave as a monochrome display for other platforms.
+/// The colour of a pixel is the combination of its bits in both planes.
+#[derive(Clone)]
+pub struct FrameBuffer {
+    /// Pixel data per plane, indexed row-major using the width of the current mode.
+    planes: [PixelPlane; NUM_PLANES],
+
+    /// Whether the 128×64 high resolution mode is active.
+    hires: bool,
+
+    /// Bitmask of the planes affected by drawing, clearing and scrolling.
+    selected_planes: u8,
+}
+
+impl FrameBuffer {
+    /// Creates a new, blank framebuffer in low resolution mode with plane 0 selected.
+    pub fn new() -> Self {
+        FrameBuffer {
+            planes: [BitArray::ZERO; NUM_PLANES],
+            hires: false,
+            selected_planes: 0b01,
+        }
+    }
+
+    /// Width of the display in the current resolution mode.
+    pub fn width(&self) -> usize {
+        if self.hires {
+            HIRES_DISPLAY_WIDTH
+        } else {
+            DISPLAY_WIDTH
+        }
+    }
+
+    /// Height of the display in the current resolution mode.
+    pub fn height(&self) -> usize {
+        if self.hires {
+            HIRES_DISPLAY_HEIGHT
+        } else {
+            DISPLAY_HEIGHT
+        }
+    }
+
+    /// Returns true if the high resolution mode is active.
+    pub fn is_hires(&self) -> bool {
+        self.hires
+    }
+
+    /// Switches between low and high resolution mode, clearing every plane.
+    pub fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        for plane in &mut self.planes {
+            plane.fill(false);
+        }
+    }
+
+    /// Bitmask of the currently selected planes (bit 0 = plane 0, bit 1 = plane 1).
+    pub fn selected_planes(&self) -> u8 {
+        self.selected_planes
+    }
+
+    /// Selects the planes affected by subsequent drawing, clearing and scrolling.
+    pub fn select_planes(&mut self, mask: u8) {
+        self.selected_planes = mask & 0b11;
+    }
+
+    /// Iterates over the indices of the currently selected planes in order.
+    pub fn selected_plane_indices(&self) -> impl Iterator<Item = usize> + use<> {
+        let mask = self.selected_planes;
+        (0..NUM_PLANES).filter(move |plane| mask & (1 << plane) != 0)
+    }
+
+    /// Returns whether the pixel at `(x, y)` is lit in any plane.
+    ///
+    /// Coordinates outside the current resolution are reported as unlit.
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.color(x, y) != 0
+    }
+
+    /// Returns the colour index (0-3) of the pixel at `(x, y)`.
+    ///
+    /// Bit 0 of the result comes from plane 0 and bit 1 from plane 1.
+    pub fn color(&self, x: usize, y: usize) -> u8 {
+        (0..NUM_PLANES).fold(0, |color, plane| {
+            color | (u8::from(self.get_plane(plane, x, y)) << plane)
+        })
+    }
+
+    /// Returns whether the pixel at `(x, y)` is lit in the given plane.
+    pub fn get_plane(&self, plane: usize, x: usize, y: usize) -> bool {
+        if x >= self.width() || y >= self.height() {
+            return false;
+        }
+        self.planes[plane][y * self.width() + x]
+    }
+
+    /// Sets the pixel at `(x, y)` in the given plane. Coordinates outside the
+    /// display are ignored.
+    pub fn set_plane(&mut self, plane: usize, x: usize, y: usize, value: bool) {
+        if x >= self.width() || y >= self.height() {
+            return;
+        }
+        let width = self.width();
+        self.planes[plane].set(y * width + x, value);
+    }
+
+    /// Turns off every pixel in the selected planes.
+    pub fn clear(&mut self) {
+        for plane in self.selected_plane_indices() {
+            self.planes[plane].fill(false);
+        }
+    }
+
+    /// Packs the backing storage of a plane into bytes, most significant bit
+    /// first, independently of the current resolution.
+    pub(crate) fn plane_bytes(&self, plane: usize) -> Vec<u8> {
+        self.planes[plane]
+            .chunks(8)
+            .map(|bits| {
+                bits.iter()
+                    .fold(0, |byte, bit| (byte << 1) | u8::from(*bit))
+            })
+            .collect()
+    }
+
+    /// Restores the backing storage of a plane packed by [`FrameBuffer::plane_bytes`].
+    pub(crate) fn set_plane_bytes(&mut self, plane: usize, bytes: &[u8]) {
+        for (idx, mut bit) in self.planes[plane].iter_mut().enumerate() {
+            *bit = bytes[idx / 8] >> (7 - idx % 8) & 1 == 1;
+        }
+    }
+
+    /// Scrolls the selected planes up by `rows` pixels, filling the bottom with blank rows.
+    pub fn scroll_up(&mut self, rows: usize) {
+        for plane in self.selected_plane_indices() {
+            for y in 0..self.height() {
+                for x in 0..self.width() {
+                    let value = self.get_plane(plane, x, y + rows);
+                    self.set_plane(plane, x, y, value);
+                }
+            }
+        }
+    }
+
+    /// Scrolls the selected planes down by `rows` pixels, filling the top with blank rows.
+    pub fn scroll_down(&mut self, rows: usize) {
+        for plane in self.selected_plane_indices() {
+            for y in (0..self.height()).rev() {
+                for x in 0..self.width() {
+                    let value = y >= rows && self.get_plane(plane, x, y - rows);
+                    self.set_plane(plane, x, y, value);
+                }
+            }
+        }
+    }
+
+    /// Scrolls the selected planes right by `cols` pixels, filling the left with
+    /// blank columns.
+    pub fn scroll_right(&mut self, cols: usize) {
+        for plane in self.selected_plane_indices() {
+            for y in 0..self.height() {
+                for x in (0..self.width()).rev() {
+                    let value = x >= cols && self.get_plane(plane, x - cols, y);
+                    self.set_plane(plane, x, y, value);
+                }
+            }
+        }
+    }
+
+    /// Scrolls the selected planes left by `cols` pixels, filling the right with
+    /// blank columns.
+    pub fn scroll_left(&mut self, cols: usize) {
+        for plane in self.selected_plane_indices() {
+            for y in 0..self.height() {
+                for x in 0..self.width() {
+                    let value = self.get_plane(plane, x + cols, y);
+                    self.set_plane(plane, x, y, value);
+                }
+            }
+        }
+    }
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Named sets of quirks matching well-known CHIP-8 interpreters.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "frontend", derive(clap::ValueEnum))]
+pub enum QuirksPreset {
+    /// The original CHIP-8 interpreter on the COSMAC VIP.
+    Vip,
+
+    /// CHIP-48 for the HP-48 calculators.
+    Chip48,
+
+    /// SUPER-CHIP 1.1 as it behaved on the HP-48.
+    SchipLegacy,
+
+    /// SUPER-CHIP as implemented by modern interpreters such as Octo.
+    SchipModern,
+
+    /// XO-CHIP as implemented by Octo.
+    #[cfg_attr(feature = "frontend", value(name = "xochip"))]
+    XoChip,
+}
+
+impl QuirksPreset {
+    /// Returns the preset matching the interpreter a platform was designed for.
+    pub fn for_platform(platform: Platform) -> Self {
+        match platform {
+            Platform::Chip8 => QuirksPreset::Vip,
+            Platform::SuperChip => QuirksPreset::SchipModern,
+            Platform::XoChip => QuirksPreset::XoChip,
+        }
+    }
+}
+
+/// Behavioural differences between CHIP-8 interpreters.
+///
+/// Several instructions behaved differently across the interpreters that ROMs
+/// were written for. Each flag selects one of the two behaviours; the names
+/// follow the quirks reported by Timendus' quirks test ROM.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Quirks {
+    /// `8XY1`, `8XY2` and `8XY3` reset VF to 0 (COSMAC VIP).
+    pub vf_reset: bool,
+
+    /// `FX55` and `FX65` leave the index register pointing past the last
+    /// register stored or loaded. When false, the index register is unchanged.
+    pub memory_increment: bool,
+
+    /// Sprites are clipped at the screen edges. When false, pixels that fall
+    /// off one edge wrap around to the opposite edge.
+    pub clipping: bool,
+
+    /// `8XY6` and `8XYE` shift Vx in place and ignore Vy (CHIP-48 and
+    /// SUPER-CHIP). When false, Vy is shifted and the result stored in Vx.
+    pub shift_in_place: bool,
+
+    /// `BNNN` behaves as `BXNN` and jumps to XNN plus Vx (CHIP-48 and
+    /// SUPER-CHIP). When false, it jumps to NNN plus V0.
+    pub jump_with_vx: bool,
+
+    /// `DXYN` waits for the vertical blank interrupt in low resolution mode, so
+    /// at most one sprite is drawn per frame (COSMAC VIP and SUPER-CHIP 1.1).
+    /// When false, sprites are drawn immediately.
+    pub display_wait: bool,
+}
+
+impl Quirks {
+    /// Returns the quirks of a named interpreter preset.
+    pub fn preset(preset: QuirksPreset) -> Self {
+        match preset {
+            QuirksPreset::Vip => Quirks {
+                vf_reset: true,
+                memory_increment: true,
+                clipping: true,
+                shift_in_place: false,
+                jump_with_vx: false,
+                display_wait: true,
+            },
+            QuirksPreset::Chip48 => Quirks {
+                vf_reset: false,
+                memory_increment: true,
+                clipping: true,
+                shift_in_place: true,
+                jump_with_vx: true,
+                display_wait: false,
+            },
+            QuirksPreset::SchipLegacy => Quirks {
+                vf_reset: false,
+                memory_increment: false,
+                clipping: true,
+                shift_in_place: true,
+                jump_with_vx: true,
+                display_wait: true,
+            },
+            QuirksPreset::SchipModern => Quirks {
+                vf_reset: false,
+                memory_increment: false,
+                clipping: true,
+                shift_in_place: true,
+                jump_with_vx: true,
+                display_wait: false,
+            },
+            QuirksPreset::XoChip => Quirks {
+                vf_reset: false,
+                memory_increment: true,
+                clipping: false,
+                shift_in_place: false,
+                jump_with_vx: false,
+                display_wait: false,
+            },
+        }
+    }
+
+    /// Returns the default quirks for a platform.
+    pub fn for_platform(platform: Platform) -> Self {
+        Quirks::preset(QuirksPreset::for_platform(platform))
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks::for_platform(Platform::default())
     }
 }
 
@@ -463,15 +910,35 @@ pub struct Settings {
     ///
     /// This should point to a valid CHIP-8 ROM file (typically .ch8 extension).
     pub rom: PathBuf,
+
+    /// Platform variant whose instruction set the ROM is written for.
+    pub platform: Platform,
+
+    /// Interpreter quirks applied to instructions whose behaviour varies
+    /// between platforms.
+    pub quirks: Quirks,
+
+    /// Random number generator used by `CXNN`.
+    pub rng: RngKind,
+
+    /// Initial generator state. `None` seeds from the operating system's
+    /// entropy source, so that every run differs.
+    pub seed: Option<u64>,
 }
 
 impl Settings {
     /// Creates a new Settings instance with the specified parameters.
+    ///
+    /// The platform defaults to the original CHIP-8 with COSMAC VIP quirks.
     pub fn new(frame_rate: u64, ips: u64, rom: String) -> Self {
         Settings {
             frame_rate,
             ips,
             rom: rom.into(),
+            platform: Platform::default(),
+            quirks: Quirks::default(),
+            rng: RngKind::default(),
+            seed: None,
         }
     }
 }
@@ -486,7 +953,7 @@ pub struct Chip8State {
     /// Emulator configuration settings (frame rate, instruction speed, ROM path).
     pub settings: Settings,
 
-    /// 4KB memory subsystem containing font data, ROM, and runtime memory.
+    /// 4KB (64KB on XO-CHIP) memory subsystem containing font data, ROM, and runtime memory.
     pub memory: Memory,
 
     /// Bank of 16 general-purpose 8-bit registers (V0-VF).
@@ -507,60 +974,130 @@ pub struct Chip8State {
     /// Sound timer that counts down at 60Hz, triggers audio when non-zero.
     pub sound_timer: Timer,
 
-    /// Display buffer representing the 64×32 monochrome screen.
-    /// Each bit represents one pixel (true = on, false = off).
-    pub display: BitArr!(for DISPLAY_WIDTH * DISPLAY_HEIGHT),
+    /// Display buffer representing the 64×32 (or 128×64 in SUPER-CHIP high
+    /// resolution mode) monochrome screen.
+    pub display: FrameBuffer,
 
     /// Input handling system for the 16-key hexadecimal keypad.
     pub keypad: Keypad,
+
+    /// RPL user flags, saved and restored by `FX75`/`FX85`.
+    /// SUPER-CHIP uses the first 8; XO-CHIP uses all 16.
+    pub rpl_flags: [u8; NUM_REGISTERS],
+
+    /// XO-CHIP audio pattern buffer of 128 1-bit samples, loaded by `F002`.
+    pub audio_pattern: [u8; AUDIO_PATTERN_SIZE],
+
+    /// XO-CHIP audio pitch register, set by `FX3A`.
+    /// The pattern plays back at `4000 * 2^((pitch - 64) / 48)` samples per second.
+    pub pitch: u8,
+
+    /// Random number generator for `CXNN`.
+    pub rng: Box<dyn RandomGenerator>,
+
+    /// Set when the program executes the SUPER-CHIP exit instruction `00FD`.
+    pub halted: bool,
+
+    /// Set when a sprite draw must wait for the next vertical blank because of
+    /// the display wait quirk. The remainder of the frame is skipped.
+    pub awaiting_vblank: bool,
 }
 
 impl Chip8State {
     /// Creates a new CHIP-8 system state with default initialization.
     pub fn new(settings: Settings) -> Self {
+        let memory = Memory::new(settings.platform.memory_size());
+        let rng = settings
+            .rng
+            .create(settings.seed.unwrap_or_else(rand::random));
+
         Chip8State {
             settings,
-            memory: Memory::new(),
+            memory,
             registers: RegisterBank::new(),
             pc: PC_START_ADDR,
             index: 0,
             stack: Vec::new(),
             delay_timer: 0,
             sound_timer: 0,
-            display: BitArray::ZERO,
+            display: FrameBuffer::new(),
             keypad: Keypad::new(),
+            rpl_flags: [0; NUM_REGISTERS],
+            audio_pattern: [0xF0; AUDIO_PATTERN_SIZE],
+            pitch: DEFAULT_PITCH,
+            rng,
+            halted: false,
+            awaiting_vblank: false,
         }
     }
 
+    /// Skips over the instruction at the program counter.
+    ///
+    /// Instructions are normally 2 bytes long, but on XO-CHIP the `F000 NNNN`
+    /// long index load occupies 4 bytes and is skipped as a whole.
+    pub fn skip_next_instruction(&mut self) -> anyhow::Result<()> {
+        let is_long_instruction = self.settings.platform.has_xochip_extensions()
+            && self.memory.read_word(self.pc)? == 0xF000;
+
+        self.pc += if is_long_instruction { 4 } else { 2 };
+        Ok(())
+    }
+
     /// Clears all pixels on the display screen.
     pub fn clear_display(&mut self) {
-        self.display.fill(false);
+        self.display.clear();
     }
 
     /// Draws a sprite on the display and detects pixel collisions.
+    ///
+    /// A height of 0 draws a 16×16 SUPER-CHIP sprite (two bytes per row) on
+    /// platforms that support it; otherwise sprites are 8 pixels wide and
+    /// `sprite_idx` rows tall. When several XO-CHIP planes are selected, the
+    /// sprite data for each plane follows the previous one in memory.
+    ///
+    /// Pixels past the screen edges are clipped, or wrapped around to the
+    /// opposite edge when the clipping quirk is disabled.
     pub fn draw_sprite(&mut self, x: usize, y: usize, sprite_idx: u8) -> anyhow::Result<bool> {
+        let (sprite_width, sprite_bytes) =
+            if sprite_idx == 0 && self.settings.platform.has_schip_extensions() {
+                (16, 32)
+            } else {
+                (8, sprite_idx)
+            };
+        let bytes_per_row = sprite_width / 8;
+        let clipping = self.settings.quirks.clipping;
+        let (display_width, display_height) = (self.display.width(), self.display.height());
         let mut collision = false;
-        let sprite = self.memory.read_sprite(self.index, sprite_idx)?;
 
-        for (row, &byte) in sprite.iter().enumerate() {
-            for bit in 0..8 {
-                let pixel_x = x + bit;
-                let pixel_y = y + row;
+        for (n, plane) in self.display.selected_plane_indices().enumerate() {
+            let sprite_addr = self.index + n * usize::from(sprite_bytes);
+            let sprite = self.memory.read_sprite(sprite_addr, sprite_bytes)?;
 
-                // Skip pixels that are outside screen boundaries
-                if pixel_x >= DISPLAY_WIDTH || pixel_y >= DISPLAY_HEIGHT {
-                    continue;
-                }
+            for (row, row_bytes) in sprite.chunks(bytes_per_row).enumerate() {
+                for bit in 0..sprite_width {
+                    let mut pixel_x = x + bit;
+                    let mut pixel_y = y + row;
 
-                let index = pixel_y * DISPLAY_WIDTH + pixel_x;
-                let current_pixel = self.display[index];
-                let new_pixel = (byte >> (7 - bit)) & 1 == 1;
+                    if clipping {
+                        // Skip pixels that are outside screen boundaries
+                        if pixel_x >= display_width || pixel_y >= display_height {
+                            continue;
+                        }
+                    } else {
+                        pixel_x %= display_width;
+                        pixel_y %= display_height;
+                    }
 
-                if current_pixel && new_pixel {
-                    collision = true;
-                }
+                    let current_pixel = self.display.get_plane(plane, pixel_x, pixel_y);
+                    let new_pixel = (row_bytes[bit / 8] >> (7 - bit % 8)) & 1 == 1;
 
-                self.display.set(index, current_pixel ^ new_pixel);
+                    if current_pixel && new_pixel {
+                        collision = true;
+                    }
+
+                    self.display
+                        .set_plane(plane, pixel_x, pixel_y, current_pixel ^ new_pixel);
+                }
             }
         }
         Ok(collision)
@@ -0,0 +1,307 @@
+//! Colour Themes
+//!
+//! A [`Theme`] colours the game screen and the panels around it: the palette
+//! of pixel colours, indexed by XO-CHIP plane combination, and the borders,
+//! titles and keypad panel text. Themes are picked by [`ThemeName`], and their
+//! colours are reduced to what the terminal can show with [`ColorDepth`].
+
+use ratatui::{
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders},
+};
+
+/// Default terminal colours for each pixel colour index (plane 0 bit | plane 1
+/// bit << 1). Index 0 is the background and is rendered as blank space.
+pub const DEFAULT_PALETTE: [Color; 4] = [
+    Color::Reset,
+    Color::White,
+    Color::LightRed,
+    Color::LightYellow,
+];
+
+/// Colours of the emulator's screen.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    /// Colours of the background, plane 1, plane 2 and both planes.
+    pub palette: [Color; 4],
+    pub border: Color,
+    pub title: Color,
+    /// Text of the keypad panel.
+    pub keypad: Color,
+    /// Colours the theme was reduced to.
+    pub depth: ColorDepth,
+}
+
+/// The built-in themes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ThemeName {
+    /// White pixels on the terminal's own background.
+    #[default]
+    Default,
+
+    /// Amber monochrome monitor.
+    Amber,
+
+    /// Green phosphor monochrome monitor.
+    Green,
+
+    /// Greenish liquid crystal display of early handhelds.
+    Lcd,
+
+    /// The default XO-CHIP colours of the Octo IDE.
+    Octo,
+}
+
+impl Theme {
+    /// Returns a built-in theme.
+    pub fn named(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Default => Theme {
+                palette: DEFAULT_PALETTE,
+                border: Color::White,
+                title: Color::White,
+                keypad: Color::Yellow,
+                depth: ColorDepth::TrueColor,
+            },
+            ThemeName::Amber => Theme {
+                palette: [
+                    Color::Rgb(0x1a, 0x12, 0x00),
+                    Color::Rgb(0xff, 0xb0, 0x00),
+                    Color::Rgb(0x99, 0x66, 0x00),
+                    Color::Rgb(0xff, 0xd8, 0x80),
+                ],
+                border: Color::Rgb(0x99, 0x66, 0x00),
+                title: Color::Rgb(0xff, 0xb0, 0x00),
+                keypad: Color::Rgb(0xff, 0xb0, 0x00),
+                depth: ColorDepth::TrueColor,
+            },
+            ThemeName::Green => Theme {
+                palette: [
+                    Color::Rgb(0x0a, 0x14, 0x0a),
+                    Color::Rgb(0x33, 0xff, 0x66),
+                    Color::Rgb(0x1a, 0x99, 0x33),
+                    Color::Rgb(0xaa, 0xff, 0xbb),
+                ],
+                border: Color::Rgb(0x1a, 0x99, 0x33),
+                title: Color::Rgb(0x33, 0xff, 0x66),
+                keypad: Color::Rgb(0x33, 0xff, 0x66),
+                depth: ColorDepth::TrueColor,
+            },
+            ThemeName::Lcd => Theme {
+                palette: [
+                    Color::Rgb(0x9b, 0xbc, 0x0f),
+                    Color::Rgb(0x0f, 0x38, 0x0f),
+                    Color::Rgb(0x30, 0x62, 0x30),
+                    Color::Rgb(0x8b, 0xac, 0x0f),
+                ],
+                border: Color::Rgb(0x30, 0x62, 0x30),
+                title: Color::Rgb(0x9b, 0xbc, 0x0f),
+                keypad: Color::Rgb(0x8b, 0xac, 0x0f),
+                depth: ColorDepth::TrueColor,
+            },
+            ThemeName::Octo => Theme {
+                palette: [
+                    Color::Rgb(0x99, 0x66, 0x00),
+                    Color::Rgb(0xff, 0xcc, 0x00),
+                    Color::Rgb(0xff, 0x66, 0x00),
+                    Color::Rgb(0x66, 0x22, 0x00),
+                ],
+                border: Color::Rgb(0x99, 0x66, 0x00),
+                title: Color::Rgb(0xff, 0xcc, 0x00),
+                keypad: Color::Rgb(0xff, 0xcc, 0x00),
+                depth: ColorDepth::TrueColor,
+            },
+        }
+    }
+
+    /// A bordered panel with `title`, in the theme's border and title colours.
+    pub fn block<'a>(&self, title: impl Into<Line<'a>>) -> Block<'a> {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.border))
+            .title_style(Style::default().fg(self.title))
+            .title(title)
+    }
+
+    /// Reduces every colour to the nearest one the terminal can show.
+    pub fn reduce(self, depth: ColorDepth) -> Self {
+        Theme {
+            palette: self.palette.map(|color| depth.reduce(color)),
+            border: depth.reduce(self.border),
+            title: depth.reduce(self.title),
+            keypad: depth.reduce(self.keypad),
+            depth,
+        }
+    }
+
+    /// The colour of a pixel of palette colour `index` at `brightness`, from
+    /// 0 for the background to 255 for the full colour, on a ramp between the
+    /// background and the palette colour. The terminal's default background is
+    /// taken as black.
+    pub fn shade(&self, index: u8, brightness: u8) -> Color {
+        let color = self.palette[usize::from(index & 0b11)];
+        if brightness == 255 || index == 0 {
+            return color;
+        }
+        if brightness == 0 {
+            return self.palette[0];
+        }
+        let background = rgb(self.palette[0]).unwrap_or((0, 0, 0));
+        let Some(foreground) = rgb(color) else {
+            return color;
+        };
+        let mix = |back: u8, front: u8| {
+            ((u16::from(back) * u16::from(255 - brightness)
+                + u16::from(front) * u16::from(brightness))
+                / 255) as u8
+        };
+        self.depth.reduce(Color::Rgb(
+            mix(background.0, foreground.0),
+            mix(background.1, foreground.1),
+            mix(background.2, foreground.2),
+        ))
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::named(ThemeName::Default)
+    }
+}
+
+/// The colours a terminal can show.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorDepth {
+    /// 24-bit RGB colours.
+    #[value(name = "truecolor")]
+    TrueColor,
+
+    /// The xterm 256-colour palette.
+    #[value(name = "256")]
+    Ansi256,
+
+    /// The 16 basic ANSI colours.
+    #[value(name = "16")]
+    Ansi16,
+}
+
+/// Typical RGB values of the 16 ANSI colours, as in xterm.
+const ANSI_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Channel levels of the 6×6×6 colour cube at indices 16-231 of the 256-colour
+/// palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    /// Guesses the terminal's colour depth from `COLORTERM` and `TERM`.
+    pub fn detect() -> Self {
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        let term = std::env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+
+    /// Returns the colour nearest to `color` at this depth. Named colours are
+    /// kept as they are.
+    pub fn reduce(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorDepth::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_indexed(r, g, b)),
+            (ColorDepth::Ansi16, Color::Rgb(r, g, b)) => nearest_ansi(r, g, b),
+            (ColorDepth::Ansi16, Color::Indexed(index)) => {
+                let (r, g, b) = indexed_rgb(index);
+                nearest_ansi(r, g, b)
+            }
+            _ => color,
+        }
+    }
+}
+
+/// The RGB value of a colour, using typical values for the named and indexed
+/// colours, or `None` for the terminal's default colour.
+pub fn rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Reset => None,
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        Color::Indexed(index) => Some(indexed_rgb(index)),
+        named => ANSI_COLORS
+            .iter()
+            .find(|&&(color, _)| color == named)
+            .map(|&(_, rgb)| rgb),
+    }
+}
+
+/// RGB values of a palette for image files, with the terminal's default colour
+/// taken as black for the background and white for the planes.
+pub fn palette_rgb(palette: &[Color; 4]) -> [(u8, u8, u8); 4] {
+    let mut rgb_palette = palette.map(|color| rgb(color).unwrap_or((255, 255, 255)));
+    rgb_palette[0] = rgb(palette[0]).unwrap_or((0, 0, 0));
+    rgb_palette
+}
+
+/// Squared distance between two RGB colours.
+pub(crate) fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    [(r1, r2), (g1, g2), (b1, b2)]
+        .iter()
+        .map(|&(a, b)| u32::from(a.abs_diff(b)).pow(2))
+        .sum()
+}
+
+/// The nearest of the 16 ANSI colours.
+fn nearest_ansi(r: u8, g: u8, b: u8) -> Color {
+    ANSI_COLORS
+        .iter()
+        .min_by_key(|&&(_, rgb)| distance(rgb, (r, g, b)))
+        .map(|&(color, _)| color)
+        .unwrap_or(Color::White)
+}
+
+/// Index of the nearest colour in the colour cube or grey ramp of the
+/// 256-colour palette. The first 16 entries are skipped since terminals
+/// customise them.
+fn nearest_indexed(r: u8, g: u8, b: u8) -> u8 {
+    (16..=255)
+        .min_by_key(|&index| distance(indexed_rgb(index), (r, g, b)))
+        .unwrap_or(16)
+}
+
+/// RGB value of a 256-colour palette index.
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_COLORS[usize::from(index)].1,
+        16..=231 => {
+            let cube = index - 16;
+            (
+                CUBE_LEVELS[usize::from(cube / 36)],
+                CUBE_LEVELS[usize::from(cube / 6 % 6)],
+                CUBE_LEVELS[usize::from(cube % 6)],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Disassembles the bundled test ROMs with `chip8 disasm --trace` and checks
+//! that `chip8 asm` turns each listing back into the original bytes, in both
+//! syntaxes and for every platform.
+
+use std::path::Path;
+use std::process::Command;
+
+const PLATFORMS: [&str; 3] = ["chip8", "schip", "xochip"];
+
+/// Round-trips every `tests/*.ch8` ROM through the disassembler and the
+/// assembler in `syntax`.
+fn round_trip(syntax: &str) {
+    let tests = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("asm-{}", syntax));
+    std::fs::create_dir_all(&out).expect("Cannot create output directory");
+    let mut roms: Vec<_> = std::fs::read_dir(&tests)
+        .expect("Cannot list tests")
+        .map(|entry| entry.expect("Cannot list tests").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ch8"))
+        .collect();
+    roms.sort();
+    assert!(!roms.is_empty(), "No test ROMs found");
+
+    for rom in &roms {
+        let original = std::fs::read(rom).expect("Cannot read ROM");
+        let name = rom.file_stem().unwrap().to_string_lossy();
+        for platform in PLATFORMS {
+            let disasm = Command::new(env!("CARGO_BIN_EXE_chip8"))
+                .arg("disasm")
+                .arg(rom)
+                .args(["--trace", "--syntax", syntax, "--platform", platform])
+                .output()
+                .expect("Cannot run chip8 disasm");
+            assert!(
+                disasm.status.success(),
+                "chip8 disasm failed on {} for {}:\n{}",
+                name,
+                platform,
+                String::from_utf8_lossy(&disasm.stderr)
+            );
+            let source = out.join(format!("{}-{}.8o", name, platform));
+            let assembled = source.with_extension("ch8");
+            std::fs::write(&source, &disasm.stdout).expect("Cannot write listing");
+
+            let asm = Command::new(env!("CARGO_BIN_EXE_chip8"))
+                .arg("asm")
+                .arg(&source)
+                .arg("-o")
+                .arg(&assembled)
+                .args(["--platform", platform])
+                .output()
+                .expect("Cannot run chip8 asm");
+            assert!(
+                asm.status.success(),
+                "chip8 asm failed on {}:\n{}",
+                source.display(),
+                String::from_utf8_lossy(&asm.stderr)
+            );
+            let bytes = std::fs::read(&assembled).expect("Cannot read assembled ROM");
+            assert!(
+                bytes == original,
+                "{} does not reassemble to {}",
+                source.display(),
+                rom.display()
+            );
+        }
+    }
+}
+
+#[test]
+fn round_trip_octo() {
+    round_trip("octo");
+}
+
+#[test]
+fn round_trip_cowgod() {
+    round_trip("cowgod");
+}
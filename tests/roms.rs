@@ -0,0 +1,132 @@
+//! Runs the ROMs of Timendus' CHIP-8 test suite headlessly and compares the
+//! final display with the expected output in `tests/expected/`, which shows
+//! every test passing.
+
+use std::path::Path;
+use std::process::Command;
+
+/// Runs `tests/<rom>.ch8` for `frames` frames with extra arguments, asserts
+/// that the display matches `tests/expected/<expected>.txt` and returns what
+/// the run printed on stderr.
+fn run_rom(rom: &str, frames: u64, args: &[&str], expected: &str) -> String {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let output = Command::new(env!("CARGO_BIN_EXE_chip8"))
+        .args(["run", "--headless", "--frames", &frames.to_string()])
+        .arg("--rom-path")
+        .arg(dir.join(format!("{}.ch8", rom)))
+        .arg("--expect")
+        .arg(dir.join("expected").join(format!("{}.txt", expected)))
+        .args(args)
+        // Keep the user's config file out of the tests; the ROM database has
+        // the settings of every test ROM
+        .env("XDG_CONFIG_HOME", env!("CARGO_TARGET_TMPDIR"))
+        .output()
+        .expect("Cannot run chip8");
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    assert!(
+        output.status.success(),
+        "{} does not match {}.txt:\n{}{}",
+        rom,
+        expected,
+        String::from_utf8_lossy(&output.stdout),
+        stderr
+    );
+    stderr
+}
+
+#[test]
+fn chip8_logo() {
+    run_rom("1-chip8-logo", 120, &[], "1-chip8-logo");
+}
+
+#[test]
+fn ibm_logo() {
+    run_rom("2-ibm-logo", 120, &[], "2-ibm-logo");
+}
+
+#[test]
+fn corax_plus() {
+    run_rom("3-corax+", 120, &[], "3-corax+");
+}
+
+#[test]
+fn flags() {
+    run_rom("4-flags", 120, &[], "4-flags");
+}
+
+#[test]
+fn quirks_chip8() {
+    run_rom("5-quirks", 600, &["--press", "1@100-110"], "5-quirks-chip8");
+}
+
+#[test]
+fn quirks_schip_modern() {
+    // Picks SUPER-CHIP, then modern SUPER-CHIP from the second menu
+    run_rom(
+        "5-quirks",
+        1000,
+        &[
+            "--platform",
+            "schip",
+            "--quirks",
+            "schip-modern",
+            "--press",
+            "2@100-110",
+            "--press",
+            "1@300-310",
+        ],
+        "5-quirks-schip-modern",
+    );
+}
+
+#[test]
+fn quirks_schip_legacy() {
+    // Picks SUPER-CHIP, then legacy SUPER-CHIP from the second menu, the
+    // preset the ROM database gives the quirks test on this platform
+    run_rom(
+        "5-quirks",
+        1000,
+        &[
+            "--platform",
+            "schip",
+            "--press",
+            "2@100-110",
+            "--press",
+            "2@300-310",
+        ],
+        "5-quirks-schip-legacy",
+    );
+}
+
+#[test]
+fn quirks_xochip() {
+    run_rom(
+        "5-quirks",
+        600,
+        &["--platform", "xochip", "--press", "3@100-110"],
+        "5-quirks-xochip",
+    );
+}
+
+#[test]
+fn keypad() {
+    // The FX0A test, which must wait for a key to be pressed and released
+    run_rom(
+        "6-keypad",
+        600,
+        &["--press", "3@100-110", "--press", "5@300-310"],
+        "6-keypad",
+    );
+}
+
+#[test]
+fn beep() {
+    // A melody plays for 60 frames until B is pressed, after which the beep
+    // must sound from the frame after the press for as long as B is held
+    let stderr = run_rom("7-beep", 300, &["--press", "b@100-299"], "7-beep");
+    assert!(
+        stderr.contains("sound on for 259 frames"),
+        "unexpected sound: {}",
+        stderr
+    );
+}